@@ -0,0 +1,10 @@
+create table idempotency_keys (
+    key text primary key,
+    fingerprint text not null,
+    status_code smallint,
+    content_type text,
+    body bytea,
+    created_at timestamptz not null default now()
+);
+
+create index idempotency_keys_created_at_index on idempotency_keys using btree(created_at);
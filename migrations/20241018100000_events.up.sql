@@ -0,0 +1,11 @@
+create table events (
+    id bigint generated always as identity primary key,
+    story_id uuid not null,
+    kind text not null,
+    entity_id uuid not null,
+    payload jsonb not null,
+    created_at timestamptz not null default now()
+);
+
+create index events_story_id_id_index on events using btree(story_id, id);
+create index events_created_at_index on events using btree(created_at);
@@ -0,0 +1,29 @@
+create table webhooks (
+    id uuid default gen_random_uuid() primary key,
+    url text not null,
+    secret text not null,
+    events text[] not null default '{}',
+    story_id uuid,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+create table webhook_deliveries (
+    id uuid default gen_random_uuid() primary key,
+    webhook_id uuid references webhooks(id) on delete cascade not null,
+    event_id bigint not null,
+    event_kind text not null,
+    payload jsonb not null,
+    status text not null default 'pending',
+    attempts integer not null default 0,
+    response_status integer,
+    last_error text,
+    next_attempt_at timestamptz not null default now(),
+    seqno bigint generated always as identity,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+create index webhook_deliveries_webhook_id_seqno_index on webhook_deliveries using btree(webhook_id, seqno);
+create index webhook_deliveries_pending_index on webhook_deliveries using btree(next_attempt_at)
+    where status = 'pending';
@@ -0,0 +1,12 @@
+create table activity (
+    id uuid default gen_random_uuid() primary key,
+    story_id uuid not null,
+    actor text not null,
+    kind text not null,
+    entity_id uuid not null,
+    changes jsonb not null,
+    seqno bigint generated always as identity,
+    created_at timestamptz not null default now()
+);
+
+create index activity_story_id_seqno_index on activity using btree(story_id, seqno);
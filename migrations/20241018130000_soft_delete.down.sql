@@ -0,0 +1,7 @@
+drop index if exists story_files_deleted_at_index;
+drop index if exists tasks_deleted_at_index;
+drop index if exists stories_deleted_at_index;
+
+alter table story_files drop column deleted_at;
+alter table tasks drop column deleted_at;
+alter table stories drop column deleted_at;
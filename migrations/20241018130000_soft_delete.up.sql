@@ -0,0 +1,7 @@
+alter table stories add column deleted_at timestamptz;
+alter table tasks add column deleted_at timestamptz;
+alter table story_files add column deleted_at timestamptz;
+
+create index stories_deleted_at_index on stories using btree(deleted_at) where deleted_at is not null;
+create index tasks_deleted_at_index on tasks using btree(deleted_at) where deleted_at is not null;
+create index story_files_deleted_at_index on story_files using btree(deleted_at) where deleted_at is not null;
@@ -0,0 +1,3 @@
+drop index if exists tasks_story_id_seqno_index;
+
+alter table tasks drop column seqno;
@@ -0,0 +1,3 @@
+alter table tasks add column seqno bigint generated always as identity;
+
+create index tasks_story_id_seqno_index on tasks using btree(story_id, seqno);
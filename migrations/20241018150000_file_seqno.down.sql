@@ -0,0 +1,3 @@
+drop index if exists story_files_story_id_seqno_index;
+
+alter table story_files drop column seqno;
@@ -0,0 +1,3 @@
+alter table story_files add column seqno bigint generated always as identity;
+
+create index story_files_story_id_seqno_index on story_files using btree(story_id, seqno);
@@ -0,0 +1,3 @@
+drop index if exists stories_updated_at_seqno_index;
+drop index if exists stories_created_at_seqno_index;
+drop index if exists stories_name_seqno_index;
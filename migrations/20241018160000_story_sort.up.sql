@@ -0,0 +1,3 @@
+create index stories_name_seqno_index on stories using btree(name, seqno) where deleted_at is null;
+create index stories_created_at_seqno_index on stories using btree(created_at, seqno) where deleted_at is null;
+create index stories_updated_at_seqno_index on stories using btree(updated_at, seqno) where deleted_at is null;
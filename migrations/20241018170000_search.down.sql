@@ -0,0 +1,7 @@
+drop index if exists story_files_search_index;
+drop index if exists tasks_search_index;
+drop index if exists stories_search_index;
+
+alter table story_files drop column search;
+alter table tasks drop column search;
+alter table stories drop column search;
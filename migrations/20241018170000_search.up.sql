@@ -0,0 +1,9 @@
+alter table stories add column search tsvector generated always as (to_tsvector('english', name)) stored;
+alter table tasks add column search tsvector generated always as (to_tsvector('english', name)) stored;
+-- Split file names on separators, so "invoice.pdf" matches a search for "invoice"
+alter table story_files add column search tsvector
+    generated always as (to_tsvector('english', translate(name, '._-', '   '))) stored;
+
+create index stories_search_index on stories using gin(search);
+create index tasks_search_index on tasks using gin(search);
+create index story_files_search_index on story_files using gin(search);
@@ -0,0 +1,13 @@
+-- Tasks are ranked within a story by position, with gaps left for moves.
+alter table tasks add column position bigint;
+
+update tasks set position = ranked.position
+from (
+    select id, row_number() over (partition by story_id order by seqno) * 65536 as position
+    from tasks
+) ranked
+where tasks.id = ranked.id;
+
+alter table tasks alter column position set not null;
+
+create index tasks_story_id_position_seqno_index on tasks using btree(story_id, position, seqno);
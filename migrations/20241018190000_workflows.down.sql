@@ -0,0 +1,2 @@
+drop table if exists workflow_transitions;
+drop table if exists workflow_states;
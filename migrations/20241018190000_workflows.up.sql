@@ -0,0 +1,16 @@
+create table workflow_states (
+    story_id uuid references stories(id) on delete cascade not null,
+    name text not null,
+    closed boolean not null,
+    position int not null,
+    primary key (story_id, name)
+);
+
+create table workflow_transitions (
+    story_id uuid not null,
+    from_state text not null,
+    to_state text not null,
+    primary key (story_id, from_state, to_state),
+    foreign key (story_id, from_state) references workflow_states(story_id, name) on delete cascade,
+    foreign key (story_id, to_state) references workflow_states(story_id, name) on delete cascade
+);
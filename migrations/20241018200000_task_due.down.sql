@@ -0,0 +1,6 @@
+drop function if exists task_is_closed(uuid, text);
+
+drop index if exists tasks_due_at_index;
+
+alter table tasks drop column reminded_at;
+alter table tasks drop column due_at;
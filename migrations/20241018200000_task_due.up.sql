@@ -0,0 +1,15 @@
+alter table tasks add column due_at timestamptz;
+alter table tasks add column reminded_at timestamptz;
+
+create index tasks_due_at_index on tasks using btree(due_at)
+    where due_at is not null and reminded_at is null and deleted_at is null;
+
+-- Whether a status is closed in a story's workflow, or in the default workflow for stories
+-- without one.
+create function task_is_closed(task_story_id uuid, task_status text) returns boolean
+language sql stable as $$
+    select coalesce(
+        (select closed from workflow_states where story_id = task_story_id and name = task_status),
+        task_status = 'complete'
+    )
+$$;
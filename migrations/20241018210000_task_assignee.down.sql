@@ -0,0 +1,3 @@
+drop index if exists tasks_assignee_due_at_seqno_index;
+
+alter table tasks drop column assignee;
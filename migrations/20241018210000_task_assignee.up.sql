@@ -0,0 +1,6 @@
+-- The user a task is assigned to, if any.
+alter table tasks add column assignee text;
+
+create index tasks_assignee_due_at_seqno_index
+    on tasks using btree(assignee, coalesce(due_at, 'infinity'), seqno)
+    where assignee is not null and deleted_at is null;
@@ -0,0 +1,3 @@
+drop table if exists task_labels;
+drop table if exists story_labels;
+drop table if exists labels;
@@ -0,0 +1,26 @@
+create table labels (
+    id uuid default gen_random_uuid() primary key,
+    name text not null,
+    color text not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+-- Label names are unique, ignoring case.
+create unique index labels_lower_name_index on labels using btree(lower(name));
+
+create table story_labels (
+    story_id uuid references stories(id) on delete cascade not null,
+    label_id uuid references labels(id) on delete cascade not null,
+    primary key (story_id, label_id)
+);
+
+create index story_labels_label_id_index on story_labels using btree(label_id);
+
+create table task_labels (
+    task_id uuid references tasks(id) on delete cascade not null,
+    label_id uuid references labels(id) on delete cascade not null,
+    primary key (task_id, label_id)
+);
+
+create index task_labels_label_id_index on task_labels using btree(label_id);
@@ -0,0 +1,6 @@
+drop function if exists task_closed_subtasks(uuid);
+drop function if exists task_subtasks(uuid);
+
+drop index if exists tasks_parent_task_id_index;
+
+alter table tasks drop column parent_task_id;
@@ -0,0 +1,17 @@
+alter table tasks add column parent_task_id uuid references tasks(id) on delete cascade;
+
+create index tasks_parent_task_id_index on tasks using btree(parent_task_id)
+    where parent_task_id is not null;
+
+-- The number of live subtasks of a task.
+create function task_subtasks(task_id uuid) returns bigint
+language sql stable as $$
+    select count(*) from tasks where parent_task_id = task_id and deleted_at is null
+$$;
+
+-- The number of live subtasks of a task that are in closed workflow states.
+create function task_closed_subtasks(task_id uuid) returns bigint
+language sql stable as $$
+    select count(*) from tasks
+    where parent_task_id = task_id and deleted_at is null and task_is_closed(story_id, status)
+$$;
@@ -0,0 +1,3 @@
+drop index if exists task_dependencies_depends_on_id_index;
+
+drop table if exists task_dependencies;
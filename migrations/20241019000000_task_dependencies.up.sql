@@ -0,0 +1,10 @@
+-- A task depends on (is blocked by) another task, possibly in another story.
+create table task_dependencies (
+    task_id uuid references tasks(id) on delete cascade not null,
+    depends_on_id uuid references tasks(id) on delete cascade not null,
+    created_at timestamptz not null default now(),
+    primary key (task_id, depends_on_id),
+    check (task_id <> depends_on_id)
+);
+
+create index task_dependencies_depends_on_id_index on task_dependencies using btree(depends_on_id);
@@ -0,0 +1,2 @@
+-- A recurrence rule, like FREQ=WEEKLY;INTERVAL=2, carried forward to each next occurrence.
+alter table tasks add column recurrence text;
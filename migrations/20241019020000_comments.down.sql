@@ -0,0 +1,9 @@
+drop index if exists comment_revisions_comment_id_seqno_index;
+
+drop table if exists comment_revisions;
+
+drop index if exists comments_reply_to_id_index;
+drop index if exists comments_task_id_seqno_index;
+drop index if exists comments_story_id_seqno_index;
+
+drop table if exists comments;
@@ -0,0 +1,35 @@
+-- Comments on a story, or on one of its tasks when task_id is set. Replies point at the comment
+-- they answer, on the same story or task.
+create table comments (
+    id uuid default gen_random_uuid() primary key,
+    story_id uuid references stories(id) on delete cascade not null,
+    task_id uuid references tasks(id) on delete cascade,
+    reply_to_id uuid references comments(id) on delete cascade,
+    author text not null,
+    body text not null,
+    seqno bigint generated always as identity,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+create index comments_story_id_seqno_index on comments using btree(story_id, seqno)
+    where task_id is null;
+
+create index comments_task_id_seqno_index on comments using btree(task_id, seqno)
+    where task_id is not null;
+
+create index comments_reply_to_id_index on comments using btree(reply_to_id)
+    where reply_to_id is not null;
+
+-- The bodies comments had before each edit.
+create table comment_revisions (
+    id uuid default gen_random_uuid() primary key,
+    comment_id uuid references comments(id) on delete cascade not null,
+    body text not null,
+    editor text not null,
+    seqno bigint generated always as identity,
+    created_at timestamptz not null default now()
+);
+
+create index comment_revisions_comment_id_seqno_index on comment_revisions
+    using btree(comment_id, seqno);
@@ -0,0 +1,2 @@
+alter table stories drop column metadata;
+alter table stories drop column description;
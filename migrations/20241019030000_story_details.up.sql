@@ -0,0 +1,4 @@
+-- Long form markdown, rendered to sanitized html on request.
+alter table stories add column description text;
+-- Custom key/value pairs, checked against the configured metadata schema.
+alter table stories add column metadata jsonb not null default '{}';
@@ -0,0 +1 @@
+drop index activity_entity_id_index;
@@ -0,0 +1,2 @@
+-- Reports look up the status history of tasks by id.
+create index activity_entity_id_index on activity using btree(entity_id);
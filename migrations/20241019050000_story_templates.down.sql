@@ -0,0 +1,4 @@
+drop index story_files_storage_id_index;
+drop table template_files;
+drop table template_tasks;
+drop table story_templates;
@@ -0,0 +1,38 @@
+-- A reusable outline of a story, saved from an existing story.
+create table story_templates (
+    id uuid default gen_random_uuid() primary key,
+    name text not null,
+    story_name text not null,
+    description text,
+    metadata jsonb not null default '{}',
+    seqno bigint generated always as identity,
+    created_at timestamptz not null default now()
+);
+
+-- Template tasks keep their order and nesting. Tasks made from them start in the first state of
+-- the new story's workflow.
+create table template_tasks (
+    id uuid primary key,
+    template_id uuid references story_templates(id) on delete cascade not null,
+    parent_id uuid references template_tasks(id) on delete cascade,
+    name text not null,
+    position bigint not null
+);
+
+create index template_tasks_template_id_index on template_tasks using btree(template_id);
+
+-- Template files share stored contents with the files they were saved from.
+create table template_files (
+    id uuid default gen_random_uuid() primary key,
+    template_id uuid references story_templates(id) on delete cascade not null,
+    storage_id uuid not null,
+    name text not null,
+    size bigint not null,
+    content_type text not null
+);
+
+create index template_files_template_id_index on template_files using btree(template_id);
+create index template_files_storage_id_index on template_files using btree(storage_id);
+
+-- Cloned files share stored contents too, so contents are only purged once unreferenced.
+create index story_files_storage_id_index on story_files using btree(storage_id);
@@ -0,0 +1 @@
+alter table webhooks drop constraint webhooks_story_id_fkey;
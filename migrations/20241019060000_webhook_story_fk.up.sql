@@ -0,0 +1,5 @@
+-- Webhooks scoped to a story are deleted with it.
+delete from webhooks w where story_id is not null
+    and not exists (select 1 from stories s where s.id = w.story_id);
+alter table webhooks add constraint webhooks_story_id_fkey
+    foreign key (story_id) references stories(id) on delete cascade;
@@ -0,0 +1,3 @@
+-- Each claim on a key gets a token, so only the request holding it can store or release it.
+alter table idempotency_keys add column claim uuid not null default gen_random_uuid();
+alter table idempotency_keys alter column claim drop default;
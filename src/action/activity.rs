@@ -0,0 +1,20 @@
+use crate::{api::Ctx, domain::Activity, Result};
+use futures_util::TryFutureExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Fetch a page of activity for a story
+pub struct GetActivity;
+impl GetActivity {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<Activity>)> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.list_activity(s.id, cursor, limit))
+            .await
+    }
+}
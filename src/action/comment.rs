@@ -0,0 +1,123 @@
+use crate::{
+    api::Ctx,
+    domain::{Comment, CommentRevision},
+    Result,
+};
+use futures_util::TryFutureExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Fetch a comment
+pub struct GetComment;
+impl GetComment {
+    pub async fn execute(ctx: Arc<Ctx>, comment_id: Uuid) -> Result<Comment> {
+        ctx.repo.fetch_comment(comment_id).await
+    }
+}
+
+/// Fetch a page of the comments on a story
+pub struct GetStoryComments;
+impl GetStoryComments {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<Comment>)> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.list_comments(s.id, None, cursor, limit))
+            .await
+    }
+}
+
+/// Fetch a page of the comments on a task
+pub struct GetTaskComments;
+impl GetTaskComments {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        task_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<Comment>)> {
+        ctx.repo
+            .fetch_task(task_id)
+            .and_then(|t| {
+                ctx.repo
+                    .list_comments(t.story_id, Some(t.id), cursor, limit)
+            })
+            .await
+    }
+}
+
+/// Comment on a story
+pub struct CreateStoryComment;
+impl CreateStoryComment {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        author: &str,
+        story_id: Uuid,
+        reply_to_id: Option<Uuid>,
+        body: String,
+    ) -> Result<Comment> {
+        ctx.repo
+            .create_comment(author, story_id, reply_to_id, body)
+            .await
+    }
+}
+
+/// Comment on a task
+pub struct CreateTaskComment;
+impl CreateTaskComment {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        author: &str,
+        task_id: Uuid,
+        reply_to_id: Option<Uuid>,
+        body: String,
+    ) -> Result<Comment> {
+        ctx.repo
+            .create_task_comment(author, task_id, reply_to_id, body)
+            .await
+    }
+}
+
+/// Edit the body of a comment
+pub struct UpdateComment;
+impl UpdateComment {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        comment_id: Uuid,
+        body: String,
+    ) -> Result<Comment> {
+        ctx.repo.update_comment(actor, comment_id, body).await
+    }
+}
+
+/// Fetch a page of the edit history of a comment
+pub struct GetCommentRevisions;
+impl GetCommentRevisions {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        comment_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<CommentRevision>)> {
+        ctx.repo
+            .fetch_comment(comment_id)
+            .and_then(|c| ctx.repo.list_comment_revisions(c.id, cursor, limit))
+            .await
+    }
+}
+
+/// Delete a comment and its replies
+pub struct DeleteComment;
+impl DeleteComment {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, comment_id: Uuid) -> Result<()> {
+        ctx.repo
+            .fetch_comment(comment_id)
+            .and_then(|c| ctx.repo.delete_comment(actor, c.id))
+            .await
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    api::Ctx,
+    domain::{Task, TaskDependencies},
+    Result,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Fetch the tasks a task depends on, and the tasks that depend on it
+pub struct GetDependencies;
+impl GetDependencies {
+    pub async fn execute(ctx: Arc<Ctx>, task_id: Uuid) -> Result<TaskDependencies> {
+        let task = ctx.repo.fetch_task(task_id).await?;
+        let blocked_by = ctx.repo.list_blockers(task.id).await?;
+        let blocking = ctx.repo.list_dependents(task.id).await?;
+        Ok(TaskDependencies {
+            blocked_by,
+            blocking,
+        })
+    }
+}
+
+/// Make a task depend on another task
+pub struct AddDependency;
+impl AddDependency {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        task_id: Uuid,
+        depends_on_id: Uuid,
+    ) -> Result<()> {
+        let task = ctx.repo.fetch_task(task_id).await?;
+        let depends_on = ctx.repo.fetch_task(depends_on_id).await?;
+        ctx.repo.add_dependency(actor, &task, &depends_on).await
+    }
+}
+
+/// Stop a task depending on another task
+pub struct RemoveDependency;
+impl RemoveDependency {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        task_id: Uuid,
+        depends_on_id: Uuid,
+    ) -> Result<()> {
+        let task = ctx.repo.fetch_task(task_id).await?;
+        let depends_on = ctx.repo.fetch_task(depends_on_id).await?;
+        ctx.repo.remove_dependency(actor, &task, &depends_on).await
+    }
+}
+
+/// Fetch the tasks of a story, each after the tasks it depends on
+pub struct GetTasksByDependencies;
+impl GetTasksByDependencies {
+    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<Vec<Task>> {
+        let story = ctx.repo.fetch_story(story_id).await?;
+        ctx.repo.list_tasks_by_dependencies(story.id).await
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{api::Ctx, domain::Event, Result};
+use futures_util::{
+    future::{self, Future},
+    stream::{self, BoxStream, Stream, StreamExt},
+};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+// The number of missed events loaded at a time when replaying on reconnect.
+const REPLAY_PAGE_SIZE: i32 = 1000;
+
+/// Stream events for a story, replaying any events that occurred after the last event id a
+/// client has seen.
+///
+/// Replay goes by event id, which is allocated when an event is recorded rather than when its
+/// transaction commits. So an event committed after a client saw a later one isn't replayed,
+/// unless it's still buffered for live delivery.
+pub struct StreamEvents;
+impl StreamEvents {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Uuid,
+        last_event_id: Option<i64>,
+    ) -> Result<BoxStream<'static, Arc<Event>>> {
+        ctx.repo.fetch_story(story_id).await?;
+
+        // Subscribe before reading missed events so nothing is lost in between
+        let receiver = ctx.events.subscribe();
+        let replayed = Arc::new(Mutex::new(HashSet::new()));
+        let missed = match last_event_id {
+            Some(event_id) => {
+                let repo = Arc::clone(&ctx.repo);
+                let fetch = move |after| {
+                    let repo = Arc::clone(&repo);
+                    async move { repo.list_events(story_id, after, REPLAY_PAGE_SIZE).await }
+                };
+                let replayed = Arc::clone(&replayed);
+                replay(event_id, REPLAY_PAGE_SIZE, fetch)
+                    .map(move |event| {
+                        if let Ok(mut replayed) = replayed.lock() {
+                            replayed.insert(event.id);
+                        }
+                        Arc::new(event)
+                    })
+                    .boxed()
+            }
+            None => stream::empty().boxed(),
+        };
+
+        // Live events end the stream when a subscriber lags, so the client reconnects and catches
+        // up using the last event id.
+        let live = stream::unfold(receiver, |mut receiver| async move {
+            match receiver.recv().await {
+                Ok(event) => Some((event, receiver)),
+                Err(RecvError::Lagged(count)) => {
+                    tracing::warn!("event subscriber lagged by {} events", count);
+                    None
+                }
+                Err(RecvError::Closed) => None,
+            }
+        })
+        .filter(move |e| {
+            let replayed = replayed.lock().is_ok_and(|r| r.contains(&e.id));
+            future::ready(e.story_id == story_id && !replayed)
+        });
+
+        Ok(missed.chain(live).boxed())
+    }
+}
+
+/// Stream the events after an event id a page at a time, until a page comes back short. The
+/// stream ends early when a page can't be loaded, so the client reconnects to resume.
+fn replay<F, Fut>(after: i64, limit: i32, fetch: F) -> impl Stream<Item = Event>
+where
+    F: FnMut(i64) -> Fut,
+    Fut: Future<Output = Result<Vec<Event>>>,
+{
+    stream::unfold((Some(after), fetch), move |(after, mut fetch)| async move {
+        let after = after?;
+        match fetch(after).await {
+            Ok(events) => {
+                let next = match events.last() {
+                    Some(last) if events.len() == limit as usize => Some(last.id),
+                    _ => None,
+                };
+                Some((stream::iter(events), (next, fetch)))
+            }
+            Err(err) => {
+                tracing::error!("unable to replay events after {}: {}", after, err);
+                None
+            }
+        }
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use chrono::Utc;
+
+    fn events(ids: impl Iterator<Item = i64>) -> Vec<Event> {
+        ids.map(|id| Event {
+            id,
+            story_id: Uuid::nil(),
+            kind: "task_created".into(),
+            entity_id: Uuid::nil(),
+            payload: serde_json::Value::Null,
+            created_at: Utc::now(),
+        })
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn replay_all_pages() {
+        let backlog = events(1..=28);
+        let mut fetches = 0;
+        let fetch = |after| {
+            fetches += 1;
+            let page = backlog.iter().filter(|e| e.id > after).take(10).cloned();
+            future::ready(Ok(page.collect()))
+        };
+        let replayed: Vec<_> = replay(5, 10, fetch).map(|e| e.id).collect().await;
+        assert_eq!(replayed, (6..=28).collect::<Vec<_>>());
+        assert_eq!(fetches, 3);
+    }
+
+    #[tokio::test]
+    async fn replay_until_a_page_fails() {
+        let fetch = |after| {
+            future::ready(match after {
+                0 => Ok(events(1..=10)),
+                _ => Err(Error::internal("unavailable".into())),
+            })
+        };
+        let replayed: Vec<_> = replay(0, 10, fetch).map(|e| e.id).collect().await;
+        assert_eq!(replayed, (1..=10).collect::<Vec<_>>());
+    }
+}
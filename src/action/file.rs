@@ -1,4 +1,8 @@
-use crate::{api::Ctx, domain::StoryFile, Error, Result};
+use crate::{
+    api::Ctx,
+    domain::{FileSort, Keyset, SortOrder, StoryFile},
+    Error, Result,
+};
 use axum::extract::Multipart;
 use futures_util::TryFutureExt;
 use std::sync::Arc;
@@ -13,6 +17,7 @@ pub struct AddFiles;
 impl AddFiles {
     pub async fn execute(
         ctx: Arc<Ctx>,
+        actor: &str,
         story_id: Uuid,
         mut multipart: Multipart,
     ) -> Result<Vec<StoryFile>> {
@@ -27,7 +32,7 @@ impl AddFiles {
                 let size = bytes.len() as i64;
                 let file = ctx
                     .repo
-                    .create_file(story_id, storage_id, file_name, size, content_type)
+                    .create_file(actor, story_id, storage_id, file_name, size, content_type)
                     .await?;
                 files.push(file);
             }
@@ -62,25 +67,15 @@ impl DownloadFile {
     }
 }
 
-/// Delete file metadata, and purge contents from storage.
+/// Move a file to the trash. Contents are purged from storage with the trash.
 pub struct DeleteFile;
 impl DeleteFile {
-    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid, file_id: Uuid) -> Result<()> {
-        // Delete file metadata
-        let file = ctx
-            .repo
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, story_id: Uuid, file_id: Uuid) -> Result<()> {
+        ctx.repo
             .fetch_story(story_id)
             .and_then(|story| ctx.repo.fetch_file(story.id, file_id))
-            .and_then(|file| ctx.repo.delete_file(file))
+            .and_then(|file| ctx.repo.delete_file(actor, file))
             .await?;
-        // Try to delete the file from storage, but only log error on failure
-        if let Err(err) = ctx.storage.delete(file.storage_id).await {
-            tracing::error!(
-                "unable to delete file {} from storage: {}",
-                file.storage_id,
-                err
-            );
-        }
         Ok(())
     }
 }
@@ -98,15 +93,20 @@ impl GetFile {
     }
 }
 
-/// Fetch all files (metadata) for a story.
+/// Fetch a sorted page of files (metadata) for a story.
 pub struct GetFiles;
 impl GetFiles {
-    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<Vec<StoryFile>> {
-        let files = ctx
-            .repo
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Uuid,
+        sort: FileSort,
+        order: SortOrder,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<StoryFile>)> {
+        ctx.repo
             .fetch_story(story_id)
-            .and_then(|s| ctx.repo.list_files(s.id))
-            .await?;
-        Ok(files)
+            .and_then(|s| ctx.repo.list_files(s.id, sort, order, after, limit))
+            .await
     }
 }
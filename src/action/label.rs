@@ -0,0 +1,121 @@
+use crate::{api::Ctx, domain::Label, Result};
+use futures_util::TryFutureExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Create a label
+pub struct CreateLabel;
+impl CreateLabel {
+    pub async fn execute(ctx: Arc<Ctx>, name: String, color: String) -> Result<Label> {
+        ctx.repo.create_label(name, color).await
+    }
+}
+
+/// Fetch a label
+pub struct GetLabel;
+impl GetLabel {
+    pub async fn execute(ctx: Arc<Ctx>, label_id: Uuid) -> Result<Label> {
+        ctx.repo.fetch_label(label_id).await
+    }
+}
+
+/// Fetch all labels
+pub struct GetLabels;
+impl GetLabels {
+    pub async fn execute(ctx: Arc<Ctx>) -> Result<Vec<Label>> {
+        ctx.repo.list_labels().await
+    }
+}
+
+/// Update a label
+pub struct UpdateLabel;
+impl UpdateLabel {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        label_id: Uuid,
+        name: String,
+        color: String,
+    ) -> Result<Label> {
+        ctx.repo
+            .fetch_label(label_id)
+            .and_then(|l| ctx.repo.update_label(l.id, name, color))
+            .await
+    }
+}
+
+/// Delete a label
+pub struct DeleteLabel;
+impl DeleteLabel {
+    pub async fn execute(ctx: Arc<Ctx>, label_id: Uuid) -> Result<()> {
+        ctx.repo
+            .fetch_label(label_id)
+            .and_then(|l| ctx.repo.delete_label(l.id))
+            .await
+    }
+}
+
+/// Fetch the labels of a story
+pub struct GetStoryLabels;
+impl GetStoryLabels {
+    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<Vec<Label>> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.list_story_labels(s.id))
+            .await
+    }
+}
+
+/// Add a label to a story
+pub struct AddStoryLabel;
+impl AddStoryLabel {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, story_id: Uuid, label_id: Uuid) -> Result<()> {
+        let story = ctx.repo.fetch_story(story_id).await?;
+        let label = ctx.repo.fetch_label(label_id).await?;
+        ctx.repo.add_story_label(actor, story.id, &label).await
+    }
+}
+
+/// Remove a label from a story
+pub struct RemoveStoryLabel;
+impl RemoveStoryLabel {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, story_id: Uuid, label_id: Uuid) -> Result<()> {
+        let story = ctx.repo.fetch_story(story_id).await?;
+        let label = ctx.repo.fetch_label(label_id).await?;
+        ctx.repo.remove_story_label(actor, story.id, &label).await
+    }
+}
+
+/// Fetch the labels of a task
+pub struct GetTaskLabels;
+impl GetTaskLabels {
+    pub async fn execute(ctx: Arc<Ctx>, task_id: Uuid) -> Result<Vec<Label>> {
+        ctx.repo
+            .fetch_task(task_id)
+            .and_then(|t| ctx.repo.list_task_labels(t.id))
+            .await
+    }
+}
+
+/// Add a label to a task
+pub struct AddTaskLabel;
+impl AddTaskLabel {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, task_id: Uuid, label_id: Uuid) -> Result<()> {
+        let task = ctx.repo.fetch_task(task_id).await?;
+        let label = ctx.repo.fetch_label(label_id).await?;
+        ctx.repo
+            .add_task_label(actor, task.story_id, task.id, &label)
+            .await
+    }
+}
+
+/// Remove a label from a task
+pub struct RemoveTaskLabel;
+impl RemoveTaskLabel {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, task_id: Uuid, label_id: Uuid) -> Result<()> {
+        let task = ctx.repo.fetch_task(task_id).await?;
+        let label = ctx.repo.fetch_label(label_id).await?;
+        ctx.repo
+            .remove_task_label(actor, task.story_id, task.id, &label)
+            .await
+    }
+}
@@ -1,3 +1,14 @@
+pub mod activity;
+pub mod comment;
+pub mod dependency;
+pub mod event;
 pub mod file;
+pub mod label;
+pub mod report;
+pub mod search;
 pub mod story;
 pub mod task;
+pub mod template;
+pub mod trash;
+pub mod webhook;
+pub mod workflow;
@@ -0,0 +1,40 @@
+use crate::{
+    api::Ctx,
+    domain::{Burndown, CycleTime},
+    Result,
+};
+use chrono::NaiveDate;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Count tasks created, completed, and left open each day, for a story or all stories
+pub struct GetBurndown;
+impl GetBurndown {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Burndown> {
+        if let Some(story_id) = story_id {
+            ctx.repo.fetch_story(story_id).await?;
+        }
+        ctx.repo.burndown(story_id, from, to).await
+    }
+}
+
+/// Get percentiles of the time taken to close tasks, for a story or all stories
+pub struct GetCycleTime;
+impl GetCycleTime {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<CycleTime> {
+        if let Some(story_id) = story_id {
+            ctx.repo.fetch_story(story_id).await?;
+        }
+        ctx.repo.cycle_time(story_id, from, to).await
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{api::Ctx, domain::SearchHit, Result};
+use std::sync::Arc;
+
+/// Search story, task, and file names
+pub struct Search;
+impl Search {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        q: &str,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<SearchHit>)> {
+        ctx.repo.search(q, cursor, limit).await
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{api::Ctx, domain::Story, Result};
+use crate::{
+    api::Ctx,
+    domain::{
+        render_markdown, Seek, SortOrder, Story, StoryChanges, StoryFilter, StorySort, StorySummary,
+    },
+    Error, Result,
+};
 use futures_util::TryFutureExt;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -11,59 +17,121 @@ impl GetStory {
     }
 }
 
-/// Fetch a page of stories
+/// Total up the tasks and files of a story
+pub struct GetStorySummary;
+impl GetStorySummary {
+    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<StorySummary> {
+        let story = ctx.repo.fetch_story(story_id).await?;
+        let summaries = ctx.repo.list_story_summaries(&[story.id]).await?;
+        summaries
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::internal(format!("story summary not found: {story_id}")))
+    }
+}
+
+/// Total up the tasks and files of a page of stories
+pub struct GetStorySummaries;
+impl GetStorySummaries {
+    pub async fn execute(ctx: Arc<Ctx>, stories: &[Story]) -> Result<Vec<StorySummary>> {
+        let story_ids: Vec<Uuid> = stories.iter().map(|s| s.id).collect();
+        ctx.repo.list_story_summaries(&story_ids).await
+    }
+}
+
+/// Render a story description as sanitized html
+pub struct RenderStoryDescription;
+impl RenderStoryDescription {
+    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<String> {
+        let story = ctx.repo.fetch_story(story_id).await?;
+        let html = story
+            .description
+            .as_deref()
+            .map(render_markdown)
+            .unwrap_or_default();
+        Ok(html)
+    }
+}
+
+/// Fetch a page of filtered, sorted stories
 pub struct GetStories;
 impl GetStories {
-    pub async fn execute(ctx: Arc<Ctx>, cursor: i64, limit: i32) -> Result<(i64, Vec<Story>)> {
-        ctx.repo.list_stories(cursor, limit).await
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        filter: &StoryFilter,
+        sort: StorySort,
+        order: SortOrder,
+        seek: &Seek,
+        limit: i32,
+    ) -> Result<(Vec<Story>, bool)> {
+        ctx.repo
+            .list_stories(filter, sort, order, seek, limit)
+            .await
+    }
+}
+
+/// Count filtered stories
+pub struct CountStories;
+impl CountStories {
+    pub async fn execute(ctx: Arc<Ctx>, filter: &StoryFilter) -> Result<(i64, bool)> {
+        ctx.repo.count_stories(filter).await
     }
 }
 
 /// Create a story
 pub struct CreateStory;
 impl CreateStory {
-    pub async fn execute(ctx: Arc<Ctx>, name: String) -> Result<Story> {
-        ctx.repo.create_story(name).await
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, changes: StoryChanges) -> Result<Story> {
+        let description = changes.description.flatten();
+        let metadata = changes.metadata.unwrap_or_default();
+        ctx.repo
+            .create_story(actor, changes.name, description, metadata)
+            .await
     }
 }
 
 /// Update a story
 pub struct UpdateStory;
 impl UpdateStory {
-    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid, name: String) -> Result<Story> {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        story_id: Uuid,
+        changes: StoryChanges,
+    ) -> Result<Story> {
         let story = ctx
             .repo
             .fetch_story(story_id)
-            .and_then(|s| ctx.repo.update_story(s.id, name))
+            .and_then(|s| ctx.repo.update_story(actor, s.id, changes))
             .await?;
         Ok(story)
     }
 }
 
-/// Delete a story
+/// Move a story to the trash
 pub struct DeleteStory;
 impl DeleteStory {
-    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<()> {
-        // Ensure story exists
-        ctx.repo.fetch_story(story_id).await?;
-
-        // Gather all storage references
-        let mut storage_ids = Vec::new();
-        if let Ok(files) = ctx.repo.list_files(story_id).await {
-            storage_ids.extend(files.into_iter().map(|f| f.storage_id));
-        }
-
-        // Delete all story metadata
-        ctx.repo.delete_story(story_id).await?;
-
-        // Delete file contents from storage only after metadata deletion succeeds
-        for storage_id in storage_ids {
-            // Don't fail action, just log the error
-            if let Err(err) = ctx.storage.delete(storage_id).await {
-                tracing::error!("unable to delete {} from storage: {}", storage_id, err);
-            }
-        }
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, story_id: Uuid) -> Result<()> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.delete_story(actor, s.id))
+            .await
+    }
+}
 
-        Ok(())
+/// Copy a story, its workflow, labels, tasks and optionally files
+pub struct CloneStory;
+impl CloneStory {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        story_id: Uuid,
+        name: Option<String>,
+        include_files: bool,
+    ) -> Result<Story> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.clone_story(actor, s.id, name, include_files))
+            .await
     }
 }
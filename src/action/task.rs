@@ -1,8 +1,9 @@
 use crate::{
     api::Ctx,
-    domain::{Status, Task},
-    Result,
+    domain::{Keyset, Placement, Recurrence, Task, TaskChanges, TaskFilter},
+    Error, Result,
 };
+use chrono::{DateTime, Utc};
 use futures_util::TryFutureExt;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -15,72 +16,183 @@ impl GetTask {
     }
 }
 
-/// Get all tasks for a story.
+/// Get a page of tasks for a story.
 pub struct GetTasks;
 impl GetTasks {
     pub async fn execute(
         ctx: Arc<Ctx>,
         story_id: Uuid,
-        status: Option<Status>,
-    ) -> Result<Vec<Task>> {
-        let mut tasks = ctx
-            .repo
-            .fetch_story(story_id)
-            .and_then(|s| ctx.repo.list_tasks(s.id))
-            .await?;
-        if let Some(status) = status {
-            let status = status.to_string();
-            tasks.retain(|t| t.status == status);
+        filter: TaskFilter,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<Task>)> {
+        let story = ctx.repo.fetch_story(story_id).await?;
+        if let Some(status) = &filter.status {
+            let workflow = ctx.repo.fetch_workflow(story.id).await?;
+            workflow
+                .ensure_state(status)
+                .map_err(|err| Error::invalid_args(&err))?;
+        }
+        ctx.repo.list_tasks(story.id, &filter, after, limit).await
+    }
+}
+
+/// Get a page of tasks assigned to a user, across all stories.
+pub struct GetAssignedTasks;
+impl GetAssignedTasks {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        assignee: &str,
+        filter: TaskFilter,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<Task>)> {
+        ctx.repo
+            .list_assigned_tasks(assignee, &filter, after, limit)
+            .await
+    }
+}
+
+/// Get a page of the direct subtasks of a task.
+pub struct GetSubtasks;
+impl GetSubtasks {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        task_id: Uuid,
+        mut filter: TaskFilter,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<Task>)> {
+        let task = ctx.repo.fetch_task(task_id).await?;
+        if let Some(status) = &filter.status {
+            let workflow = ctx.repo.fetch_workflow(task.story_id).await?;
+            workflow
+                .ensure_state(status)
+                .map_err(|err| Error::invalid_args(&err))?;
         }
-        Ok(tasks)
+        filter.parent_task_id = Some(task.id);
+        ctx.repo
+            .list_tasks(task.story_id, &filter, after, limit)
+            .await
     }
 }
 
-/// Create a task
+/// Create a task, in the initial workflow state unless a status is given.
 pub struct CreateTask;
 impl CreateTask {
     pub async fn execute(
         ctx: Arc<Ctx>,
+        actor: &str,
         story_id: Uuid,
+        parent_task_id: Option<Uuid>,
         name: String,
-        status: Status,
+        status: Option<String>,
+        due_at: Option<DateTime<Utc>>,
     ) -> Result<Task> {
         ctx.repo
-            .fetch_story(story_id)
-            .and_then(|s| ctx.repo.create_task(s.id, name, status))
+            .create_task(
+                actor,
+                story_id,
+                parent_task_id,
+                name,
+                status.as_deref(),
+                due_at,
+            )
             .await
     }
 }
 
-/// Update name and/or status of a task.
+/// Update name, status, due time, parent, and/or story of a task. Status changes must be allowed
+/// by the story workflow, and a task can only be closed once the tasks it depends on are, unless
+/// forced. Closing a recurring task creates its next occurrence. When enabled, closing the last
+/// open subtask of a task closes the task too. Moving a task to another story takes its subtasks
+/// along, and checks the rest of the update against the workflow of that story.
 pub struct UpdateTask;
 impl UpdateTask {
     pub async fn execute(
         ctx: Arc<Ctx>,
+        actor: &str,
         task_id: Uuid,
-        name: Option<String>,
-        status: Option<Status>,
+        changes: TaskChanges,
     ) -> Result<Task> {
-        let task = ctx
-            .repo
+        let complete_parents = ctx.config.auto_complete_parent_tasks;
+        ctx.repo
             .fetch_task(task_id)
             .and_then(|t| {
-                let status = status.unwrap_or(t.status());
-                let name = name.unwrap_or(t.name);
-                ctx.repo.update_task(task_id, name, status)
+                ctx.repo
+                    .update_task(actor, t.id, &changes, complete_parents)
             })
-            .await?;
-        Ok(task)
+            .await
+    }
+}
+
+/// Set the recurrence rule of a task, or stop it repeating
+pub struct SetRecurrence;
+impl SetRecurrence {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        task_id: Uuid,
+        recurrence: Option<Recurrence>,
+    ) -> Result<Task> {
+        let recurrence = recurrence.map(|r| r.to_string());
+        ctx.repo
+            .set_recurrence(actor, task_id, recurrence.as_deref())
+            .await
+    }
+}
+
+/// Assign a task to a user, or unassign it
+pub struct AssignTask;
+impl AssignTask {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        task_id: Uuid,
+        assignee: Option<String>,
+    ) -> Result<Task> {
+        ctx.repo
+            .assign_task(actor, task_id, assignee.as_deref())
+            .await
+    }
+}
+
+/// Move a task next to a sibling task
+pub struct MoveTask;
+impl MoveTask {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        task_id: Uuid,
+        placement: Placement,
+    ) -> Result<Task> {
+        ctx.repo.move_task(actor, task_id, placement).await
+    }
+}
+
+/// Move tasks, with their subtasks, to the end of another story as top level tasks
+pub struct MoveTasksToStory;
+impl MoveTasksToStory {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        story_id: Uuid,
+        task_ids: Vec<Uuid>,
+    ) -> Result<Vec<Task>> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.move_tasks_to_story(actor, &task_ids, s.id, None))
+            .await
     }
 }
 
-/// Delete a task
+/// Move a task to the trash
 pub struct DeleteTask;
 impl DeleteTask {
-    pub async fn execute(ctx: Arc<Ctx>, task_id: Uuid) -> Result<()> {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, task_id: Uuid) -> Result<()> {
         ctx.repo
             .fetch_task(task_id)
-            .and_then(|t| ctx.repo.delete_task(t.id))
+            .and_then(|t| ctx.repo.delete_task(actor, t.id))
             .await
     }
 }
@@ -0,0 +1,70 @@
+use crate::{
+    api::Ctx,
+    domain::{Story, StoryTemplate},
+    Result,
+};
+use futures_util::TryFutureExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Fetch a template
+pub struct GetTemplate;
+impl GetTemplate {
+    pub async fn execute(ctx: Arc<Ctx>, template_id: Uuid) -> Result<StoryTemplate> {
+        ctx.repo.fetch_template(template_id).await
+    }
+}
+
+/// Fetch all templates
+pub struct GetTemplates;
+impl GetTemplates {
+    pub async fn execute(ctx: Arc<Ctx>) -> Result<Vec<StoryTemplate>> {
+        ctx.repo.list_templates().await
+    }
+}
+
+/// Save a story, its tasks and optionally its files as a template
+pub struct SaveTemplate;
+impl SaveTemplate {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        story_id: Uuid,
+        name: String,
+        include_files: bool,
+    ) -> Result<StoryTemplate> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.create_template(s.id, name, include_files))
+            .await
+    }
+}
+
+/// Delete a template, along with file contents no story or other template shares.
+pub struct DeleteTemplate;
+impl DeleteTemplate {
+    pub async fn execute(ctx: Arc<Ctx>, template_id: Uuid) -> Result<()> {
+        let storage_ids = ctx.repo.delete_template(template_id).await?;
+        // Contents are removed only after metadata deletion succeeds
+        for storage_id in storage_ids {
+            if let Err(err) = ctx.storage.delete(storage_id).await {
+                tracing::error!("unable to delete {} from storage: {}", storage_id, err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Create a story from a template
+pub struct CreateStoryFromTemplate;
+impl CreateStoryFromTemplate {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        template_id: Uuid,
+        name: Option<String>,
+    ) -> Result<Story> {
+        ctx.repo
+            .create_story_from_template(actor, template_id, name)
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{
+    api::Ctx,
+    domain::{Story, StoryFile, Task},
+    Result,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Fetch deleted stories, tasks, and files
+pub struct GetTrash;
+impl GetTrash {
+    pub async fn execute(ctx: Arc<Ctx>) -> Result<(Vec<Story>, Vec<Task>, Vec<StoryFile>)> {
+        let stories = ctx.repo.list_deleted_stories().await?;
+        let tasks = ctx.repo.list_deleted_tasks().await?;
+        let files = ctx.repo.list_deleted_files().await?;
+        Ok((stories, tasks, files))
+    }
+}
+
+/// Restore a story from the trash
+pub struct RestoreStory;
+impl RestoreStory {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, story_id: Uuid) -> Result<Story> {
+        ctx.repo.restore_story(actor, story_id).await
+    }
+}
+
+/// Restore a task from the trash
+pub struct RestoreTask;
+impl RestoreTask {
+    pub async fn execute(ctx: Arc<Ctx>, actor: &str, task_id: Uuid) -> Result<Task> {
+        ctx.repo.restore_task(actor, task_id).await
+    }
+}
+
+/// Restore a file from the trash
+pub struct RestoreFile;
+impl RestoreFile {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        story_id: Uuid,
+        file_id: Uuid,
+    ) -> Result<StoryFile> {
+        ctx.repo.restore_file(actor, story_id, file_id).await
+    }
+}
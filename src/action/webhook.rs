@@ -0,0 +1,70 @@
+use crate::{
+    api::Ctx,
+    domain::{EventKind, Webhook, WebhookDelivery},
+    Result,
+};
+use futures_util::TryFutureExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Create a webhook
+pub struct CreateWebhook;
+impl CreateWebhook {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        url: String,
+        secret: String,
+        events: Vec<EventKind>,
+        story_id: Option<Uuid>,
+    ) -> Result<Webhook> {
+        // Ensure the story exists when scoping to a single story
+        if let Some(story_id) = story_id {
+            ctx.repo.fetch_story(story_id).await?;
+        }
+        let events = events.into_iter().map(|e| e.to_string()).collect();
+        ctx.repo.create_webhook(url, secret, events, story_id).await
+    }
+}
+
+/// Fetch a webhook
+pub struct GetWebhook;
+impl GetWebhook {
+    pub async fn execute(ctx: Arc<Ctx>, webhook_id: Uuid) -> Result<Webhook> {
+        ctx.repo.fetch_webhook(webhook_id).await
+    }
+}
+
+/// Fetch all webhooks
+pub struct GetWebhooks;
+impl GetWebhooks {
+    pub async fn execute(ctx: Arc<Ctx>) -> Result<Vec<Webhook>> {
+        ctx.repo.list_webhooks().await
+    }
+}
+
+/// Delete a webhook
+pub struct DeleteWebhook;
+impl DeleteWebhook {
+    pub async fn execute(ctx: Arc<Ctx>, webhook_id: Uuid) -> Result<()> {
+        ctx.repo
+            .fetch_webhook(webhook_id)
+            .and_then(|w| ctx.repo.delete_webhook(w.id))
+            .await
+    }
+}
+
+/// Fetch a page of deliveries for a webhook
+pub struct GetDeliveries;
+impl GetDeliveries {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        webhook_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<WebhookDelivery>)> {
+        ctx.repo
+            .fetch_webhook(webhook_id)
+            .and_then(|w| ctx.repo.list_deliveries(w.id, cursor, limit))
+            .await
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{api::Ctx, domain::Workflow, Result};
+use futures_util::TryFutureExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Get the workflow of a story
+pub struct GetWorkflow;
+impl GetWorkflow {
+    pub async fn execute(ctx: Arc<Ctx>, story_id: Uuid) -> Result<Workflow> {
+        ctx.repo
+            .fetch_story(story_id)
+            .and_then(|s| ctx.repo.fetch_workflow(s.id))
+            .await
+    }
+}
+
+/// Replace the workflow of a story
+pub struct UpdateWorkflow;
+impl UpdateWorkflow {
+    pub async fn execute(
+        ctx: Arc<Ctx>,
+        actor: &str,
+        story_id: Uuid,
+        workflow: Workflow,
+    ) -> Result<Workflow> {
+        ctx.repo.replace_workflow(actor, story_id, workflow).await
+    }
+}
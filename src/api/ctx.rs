@@ -1,20 +1,36 @@
-use crate::{driver::storage::Storage, repo::Repo};
+use crate::{config::Config, domain::Event, driver::storage::Storage, repo::Repo};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+// The number of events buffered for slow subscribers.
+const EVENT_BUFFER: usize = 1024;
+
 /// Context contains pointers to keepers and drivers for use in API routes.
 #[derive(Clone)]
 pub struct Ctx {
+    /// Configuration settings
+    pub config: Arc<Config>,
+
     /// Binary object storage
     pub storage: Arc<Box<dyn Storage<Uuid>>>,
 
     /// Persistence API
     pub repo: Arc<Repo>,
+
+    /// Story events relayed from the database
+    pub events: broadcast::Sender<Arc<Event>>,
 }
 
 impl Ctx {
     /// Create a new API context
-    pub fn new(storage: Arc<Box<dyn Storage<Uuid>>>, repo: Arc<Repo>) -> Self {
-        Self { storage, repo }
+    pub fn new(config: Arc<Config>, storage: Arc<Box<dyn Storage<Uuid>>>, repo: Arc<Repo>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            config,
+            storage,
+            repo,
+            events,
+        }
     }
 }
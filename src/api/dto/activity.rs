@@ -0,0 +1,21 @@
+use crate::domain::Activity;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// A page of story activity
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Activities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    activity: Vec<Activity>,
+}
+
+impl Activities {
+    // Create a new page of activity
+    pub fn new(next_page: Option<String>, activity: Vec<Activity>) -> Self {
+        Self {
+            next_page,
+            activity,
+        }
+    }
+}
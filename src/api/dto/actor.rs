@@ -0,0 +1,76 @@
+use crate::{Error, Result};
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderMap},
+};
+
+/// The header that identifies the caller making a change.
+const USER_ID: &str = "x-user-id";
+
+/// The actor recorded for changes made without a user id.
+const ANONYMOUS: &str = "anonymous";
+
+/// Limit actor size in http request header.
+const MAX_ACTOR_LEN: usize = 100;
+
+/// The caller performing an action, taken from the user id header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor(pub String);
+
+impl Actor {
+    /// Get the actor from request headers, falling back to anonymous.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self> {
+        let Some(value) = headers.get(USER_ID) else {
+            return Ok(Self(ANONYMOUS.into()));
+        };
+        let actor = value
+            .to_str()
+            .map_err(|_| Error::invalid_args("x-user-id: invalid characters"))?
+            .trim();
+        if actor.is_empty() || actor.len() > MAX_ACTOR_LEN {
+            return Err(Error::invalid_args("x-user-id: invalid length"));
+        }
+        Ok(Self(actor.into()))
+    }
+
+    /// Get the user id of the caller, failing when the request has none.
+    pub fn user_id(&self) -> Result<&str> {
+        if self.0 == ANONYMOUS {
+            return Err(Error::invalid_args("x-user-id: required"));
+        }
+        Ok(&self.0)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Actor {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self> {
+        Self::from_headers(&parts.headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn actor_from_headers() {
+        let mut headers = HeaderMap::new();
+        let actor = Actor::from_headers(&headers).unwrap();
+        assert_eq!(actor.0, ANONYMOUS);
+        assert!(actor.user_id().is_err());
+        headers.insert(USER_ID, HeaderValue::from_static(" alice "));
+        let actor = Actor::from_headers(&headers).unwrap();
+        assert_eq!(actor.0, "alice");
+        assert_eq!(actor.user_id().unwrap(), "alice");
+        headers.insert(USER_ID, HeaderValue::from_static(" "));
+        assert!(Actor::from_headers(&headers).is_err());
+        let long = HeaderValue::from_str(&"a".repeat(MAX_ACTOR_LEN + 1)).unwrap();
+        headers.insert(USER_ID, long);
+        assert!(Actor::from_headers(&headers).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    domain::{Comment, CommentRevision},
+    Error, Result,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Limit comment body size in http request body.
+const MAX_BODY_LEN: usize = 10_000;
+
+/// The POST body for creating comments
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCommentRequest {
+    /// The comment text, as markdown
+    pub body: String,
+    /// A comment on the same story or task to reply to
+    pub reply_to_id: Option<Uuid>,
+}
+
+impl CreateCommentRequest {
+    /// Validate a comment create request.
+    pub fn validate(&self) -> Result<(Option<Uuid>, String)> {
+        let body = validate_body(&self.body)?;
+        Ok((self.reply_to_id, body))
+    }
+}
+
+/// The PATCH body for editing comments
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCommentRequest {
+    /// The comment text, as markdown
+    pub body: String,
+}
+
+impl UpdateCommentRequest {
+    /// Validate a comment update request.
+    pub fn validate(&self) -> Result<String> {
+        validate_body(&self.body)
+    }
+}
+
+/// A page of comments
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Comments {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    comments: Vec<Comment>,
+}
+
+impl Comments {
+    // Create a new page of comments
+    pub fn new(next_page: Option<String>, comments: Vec<Comment>) -> Self {
+        Self {
+            next_page,
+            comments,
+        }
+    }
+}
+
+/// A page of comment revisions
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentRevisions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    revisions: Vec<CommentRevision>,
+}
+
+impl CommentRevisions {
+    // Create a new page of comment revisions
+    pub fn new(next_page: Option<String>, revisions: Vec<CommentRevision>) -> Self {
+        Self {
+            next_page,
+            revisions,
+        }
+    }
+}
+
+/// Check a comment body isn't blank or too long. Markdown is kept as written, apart from
+/// surrounding whitespace.
+fn validate_body(body: &str) -> Result<String> {
+    let body = body.trim();
+    if body.is_empty() || body.len() > MAX_BODY_LEN {
+        return Err(Error::invalid_args("body: invalid length"));
+    }
+    Ok(body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_comment_requests() {
+        let req = CreateCommentRequest {
+            body: "  **Agreed**\n\n- one\n- two\n".into(),
+            reply_to_id: None,
+        };
+        let (_, body) = req.validate().unwrap();
+        assert_eq!(body, "**Agreed**\n\n- one\n- two");
+        let req = UpdateCommentRequest { body: " \n".into() };
+        assert!(req.validate().is_err());
+        let req = UpdateCommentRequest {
+            body: "a".repeat(MAX_BODY_LEN + 1),
+        };
+        assert!(req.validate().is_err());
+    }
+}
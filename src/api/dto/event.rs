@@ -0,0 +1,48 @@
+use crate::{Error, Result};
+use axum::http::HeaderMap;
+use serde::Deserialize;
+
+/// The header sent by SSE clients when reconnecting.
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// The query parameters for streaming story events.
+#[derive(Debug, Deserialize, Default)]
+pub struct EventParams {
+    pub last_event_id: Option<i64>,
+}
+
+impl EventParams {
+    /// Get the last event id seen by a client, preferring the header over the query param.
+    pub fn last_event_id(&self, headers: &HeaderMap) -> Result<Option<i64>> {
+        match headers.get(LAST_EVENT_ID) {
+            Some(value) => {
+                let event_id = value
+                    .to_str()
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .ok_or_else(|| Error::invalid_args("last-event-id: invalid event id"))?;
+                Ok(Some(event_id))
+            }
+            None => Ok(self.last_event_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn last_event_id_from_header_or_param() {
+        let params = EventParams {
+            last_event_id: Some(7),
+        };
+        let mut headers = HeaderMap::new();
+        assert_eq!(params.last_event_id(&headers).unwrap(), Some(7));
+        headers.insert(LAST_EVENT_ID, HeaderValue::from_static("42"));
+        assert_eq!(params.last_event_id(&headers).unwrap(), Some(42));
+        headers.insert(LAST_EVENT_ID, HeaderValue::from_static("x"));
+        assert!(params.last_event_id(&headers).is_err());
+    }
+}
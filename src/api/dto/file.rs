@@ -0,0 +1,89 @@
+use crate::{
+    domain::{FileSort, SortOrder, StoryFile},
+    Error, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// The query parameters for sorting the files of a story.
+#[derive(Debug, Deserialize, Default)]
+pub struct FileParams {
+    pub sort: Option<String>,
+    pub order: Option<String>,
+}
+
+impl FileParams {
+    /// Validate sort params.
+    pub fn validate(&self) -> Result<(FileSort, SortOrder)> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
+        let mut sort = FileSort::default();
+        if let Some(s) = &self.sort {
+            match FileSort::from_str(s) {
+                Ok(parsed) => sort = parsed,
+                Err(err) => messages.push(format!("sort: {}", err)),
+            }
+        }
+        let mut order = SortOrder::default();
+        if let Some(s) = &self.order {
+            match SortOrder::from_str(s) {
+                Ok(parsed) => order = parsed,
+                Err(err) => messages.push(format!("order: {}", err)),
+            }
+        }
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        Ok((sort, order))
+    }
+}
+
+/// A page of story files
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Files {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    files: Vec<StoryFile>,
+}
+
+impl Files {
+    // Create a new page of files
+    pub fn new(next_page: Option<String>, files: Vec<StoryFile>) -> Self {
+        Self { next_page, files }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_file_params() {
+        let params = FileParams::default();
+        assert_eq!(
+            params.validate().unwrap(),
+            (FileSort::CreatedAt, SortOrder::Asc)
+        );
+        let params = FileParams {
+            sort: Some("size".into()),
+            order: Some("desc".into()),
+        };
+        assert_eq!(
+            params.validate().unwrap(),
+            (FileSort::Size, SortOrder::Desc)
+        );
+        let params = FileParams {
+            sort: Some("owner".into()),
+            order: Some("up".into()),
+        };
+        let Err(Error::InvalidArgs { messages }) = params.validate() else {
+            panic!("expected invalid args");
+        };
+        assert_eq!(messages.len(), 2);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    domain::{LabelFilter, LabelMatch},
+    Error, Result,
+};
+use serde::Deserialize;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// Limit label name size in http requests.
+const MAX_NAME_LEN: usize = 50;
+
+/// Limit the number of labels in a filter.
+const MAX_FILTER_LABELS: usize = 10;
+
+/// The request body for creating or updating labels
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LabelRequest {
+    pub name: String,
+    /// A hex rgb colour, like #1f883d
+    pub color: String,
+}
+
+impl LabelRequest {
+    /// Validate a label create/update request.
+    pub fn validate(&self) -> Result<(String, String)> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
+        // Validate body params
+        let name = self.name.trim().to_string();
+        if name.is_empty() || name.len() > MAX_NAME_LEN || name.contains(',') {
+            messages.push("name: invalid length or characters".into());
+        }
+        let color = self.color.trim().to_ascii_lowercase();
+        let valid_color = color.len() == 7
+            && color.starts_with('#')
+            && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid_color {
+            messages.push("color: must be a hex rgb colour, like #1f883d".into());
+        }
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        Ok((name, color))
+    }
+}
+
+/// Parse comma separated label names and a match mode into a label filter, collecting error
+/// messages if invalid.
+pub(super) fn parse_labels(
+    label: &Option<String>,
+    label_match: &Option<String>,
+    messages: &mut Vec<String>,
+) -> Option<LabelFilter> {
+    let mut mode = LabelMatch::default();
+    if let Some(s) = label_match {
+        match LabelMatch::from_str(s) {
+            Ok(parsed) => mode = parsed,
+            Err(err) => messages.push(format!("label_match: {}", err)),
+        }
+    }
+    let mut names: Vec<String> = Vec::new();
+    for name in label.as_deref()?.split(',') {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            messages.push("label: invalid length".into());
+            return None;
+        }
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    if names.len() > MAX_FILTER_LABELS {
+        messages.push(format!("label: at most {MAX_FILTER_LABELS} labels"));
+        return None;
+    }
+    Some(LabelFilter { names, mode })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_label_request() {
+        let req = LabelRequest {
+            name: " Backend ".into(),
+            color: "#1F883D".into(),
+        };
+        assert_eq!(
+            req.validate().unwrap(),
+            ("Backend".to_string(), "#1f883d".to_string())
+        );
+        for (name, color) in [("", "#1f883d"), ("a,b", "#1f883d"), ("ops", "green")] {
+            let req = LabelRequest {
+                name: name.into(),
+                color: color.into(),
+            };
+            assert!(req.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn parse_label_filter() {
+        let mut messages = Vec::new();
+        assert_eq!(parse_labels(&None, &None, &mut messages), None);
+        let label = Some("Backend, design,backend".into());
+        let filter = parse_labels(&label, &Some("any".into()), &mut messages).unwrap();
+        assert_eq!(filter.names, vec!["backend", "design"]);
+        assert_eq!(filter.mode, LabelMatch::Any);
+        assert!(messages.is_empty());
+        parse_labels(&Some("ops,".into()), &None, &mut messages);
+        parse_labels(&label, &Some("some".into()), &mut messages);
+        assert_eq!(messages.len(), 2);
+    }
+}
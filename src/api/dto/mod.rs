@@ -1,7 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+mod activity;
+mod actor;
+mod comment;
+mod event;
+mod file;
+mod label;
 mod page;
+mod report;
+mod search;
 mod story;
 mod task;
+mod template;
+mod trash;
+mod webhook;
+mod workflow;
+
+pub use activity::Activities;
+pub use actor::Actor;
+pub use comment::{CommentRevisions, Comments, CreateCommentRequest, UpdateCommentRequest};
+pub use event::EventParams;
+pub use file::{FileParams, Files};
+pub use label::LabelRequest;
+pub use page::{PageCodec, PageParams, PageScope};
+pub use report::{burndown_csv, cycle_time_csv, ReportFormat, ReportParams};
+pub use search::{SearchParams, SearchResults};
+pub use story::{CloneStoryRequest, Stories, StoryParams, StoryRequest};
+pub use task::{
+    AssignTaskRequest, CreateTaskRequest, MoveTaskRequest, MoveTasksRequest, RecurrenceRequest,
+    TaskParams, Tasks, UpdateTaskRequest,
+};
+pub use template::{FromTemplateRequest, TemplateRequest};
+pub use trash::Trash;
+pub use webhook::{Deliveries, WebhookRequest};
+pub use workflow::WorkflowRequest;
+
+/// Parse an optional RFC 3339 timestamp field, collecting an error message if invalid.
+fn parse_time(
+    field: &str,
+    value: &Option<String>,
+    messages: &mut Vec<String>,
+) -> Option<DateTime<Utc>> {
+    let value = value.as_ref()?;
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => Some(time.with_timezone(&Utc)),
+        Err(err) => {
+            messages.push(format!("{field}: {err}"));
+            None
+        }
+    }
+}
 
-pub use page::{PageParams, PageToken};
-pub use story::{Stories, StoryRequest};
-pub use task::{CreateTaskRequest, TaskParams, UpdateTaskRequest};
+/// Deserialize a field that is present, possibly as null, so it can be told apart from a
+/// missing field.
+fn present<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
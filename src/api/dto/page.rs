@@ -1,8 +1,19 @@
-use crate::{Error, Result};
+use crate::{
+    config::Config,
+    domain::{Keyset, LabelFilter, Seek},
+    Error, Result,
+};
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 use borsh::{BorshDeserialize, BorshSerialize};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::Deserialize;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sha2::Sha256;
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 // Define a reasonable default page size.
 const DEFAULT_PAGE_SIZE: i32 = 100;
@@ -11,6 +22,11 @@ const DEFAULT_PAGE_SIZE: i32 = 100;
 const MIN_PAGE_SIZE: i32 = 10;
 const MAX_PAGE_SIZE: i32 = 1000;
 
+// The size of a HMAC-SHA256 tag appended to page tokens.
+const TAG_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
 /// The query parameters for getting a page of domain objects from a list endpoint.
 #[derive(Debug, Deserialize, Default)]
 pub struct PageParams {
@@ -27,43 +43,217 @@ impl PageParams {
 }
 
 /// A paging token for accessing previous, next pages of domain objects in a list call.
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct PageToken {
+#[derive(Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+struct PageToken {
     cursor: i64,
+    key: Option<String>,
+    backward: bool,
     ts: u64,
 }
 
-impl PageToken {
+/// Signs and verifies page tokens for a single list query. Tokens are bound to the query scope
+/// (the endpoint and filters they were issued for), and expire after a max age.
+pub struct PageCodec {
+    secret: Vec<u8>,
+    max_age_secs: u64,
+    scope: String,
+}
+
+impl PageCodec {
+    /// Create a codec for a list query scope, using config settings.
+    pub fn new(config: &Config, scope: String) -> Self {
+        let secret = config.page_token_secret.as_bytes();
+        Self::with_key(secret, config.page_token_max_age_secs, scope)
+    }
+
+    /// Create a codec with a signing key and max token age.
+    pub fn with_key(secret: &[u8], max_age_secs: u64, scope: String) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            max_age_secs,
+            scope,
+        }
+    }
+
     /// Encode a cursor seqno as a page token.
-    pub fn encode(cursor: i64) -> Option<String> {
+    pub fn encode(&self, cursor: i64) -> Option<String> {
         if cursor <= 0 {
             return None;
         }
-        match borsh::to_vec(&PageToken { cursor, ts: now() }) {
-            Ok(bytes) => Some(URL_SAFE.encode(bytes)),
-            Err(err) => {
-                tracing::warn!("failed serializing page token: {}", err);
-                None
-            }
-        }
+        self.sign(&PageToken {
+            cursor,
+            key: None,
+            backward: false,
+            ts: now(),
+        })
+    }
+
+    /// Encode a seek position as a page token.
+    pub fn encode_seek(&self, seek: &Seek) -> Option<String> {
+        let (cursor, key) = match seek {
+            Seek::First | Seek::Last => (0, None),
+            Seek::After(keyset) | Seek::Before(keyset) => (keyset.seqno, Some(keyset.key.clone())),
+        };
+        self.sign(&PageToken {
+            cursor,
+            key,
+            backward: seek.is_backward(),
+            ts: now(),
+        })
+    }
+
+    /// Encode the keyset of the last item in a sorted page as a page token.
+    pub fn encode_keyset(&self, keyset: Option<Keyset>) -> Option<String> {
+        let keyset = keyset?;
+        self.sign(&PageToken {
+            cursor: keyset.seqno,
+            key: Some(keyset.key),
+            backward: false,
+            ts: now(),
+        })
     }
 
     /// Extract page cursor from encoded token param, falling back to a default value.
-    pub fn decode_or(token_opt: &Option<String>, default: i64) -> Result<i64> {
+    pub fn decode_or(&self, token_opt: &Option<String>, default: i64) -> Result<i64> {
         if default <= 0 {
             return Err(Error::invalid_args("default page cursor must be > 0"));
         }
         match token_opt {
             None => Ok(default),
-            Some(token) => {
-                let bytes = URL_SAFE.decode(token)?;
-                let page_token: PageToken = borsh::from_slice(&bytes)?;
-                Ok(page_token.cursor)
+            Some(token) => Ok(self.verify(token)?.cursor),
+        }
+    }
+
+    /// Extract a seek position from an encoded token param, falling back to a default value.
+    pub fn decode_seek_or(&self, token_opt: &Option<String>, default: Seek) -> Result<Seek> {
+        let Some(token) = token_opt else {
+            return Ok(default);
+        };
+        let page_token = self.verify(token)?;
+        let keyset = page_token.key.map(|key| Keyset {
+            key,
+            seqno: page_token.cursor,
+        });
+        let seek = match (keyset, page_token.backward) {
+            (None, false) => Seek::First,
+            (None, true) => Seek::Last,
+            (Some(keyset), false) => Seek::After(keyset),
+            (Some(keyset), true) => Seek::Before(keyset),
+        };
+        Ok(seek)
+    }
+
+    /// Extract a keyset from an encoded token param, if any.
+    pub fn decode_keyset(&self, token_opt: &Option<String>) -> Result<Option<Keyset>> {
+        let Some(token) = token_opt else {
+            return Ok(None);
+        };
+        let page_token = self.verify(token)?;
+        match page_token.key {
+            Some(key) => Ok(Some(Keyset {
+                key,
+                seqno: page_token.cursor,
+            })),
+            None => Err(invalid_token()),
+        }
+    }
+
+    // Serialize, tag and base64 encode a page token.
+    fn sign(&self, page_token: &PageToken) -> Option<String> {
+        let mut bytes = match borsh::to_vec(page_token) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                tracing::warn!("failed serializing page token: {}", err);
+                return None;
             }
+        };
+        let tag = self.mac(&bytes).finalize().into_bytes();
+        bytes.extend_from_slice(&tag);
+        Some(URL_SAFE.encode(bytes))
+    }
+
+    // Decode a page token, checking its tag and age.
+    fn verify(&self, token: &str) -> Result<PageToken> {
+        let bytes = URL_SAFE.decode(token).map_err(|_| invalid_token())?;
+        if bytes.len() <= TAG_LEN {
+            return Err(invalid_token());
+        }
+        let (payload, tag) = bytes.split_at(bytes.len() - TAG_LEN);
+        self.mac(payload)
+            .verify_slice(tag)
+            .map_err(|_| invalid_token())?;
+        let page_token: PageToken = borsh::from_slice(payload).map_err(|_| invalid_token())?;
+        if now().saturating_sub(page_token.ts) > self.max_age_secs {
+            return Err(Error::invalid_args("page_token: expired"));
         }
+        Ok(page_token)
+    }
+
+    // Start a MAC over a token payload and the query scope.
+    fn mac(&self, payload: &[u8]) -> HmacSha256 {
+        // HMAC accepts keys of any length, so this can't fail.
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("hmac key");
+        mac.update(self.scope.as_bytes());
+        mac.update(&[0]);
+        mac.update(payload);
+        mac
     }
 }
 
+/// Builds the scope of a list query from its path and validated query params. Params are added
+/// in a fixed order and encoded explicitly, so a scope only changes when the query does.
+pub struct PageScope {
+    scope: String,
+    params: usize,
+}
+
+impl PageScope {
+    /// Start a scope for a list endpoint path.
+    pub fn new(path: impl Display) -> Self {
+        Self {
+            scope: path.to_string(),
+            params: 0,
+        }
+    }
+
+    /// Add a query param, when set.
+    pub fn param(mut self, name: &str, value: Option<impl Display>) -> Self {
+        if let Some(value) = value {
+            self.scope.push(if self.params == 0 { '?' } else { '&' });
+            self.scope.push_str(name);
+            self.scope.push('=');
+            let value = value.to_string();
+            self.scope
+                .extend(utf8_percent_encode(&value, NON_ALPHANUMERIC));
+            self.params += 1;
+        }
+        self
+    }
+
+    /// Add a time param, when set.
+    pub fn time(self, name: &str, value: Option<DateTime<Utc>>) -> Self {
+        self.param(name, value.map(|t| t.to_rfc3339()))
+    }
+
+    /// Add label filter params, when set.
+    pub fn labels(self, labels: &Option<LabelFilter>) -> Self {
+        let names = labels.as_ref().map(|l| l.names.join(","));
+        self.param("label", names)
+            .param("label_match", labels.as_ref().map(|l| l.mode))
+    }
+}
+
+impl From<PageScope> for String {
+    fn from(scope: PageScope) -> Self {
+        scope.scope
+    }
+}
+
+/// The error for tokens that were forged, altered, or issued for another query.
+fn invalid_token() -> Error {
+    Error::invalid_args("page_token: invalid")
+}
+
 /// Calculate the number of seconds since the unix epoch.
 fn now() -> u64 {
     SystemTime::now()
@@ -76,29 +266,135 @@ fn now() -> u64 {
 mod tests {
     use super::*;
 
+    fn codec(scope: &str) -> PageCodec {
+        PageCodec::with_key(b"test-secret", 60, scope.into())
+    }
+
     #[test]
     fn encode_decode_page_token() {
+        let codec = codec("stories");
         let input = 5201;
-        let pt = PageToken::encode(input);
+        let pt = codec.encode(input);
         assert!(pt.is_some());
-        let output = PageToken::decode_or(&pt, 1).unwrap();
+        let output = codec.decode_or(&pt, 1).unwrap();
         assert_eq!(input, output);
     }
 
+    #[test]
+    fn encode_decode_seek_page_token() {
+        let codec = codec("stories");
+        let keyset = Keyset {
+            key: "Poems".into(),
+            seqno: 7,
+        };
+        for seek in [
+            Seek::First,
+            Seek::Last,
+            Seek::After(keyset.clone()),
+            Seek::Before(keyset),
+        ] {
+            let pt = codec.encode_seek(&seek);
+            assert_eq!(codec.decode_seek_or(&pt, Seek::First).unwrap(), seek);
+        }
+        let output = codec.decode_seek_or(&None, Seek::Last).unwrap();
+        assert_eq!(output, Seek::Last);
+    }
+
+    #[test]
+    fn encode_decode_keyset_page_token() {
+        let codec = codec("files");
+        let keyset = Keyset {
+            key: "notes.txt".into(),
+            seqno: 42,
+        };
+        let pt = codec.encode_keyset(Some(keyset.clone()));
+        assert!(pt.is_some());
+        assert_eq!(codec.decode_keyset(&pt).unwrap(), Some(keyset));
+        assert!(codec.encode_keyset(None).is_none());
+        assert_eq!(codec.decode_keyset(&None).unwrap(), None);
+        // Cursor tokens don't carry a keyset
+        assert!(codec.decode_keyset(&codec.encode(42)).is_err());
+    }
+
     #[test]
     fn encode_invalid_page_cursor() {
-        assert!(PageToken::encode(0).is_none());
-        assert!(PageToken::encode(-10).is_none());
+        let codec = codec("stories");
+        assert!(codec.encode(0).is_none());
+        assert!(codec.encode(-10).is_none());
     }
 
     #[test]
     fn decode_default() {
         // Should get default when page token is None
+        let codec = codec("stories");
         let expect = i64::MAX;
-        let output = PageToken::decode_or(&None, expect).unwrap();
+        let output = codec.decode_or(&None, expect).unwrap();
         assert_eq!(output, expect);
         // Invalid defaults should produce an error
-        assert!(PageToken::decode_or(&None, 0).is_err());
-        assert!(PageToken::decode_or(&None, -10).is_err());
+        assert!(codec.decode_or(&None, 0).is_err());
+        assert!(codec.decode_or(&None, -10).is_err());
+    }
+
+    #[test]
+    fn decode_invalid_page_tokens() {
+        let codec = codec("tasks?status=complete");
+        let token = codec.encode(10).unwrap();
+
+        // Malformed tokens
+        for token in ["", "not base64!", "AAAA"] {
+            let result = codec.decode_or(&Some(token.into()), 1);
+            assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+        }
+
+        // Altered tokens
+        let mut bytes = URL_SAFE.decode(&token).unwrap();
+        bytes[0] ^= 1;
+        let altered = Some(URL_SAFE.encode(bytes));
+        assert!(codec.decode_or(&altered, 1).is_err());
+
+        // Tokens issued for another query or with another key
+        let other = self::codec("tasks?status=incomplete");
+        assert!(other.decode_or(&Some(token.clone()), 1).is_err());
+        let other = PageCodec::with_key(b"other-secret", 60, "tasks?status=complete".into());
+        assert!(other.decode_or(&Some(token), 1).is_err());
+    }
+
+    #[test]
+    fn build_page_scopes() {
+        let scope: String = PageScope::new("stories").into();
+        assert_eq!(scope, "stories");
+        let labels = Some(LabelFilter {
+            names: vec!["home".into(), "to do".into()],
+            ..Default::default()
+        });
+        let due = DateTime::parse_from_rfc3339("2024-10-18T12:00:00+02:00").unwrap();
+        let scope: String = PageScope::new("tasks")
+            .param("status", Some("done"))
+            .param("overdue", None::<bool>)
+            .time("due_before", Some(due.with_timezone(&Utc)))
+            .labels(&labels)
+            .into();
+        assert_eq!(
+            scope,
+            "tasks?status=done&due_before=2024%2D10%2D18T10%3A00%3A00%2B00%3A00\
+            &label=home%2Cto%20do&label_match=all"
+        );
+        let scope: String = PageScope::new("search").param("q", Some("a&page=2")).into();
+        assert_eq!(scope, "search?q=a%26page%3D2");
+    }
+
+    #[test]
+    fn decode_expired_page_token() {
+        let codec = codec("stories");
+        let token = codec.sign(&PageToken {
+            cursor: 10,
+            key: None,
+            backward: false,
+            ts: now() - 61,
+        });
+        let Err(Error::InvalidArgs { messages }) = codec.decode_or(&token, 1) else {
+            panic!("expected invalid args");
+        };
+        assert_eq!(messages, vec!["page_token: expired".to_string()]);
     }
 }
@@ -0,0 +1,194 @@
+use crate::{
+    domain::{Burndown, CycleTime},
+    Error, Result,
+};
+use chrono::{Days, NaiveDate};
+use serde::Deserialize;
+use std::{fmt::Write, str::FromStr};
+use strum_macros::EnumString;
+use uuid::Uuid;
+
+/// The number of days reported when no start date is given.
+const DEFAULT_REPORT_DAYS: u64 = 30;
+
+/// Limit the number of days in a report.
+const MAX_REPORT_DAYS: i64 = 366;
+
+/// The formats reports can be returned in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// The query parameters for task reports.
+#[derive(Debug, Deserialize, Default)]
+pub struct ReportParams {
+    pub story_id: Option<Uuid>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub format: Option<String>,
+}
+
+/// Validated report params: story, first and last dates, and format.
+type ReportRange = (Option<Uuid>, NaiveDate, NaiveDate, ReportFormat);
+
+impl ReportParams {
+    /// Validate report params. The range defaults to the 30 days up to today.
+    pub fn validate(&self, today: NaiveDate) -> Result<ReportRange> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
+        let to = parse_date("to", &self.to, &mut messages).unwrap_or(today);
+        let from = parse_date("from", &self.from, &mut messages)
+            .or_else(|| to.checked_sub_days(Days::new(DEFAULT_REPORT_DAYS - 1)))
+            .unwrap_or(to);
+        let days = (to - from).num_days() + 1;
+        if days < 1 {
+            messages.push("from: after to".into());
+        } else if days > MAX_REPORT_DAYS {
+            messages.push(format!("from: more than {MAX_REPORT_DAYS} days before to"));
+        }
+        let mut format = ReportFormat::default();
+        if let Some(s) = &self.format {
+            match ReportFormat::from_str(s) {
+                Ok(parsed) => format = parsed,
+                Err(err) => messages.push(format!("format: {}", err)),
+            }
+        }
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        Ok((self.story_id, from, to, format))
+    }
+}
+
+/// Parse an optional YYYY-MM-DD date field, collecting an error message if invalid.
+fn parse_date(
+    field: &str,
+    value: &Option<String>,
+    messages: &mut Vec<String>,
+) -> Option<NaiveDate> {
+    let value = value.as_ref()?;
+    match NaiveDate::from_str(value) {
+        Ok(date) => Some(date),
+        Err(err) => {
+            messages.push(format!("{field}: {err}"));
+            None
+        }
+    }
+}
+
+/// Write a burndown as csv, one row per day.
+pub fn burndown_csv(burndown: &Burndown) -> String {
+    let mut csv = String::from("date,created,completed,remaining\n");
+    for day in &burndown.days {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{}",
+            day.date, day.created, day.completed, day.remaining
+        );
+    }
+    csv
+}
+
+/// Write cycle time percentiles as csv, with a single row.
+pub fn cycle_time_csv(cycle_time: &CycleTime) -> String {
+    let secs = |p: Option<i64>| p.map(|s| s.to_string()).unwrap_or_default();
+    format!(
+        "from,to,tasks,p50_secs,p75_secs,p90_secs,p95_secs\n{},{},{},{},{},{},{}\n",
+        cycle_time.from,
+        cycle_time.to,
+        cycle_time.tasks,
+        secs(cycle_time.p50_secs),
+        secs(cycle_time.p75_secs),
+        secs(cycle_time.p90_secs),
+        secs(cycle_time.p95_secs),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DailyTasks;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn validate_report_params() {
+        let today = date("2024-10-18");
+        let (story_id, from, to, format) = ReportParams::default().validate(today).unwrap();
+        assert_eq!(story_id, None);
+        assert_eq!((from, to), (date("2024-09-19"), today));
+        assert_eq!(format, ReportFormat::Json);
+
+        let params = ReportParams {
+            from: Some("2024-01-01".into()),
+            to: Some("2024-01-31".into()),
+            format: Some("csv".into()),
+            ..Default::default()
+        };
+        let (_, from, to, format) = params.validate(today).unwrap();
+        assert_eq!((from, to), (date("2024-01-01"), date("2024-01-31")));
+        assert_eq!(format, ReportFormat::Csv);
+
+        for (from, to, format) in [
+            ("2024-02-01", "2024-01-31", "csv"),
+            ("2023-01-01", "2024-01-31", "csv"),
+            ("yesterday", "2024-01-31", "xml"),
+        ] {
+            let params = ReportParams {
+                from: Some(from.into()),
+                to: Some(to.into()),
+                format: Some(format.into()),
+                ..Default::default()
+            };
+            assert!(params.validate(today).is_err(), "{from} {to} {format}");
+        }
+    }
+
+    #[test]
+    fn write_csv() {
+        let burndown = Burndown {
+            story_id: None,
+            from: date("2024-10-17"),
+            to: date("2024-10-18"),
+            days: vec![
+                DailyTasks {
+                    date: date("2024-10-17"),
+                    created: 3,
+                    completed: 0,
+                    remaining: 3,
+                },
+                DailyTasks {
+                    date: date("2024-10-18"),
+                    created: 0,
+                    completed: 2,
+                    remaining: 1,
+                },
+            ],
+        };
+        assert_eq!(
+            burndown_csv(&burndown),
+            "date,created,completed,remaining\n2024-10-17,3,0,3\n2024-10-18,0,2,1\n"
+        );
+        let cycle_time = CycleTime {
+            story_id: None,
+            from: burndown.from,
+            to: burndown.to,
+            tasks: 0,
+            p50_secs: None,
+            p75_secs: None,
+            p90_secs: None,
+            p95_secs: None,
+        };
+        assert!(cycle_time_csv(&cycle_time).ends_with("2024-10-17,2024-10-18,0,,,,\n"));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{domain::SearchHit, Error, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Limit search query size.
+const MAX_QUERY_LEN: usize = 200;
+
+/// The query parameters for searching.
+#[derive(Debug, Deserialize, Default)]
+pub struct SearchParams {
+    pub q: Option<String>,
+}
+
+impl SearchParams {
+    /// Validate search params.
+    pub fn validate(&self) -> Result<String> {
+        let q = self.q.as_deref().unwrap_or_default().trim();
+        if q.is_empty() || q.len() > MAX_QUERY_LEN {
+            return Err(Error::invalid_args("q: invalid length"));
+        }
+        Ok(q.to_string())
+    }
+}
+
+/// A page of search results
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchResults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    results: Vec<SearchHit>,
+}
+
+impl SearchResults {
+    // Create a new page of search results
+    pub fn new(next_page: Option<String>, results: Vec<SearchHit>) -> Self {
+        Self { next_page, results }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_search_params() {
+        let params = SearchParams {
+            q: Some(" invoices ".into()),
+        };
+        assert_eq!(params.validate().unwrap(), "invoices");
+        assert!(SearchParams::default().validate().is_err());
+        let params = SearchParams {
+            q: Some("x".repeat(MAX_QUERY_LEN + 1)),
+        };
+        assert!(params.validate().is_err());
+    }
+}
@@ -1,25 +1,206 @@
-use crate::{domain::Story, Error, Result};
+use super::{
+    label::parse_labels, parse_time, present, template::validate_name, PageCodec, PageScope,
+};
+use crate::{
+    domain::{
+        Metadata, MetadataSchema, Seek, SortOrder, Story, StoryChanges, StoryFilter, StorySort,
+        StorySummary,
+    },
+    Error, Result,
+};
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::str::FromStr;
 use utoipa::ToSchema;
 
 /// Limit name size in http request body.
 const MAX_NAME_LEN: usize = 100;
 
+/// Limit description size in http request body.
+const MAX_DESCRIPTION_LEN: usize = 50_000;
+
+/// Limit the number and size of metadata entries in http request body.
+const MAX_METADATA_KEYS: usize = 50;
+const MAX_METADATA_KEY_LEN: usize = 64;
+const MAX_METADATA_VALUE_LEN: usize = 1000;
+
 /// The request body for creating or updating stories
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct StoryRequest {
     name: String,
+    /// Long form markdown, or null to clear it. Left as is on update when missing.
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    description: Option<Option<String>>,
+    /// Custom key/value pairs, replacing all existing metadata. Keys and value types may be
+    /// restricted by the server's metadata schema.
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<Metadata>,
 }
 
 impl StoryRequest {
-    /// Validate a story create/update request.
-    pub fn validate(&self) -> Result<String> {
+    /// Validate a story create/update request, checking metadata against a schema.
+    pub fn validate(self, schema: &MetadataSchema) -> Result<StoryChanges> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
         let name = self.name.trim().to_string();
         if name.is_empty() || name.len() > MAX_NAME_LEN {
-            return Err(Error::invalid_args("name: invalid length"));
+            messages.push("name: invalid length".into());
+        }
+        // Blank descriptions are cleared
+        let description = self.description.map(|d| d.filter(|d| !d.trim().is_empty()));
+        if let Some(Some(d)) = &description {
+            if d.len() > MAX_DESCRIPTION_LEN {
+                messages.push("description: invalid length".into());
+            }
+        }
+        if let Some(metadata) = &self.metadata {
+            if metadata.len() > MAX_METADATA_KEYS {
+                messages.push(format!("metadata: more than {MAX_METADATA_KEYS} keys"));
+            }
+            for (key, value) in metadata {
+                if key.is_empty() || key.len() > MAX_METADATA_KEY_LEN {
+                    messages.push(format!("metadata.{key}: invalid key length"));
+                } else if value
+                    .as_str()
+                    .is_some_and(|v| v.len() > MAX_METADATA_VALUE_LEN)
+                {
+                    messages.push(format!("metadata.{key}: invalid value length"));
+                } else if let Err(err) = schema.check(key, value) {
+                    messages.push(format!("metadata.{key}: {err}"));
+                }
+            }
         }
-        Ok(name)
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        Ok(StoryChanges {
+            name,
+            description,
+            metadata: self.metadata,
+        })
+    }
+}
+
+/// The request body for copying a story
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CloneStoryRequest {
+    /// The name of the copy, defaulting to the name of the story
+    name: Option<String>,
+    /// Whether to copy files too, sharing their contents (default true)
+    include_files: Option<bool>,
+}
+
+impl CloneStoryRequest {
+    /// Validate a clone request, returning the name if given and whether to include files.
+    pub fn validate(self) -> Result<(Option<String>, bool)> {
+        let name = validate_name(self.name)?;
+        Ok((name, self.include_files.unwrap_or(true)))
+    }
+}
+
+/// The query parameters for filtering and sorting stories.
+#[derive(Debug, Deserialize, Default)]
+pub struct StoryParams {
+    pub name: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub updated_after: Option<String>,
+    pub updated_before: Option<String>,
+    pub has_incomplete_tasks: Option<bool>,
+    pub label: Option<String>,
+    pub label_match: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub include_total: Option<bool>,
+    pub include: Option<String>,
+}
+
+/// Validated list params: filter, sort, order, and whether to include the total and stats.
+type StoryListParams = (StoryFilter, StorySort, SortOrder, bool, bool);
+
+impl StoryParams {
+    /// Validate list params.
+    pub fn validate(&self) -> Result<StoryListParams> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
+        let mut filter = StoryFilter {
+            has_incomplete_tasks: self.has_incomplete_tasks,
+            ..Default::default()
+        };
+        if let Some(name) = &self.name {
+            let name = name.trim();
+            if name.is_empty() || name.len() > MAX_NAME_LEN {
+                messages.push("name: invalid length".into());
+            } else {
+                filter.name = Some(name.to_string());
+            }
+        }
+        filter.created_after = parse_time("created_after", &self.created_after, &mut messages);
+        filter.created_before = parse_time("created_before", &self.created_before, &mut messages);
+        filter.updated_after = parse_time("updated_after", &self.updated_after, &mut messages);
+        filter.updated_before = parse_time("updated_before", &self.updated_before, &mut messages);
+        filter.labels = parse_labels(&self.label, &self.label_match, &mut messages);
+
+        let mut sort = StorySort::default();
+        if let Some(s) = &self.sort {
+            match StorySort::from_str(s) {
+                Ok(parsed) => sort = parsed,
+                Err(err) => messages.push(format!("sort: {}", err)),
+            }
+        }
+        let mut order = SortOrder::default();
+        if let Some(s) = &self.order {
+            match SortOrder::from_str(s) {
+                Ok(parsed) => order = parsed,
+                Err(err) => messages.push(format!("order: {}", err)),
+            }
+        }
+
+        let mut include_stats = false;
+        for part in self.include.iter().flat_map(|s| s.split(',')) {
+            match part.trim() {
+                "stats" => include_stats = true,
+                "" => {}
+                other => messages.push(format!("include: unknown value: {other}")),
+            }
+        }
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        let include_total = self.include_total.unwrap_or_default();
+        Ok((filter, sort, order, include_total, include_stats))
+    }
+}
+
+impl PageScope {
+    /// Add the params of a story filter.
+    pub fn story_filter(self, filter: &StoryFilter) -> Self {
+        // Destructured so new filter fields aren't left out of the scope
+        let StoryFilter {
+            name,
+            created_after,
+            created_before,
+            updated_after,
+            updated_before,
+            has_incomplete_tasks,
+            labels,
+        } = filter;
+        self.param("name", name.as_ref())
+            .time("created_after", *created_after)
+            .time("created_before", *created_before)
+            .time("updated_after", *updated_after)
+            .time("updated_before", *updated_before)
+            .param("has_incomplete_tasks", *has_incomplete_tasks)
+            .labels(labels)
     }
 }
 
@@ -28,12 +209,216 @@ impl StoryRequest {
 pub struct Stories {
     #[serde(skip_serializing_if = "Option::is_none")]
     next_page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev_page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_estimated: Option<bool>,
     stories: Vec<Story>,
+    /// Task and file totals for each story, in the same order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<Vec<StorySummary>>,
 }
 
 impl Stories {
-    // Create a new page of stories
-    pub fn new(next_page: Option<String>, stories: Vec<Story>) -> Self {
-        Self { next_page, stories }
+    // Create a new page of stories, with tokens for neighboring pages. More is whether there are
+    // stories past the page, in the direction it was selected.
+    pub fn new(
+        codec: &PageCodec,
+        sort: StorySort,
+        seek: &Seek,
+        stories: Vec<Story>,
+        more: bool,
+    ) -> Self {
+        let (has_next, has_prev) = match seek {
+            Seek::First => (more, false),
+            Seek::After(_) => (more, true),
+            Seek::Before(_) => (true, more),
+            Seek::Last => (false, more),
+        };
+        let next_page = stories
+            .last()
+            .filter(|_| has_next)
+            .and_then(|s| codec.encode_seek(&Seek::After(s.keyset(sort))));
+        let prev_page = stories
+            .first()
+            .filter(|_| has_prev)
+            .and_then(|s| codec.encode_seek(&Seek::Before(s.keyset(sort))));
+        Self {
+            next_page,
+            prev_page,
+            first_page: codec.encode_seek(&Seek::First),
+            last_page: codec.encode_seek(&Seek::Last),
+            total: None,
+            total_estimated: None,
+            stories,
+            stats: None,
+        }
+    }
+
+    // Add the total number of stories to a page
+    pub fn with_total(mut self, total: i64, estimated: bool) -> Self {
+        self.total = Some(total);
+        self.total_estimated = Some(estimated);
+        self
+    }
+
+    // Add task and file totals for the stories on a page
+    pub fn with_stats(mut self, stats: Vec<StorySummary>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Keyset;
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn story(seqno: i64) -> Story {
+        Story {
+            id: Uuid::new_v4(),
+            name: format!("Story {seqno}"),
+            description: None,
+            metadata: Default::default(),
+            seqno,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn codec() -> PageCodec {
+        PageCodec::with_key(b"test-secret", 60, "stories".into())
+    }
+
+    fn seek(token: &Option<String>) -> Seek {
+        codec()
+            .decode_seek_or(token, Seek::After(keyset(-1)))
+            .unwrap()
+    }
+
+    fn keyset(seqno: i64) -> Keyset {
+        Keyset {
+            key: format!("Story {seqno}"),
+            seqno,
+        }
+    }
+
+    #[test]
+    fn stories_page_links() {
+        // First page, sorted by name
+        let sort = StorySort::Name;
+        let page = Stories::new(&codec(), sort, &Seek::First, vec![story(9), story(8)], true);
+        assert_eq!(seek(&page.next_page), Seek::After(keyset(8)));
+        assert!(page.prev_page.is_none());
+        assert_eq!(seek(&page.first_page), Seek::First);
+        assert_eq!(seek(&page.last_page), Seek::Last);
+
+        // Middle page, reached by going back
+        let before = Seek::Before(keyset(5));
+        let page = Stories::new(&codec(), sort, &before, vec![story(7), story(6)], true);
+        assert_eq!(seek(&page.next_page), Seek::After(keyset(6)));
+        assert_eq!(seek(&page.prev_page), Seek::Before(keyset(7)));
+
+        // Last page
+        let page = Stories::new(&codec(), sort, &Seek::Last, vec![story(2), story(1)], true);
+        assert!(page.next_page.is_none());
+        assert_eq!(seek(&page.prev_page), Seek::Before(keyset(2)));
+    }
+
+    #[test]
+    fn validate_story_request() {
+        let schema = MetadataSchema::from_str("team:string,points:number").unwrap();
+        let req: StoryRequest = serde_json::from_value(json!({"name": " Launch "})).unwrap();
+        let changes = req.validate(&schema).unwrap();
+        assert_eq!(changes.name, "Launch");
+        assert_eq!((changes.description, changes.metadata), (None, None));
+
+        let req: StoryRequest = serde_json::from_value(json!({
+            "name": "Launch",
+            "description": " ",
+            "metadata": {"team": "web", "points": 5}
+        }))
+        .unwrap();
+        let changes = req.validate(&schema).unwrap();
+        assert_eq!(changes.description, Some(None));
+        assert_eq!(changes.metadata.unwrap().len(), 2);
+
+        let req: StoryRequest = serde_json::from_value(json!({
+            "name": "",
+            "description": "x".repeat(MAX_DESCRIPTION_LEN + 1),
+            "metadata": {"team": 1, "owner": "alice"}
+        }))
+        .unwrap();
+        let Err(Error::InvalidArgs { messages }) = req.validate(&schema) else {
+            panic!("expected invalid args");
+        };
+        assert_eq!(messages.len(), 4);
+    }
+
+    #[test]
+    fn validate_clone_story_request() {
+        let req: CloneStoryRequest = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(req.validate().unwrap(), (None, true));
+        let req: CloneStoryRequest =
+            serde_json::from_value(json!({"name": " Launch v2 ", "include_files": false})).unwrap();
+        assert_eq!(req.validate().unwrap(), (Some("Launch v2".into()), false));
+        let req: CloneStoryRequest = serde_json::from_value(json!({"name": ""})).unwrap();
+        assert!(req.validate().is_err());
+    }
+
+    #[test]
+    fn validate_story_params() {
+        let params = StoryParams::default();
+        let (filter, sort, order, include_total, include_stats) = params.validate().unwrap();
+        assert!(filter.is_empty());
+        assert_eq!(sort, StorySort::CreatedAt);
+        assert_eq!(order, SortOrder::Asc);
+        assert!(!include_total);
+        assert!(!include_stats);
+
+        let params = StoryParams {
+            name: Some(" poems ".into()),
+            created_after: Some("2024-10-18T09:00:00Z".into()),
+            has_incomplete_tasks: Some(true),
+            sort: Some("updated_at".into()),
+            order: Some("desc".into()),
+            include_total: Some(true),
+            include: Some("stats".into()),
+            ..Default::default()
+        };
+        let (filter, sort, order, include_total, include_stats) = params.validate().unwrap();
+        assert_eq!(filter.name.as_deref(), Some("poems"));
+        assert_eq!(
+            filter.created_after.map(|t| t.to_rfc3339()).as_deref(),
+            Some("2024-10-18T09:00:00+00:00")
+        );
+        assert_eq!(filter.has_incomplete_tasks, Some(true));
+        assert_eq!(sort, StorySort::UpdatedAt);
+        assert_eq!(order, SortOrder::Desc);
+        assert!(include_total);
+        assert!(include_stats);
+
+        let params = StoryParams {
+            name: Some(" ".into()),
+            updated_before: Some("yesterday".into()),
+            sort: Some("size".into()),
+            order: Some("newest".into()),
+            include: Some("stats,tasks".into()),
+            ..Default::default()
+        };
+        let Err(Error::InvalidArgs { messages }) = params.validate() else {
+            panic!("expected invalid args");
+        };
+        assert_eq!(messages.len(), 5);
     }
 }
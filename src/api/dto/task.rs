@@ -1,24 +1,48 @@
-use crate::{domain::Status, Error, Result};
-use serde::Deserialize;
-use std::fmt::Debug;
-use std::str::FromStr;
+use super::{label::parse_labels, parse_time, present, PageScope};
+use crate::{
+    domain::{Placement, Recurrence, Task, TaskChanges, TaskFilter},
+    Error, Result,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, str::FromStr};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// Limit name size in http request body.
 const MAX_NAME_LEN: usize = 100;
 
+/// Limit workflow state size in http requests.
+const MAX_STATUS_LEN: usize = 30;
+
+/// Limit assignee size in http request body, the same as user ids.
+const MAX_ASSIGNEE_LEN: usize = 100;
+
+/// Limit the number of tasks moved to another story at once.
+const MAX_MOVE_TASKS: usize = 100;
+
+/// When a task is due, if ever.
+type DueAt = Option<DateTime<Utc>>;
+
+/// Validated task create fields: story, parent task, name, status, and due time.
+type NewTask = (Uuid, Option<Uuid>, String, Option<String>, DueAt);
+
 /// The POST body for creating tasks
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateTaskRequest {
     pub name: String,
     pub story_id: Uuid,
+    /// A task in the same story to create this task as a subtask of
+    pub parent_task_id: Option<Uuid>,
+    /// A state of the story workflow, defaults to its first state
     pub status: Option<String>,
+    /// When the task is due, as an RFC 3339 time
+    pub due_at: Option<String>,
 }
 
 impl CreateTaskRequest {
     /// Validate a task create request.
-    pub fn validate(&self) -> Result<(Uuid, String, Status)> {
+    pub fn validate(&self) -> Result<NewTask> {
         // Collects error messages
         let mut messages = Vec::new();
 
@@ -27,20 +51,15 @@ impl CreateTaskRequest {
         if name.is_empty() || name.len() > MAX_NAME_LEN {
             messages.push("name: invalid length".into());
         }
-        let mut status: Option<Status> = None;
-        if let Some(s) = &self.status {
-            match Status::from_str(s) {
-                Ok(parsed) => status = Some(parsed),
-                Err(err) => messages.push(format!("status: {}", err)),
-            }
-        }
+        let status = validate_status(&self.status, &mut messages);
+        let due_at = parse_time("due_at", &self.due_at, &mut messages);
 
         // Check for validation failures and return an error if found
         if !messages.is_empty() {
             return Err(Error::InvalidArgs { messages });
         }
 
-        Ok((self.story_id, name, status.unwrap_or_default()))
+        Ok((self.story_id, self.parent_task_id, name, status, due_at))
     }
 }
 
@@ -48,21 +67,42 @@ impl CreateTaskRequest {
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateTaskRequest {
     pub name: Option<String>,
+    /// A state of the story workflow, reachable from the current status
     pub status: Option<String>,
+    /// When the task is due, as an RFC 3339 time, or null to clear it
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub due_at: Option<Option<String>>,
+    /// A task in the same story to make this a subtask of, or null to make it a top level task
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<Uuid>)]
+    pub parent_task_id: Option<Option<Uuid>>,
+    /// A story to move the task and its subtasks to, where it becomes a top level task unless
+    /// parent_task_id names a task in that story
+    pub story_id: Option<Uuid>,
+    /// Close the task even if tasks it depends on are still open
+    #[serde(default)]
+    pub force: bool,
 }
 
 impl UpdateTaskRequest {
     /// Validate a task update request.
-    pub fn validate(&self) -> Result<(Option<String>, Option<Status>)> {
+    pub fn validate(&self) -> Result<TaskChanges> {
         // Make sure at least one field is provided
-        if self.name.is_none() && self.status.is_none() {
-            return Err(Error::invalid_args("name and/or status must be provided"));
+        if self.name.is_none()
+            && self.status.is_none()
+            && self.due_at.is_none()
+            && self.parent_task_id.is_none()
+            && self.story_id.is_none()
+        {
+            return Err(Error::invalid_args(
+                "name, status, due_at, parent_task_id and/or story_id must be provided",
+            ));
         }
 
         // Defaults for return values
         let mut messages = Vec::new();
         let mut name: Option<String> = None;
-        let mut status: Option<Status> = None;
 
         // Validate
         if let Some(n) = &self.name {
@@ -73,35 +113,326 @@ impl UpdateTaskRequest {
                 name = Some(n.to_string());
             }
         }
-        if let Some(s) = &self.status {
-            match Status::from_str(s) {
-                Ok(parsed) => status = Some(parsed),
-                Err(err) => messages.push(format!("status: {}", err)),
-            }
-        }
+        let status = validate_status(&self.status, &mut messages);
+        let due_at = self
+            .due_at
+            .as_ref()
+            .map(|due_at| parse_time("due_at", due_at, &mut messages));
 
         // Check for validation failures and return an error if found
         if !messages.is_empty() {
             return Err(Error::InvalidArgs { messages });
         }
 
-        Ok((name, status))
+        Ok(TaskChanges {
+            name,
+            status,
+            due_at,
+            parent_task_id: self.parent_task_id,
+            story_id: self.story_id,
+            force: self.force,
+        })
     }
 }
 
+/// The POST body for moving tasks, next to exactly one sibling task
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MoveTaskRequest {
+    pub before: Option<Uuid>,
+    pub after: Option<Uuid>,
+}
+
+impl MoveTaskRequest {
+    /// Validate a task move request.
+    pub fn validate(&self) -> Result<Placement> {
+        match (self.before, self.after) {
+            (Some(id), None) => Ok(Placement::Before(id)),
+            (None, Some(id)) => Ok(Placement::After(id)),
+            _ => Err(Error::invalid_args(
+                "exactly one of before or after must be provided",
+            )),
+        }
+    }
+}
+
+/// The POST body for moving tasks, with their subtasks, to another story
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MoveTasksRequest {
+    /// The tasks to move, which become top level tasks of the story in this order
+    pub task_ids: Vec<Uuid>,
+}
+
+impl MoveTasksRequest {
+    /// Validate a bulk task move request, dropping repeated task ids.
+    pub fn validate(&self) -> Result<Vec<Uuid>> {
+        let mut task_ids: Vec<Uuid> = Vec::with_capacity(self.task_ids.len());
+        for &task_id in &self.task_ids {
+            if !task_ids.contains(&task_id) {
+                task_ids.push(task_id);
+            }
+        }
+        if task_ids.is_empty() || task_ids.len() > MAX_MOVE_TASKS {
+            return Err(Error::invalid_args(&format!(
+                "task_ids: between 1 and {MAX_MOVE_TASKS} tasks must be provided"
+            )));
+        }
+        Ok(task_ids)
+    }
+}
+
+/// The PUT body for assigning tasks
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssignTaskRequest {
+    /// The id of the user to assign the task to
+    pub assignee: String,
+}
+
+impl AssignTaskRequest {
+    /// Validate a task assign request.
+    pub fn validate(&self) -> Result<String> {
+        let assignee = self.assignee.trim();
+        if assignee.is_empty() || assignee.len() > MAX_ASSIGNEE_LEN {
+            return Err(Error::invalid_args("assignee: invalid length"));
+        }
+        Ok(assignee.to_string())
+    }
+}
+
+/// The PUT body for making tasks recur
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RecurrenceRequest {
+    /// A recurrence rule: FREQ=DAILY, WEEKLY or MONTHLY, with an optional INTERVAL, and either
+    /// an UNTIL time or a COUNT of occurrences. Monthly rules can set the BYMONTHDAY they fall
+    /// on, which defaults to the day of the first occurrence. For example
+    /// FREQ=WEEKLY;INTERVAL=2;COUNT=5. Each occurrence is due one interval after the one it
+    /// follows, or after the time that one was completed when it had no due time.
+    pub rule: String,
+}
+
+impl RecurrenceRequest {
+    /// Validate a task recurrence request.
+    pub fn validate(&self) -> Result<Recurrence> {
+        Recurrence::from_str(&self.rule).map_err(|err| Error::invalid_args(&format!("rule: {err}")))
+    }
+}
+
+/// The query parameters for filtering the tasks of a story, or of the caller.
 #[derive(Debug, Deserialize, Default)]
 pub struct TaskParams {
     pub status: Option<String>,
+    pub due_before: Option<String>,
+    pub due_after: Option<String>,
+    pub overdue: Option<bool>,
+    pub label: Option<String>,
+    pub label_match: Option<String>,
 }
 
 impl TaskParams {
-    /// Parse status enum from param string
-    pub fn status(&self) -> Option<Status> {
-        if let Some(s) = self.status.clone() {
-            if let Ok(status) = Status::from_str(&s) {
-                return Some(status);
-            }
+    /// Validate filter params.
+    pub fn validate(&self) -> Result<TaskFilter> {
+        let mut messages = Vec::new();
+        let filter = TaskFilter {
+            status: validate_status(&self.status, &mut messages),
+            due_before: parse_time("due_before", &self.due_before, &mut messages),
+            due_after: parse_time("due_after", &self.due_after, &mut messages),
+            overdue: self.overdue,
+            labels: parse_labels(&self.label, &self.label_match, &mut messages),
+            ..Default::default()
+        };
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
         }
-        None
+        Ok(filter)
+    }
+}
+
+impl PageScope {
+    /// Add the params of a task filter.
+    pub fn task_filter(self, filter: &TaskFilter) -> Self {
+        // Destructured so new filter fields aren't left out of the scope
+        let TaskFilter {
+            status,
+            due_before,
+            due_after,
+            overdue,
+            labels,
+            parent_task_id,
+        } = filter;
+        self.param("status", status.as_ref())
+            .time("due_before", *due_before)
+            .time("due_after", *due_after)
+            .param("overdue", *overdue)
+            .labels(labels)
+            .param("parent_task_id", *parent_task_id)
+    }
+}
+
+/// A page of tasks
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Tasks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    tasks: Vec<Task>,
+}
+
+impl Tasks {
+    // Create a new page of tasks
+    pub fn new(next_page: Option<String>, tasks: Vec<Task>) -> Self {
+        Self { next_page, tasks }
+    }
+}
+
+/// Check the size of a workflow state name. Whether the state exists depends on the story.
+fn validate_status(status: &Option<String>, messages: &mut Vec<String>) -> Option<String> {
+    let status = status.as_ref()?.trim();
+    if status.is_empty() || status.len() > MAX_STATUS_LEN {
+        messages.push("status: invalid length".into());
+        return None;
+    }
+    Some(status.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_task_params() {
+        let params = TaskParams::default();
+        assert_eq!(params.validate().unwrap(), TaskFilter::default());
+        let params = TaskParams {
+            status: Some("complete".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap().status.as_deref(),
+            Some("complete")
+        );
+        let params = TaskParams {
+            status: Some(" ".into()),
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+        let params = TaskParams {
+            due_before: Some("2024-10-18T12:00:00+02:00".into()),
+            overdue: Some(true),
+            ..Default::default()
+        };
+        let filter = params.validate().unwrap();
+        assert_eq!(
+            filter.due_before.map(|t| t.to_rfc3339()).as_deref(),
+            Some("2024-10-18T10:00:00+00:00")
+        );
+        assert_eq!(filter.overdue, Some(true));
+        let params = TaskParams {
+            due_after: Some("tomorrow".into()),
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn validate_update_task_due_at() {
+        let parse = |body: &str| -> UpdateTaskRequest { serde_json::from_str(body).unwrap() };
+        // Missing leaves the due time as is, null clears it
+        let changes = parse(r#"{"name": "Pay"}"#).validate().unwrap();
+        assert_eq!(changes.due_at, None);
+        let changes = parse(r#"{"due_at": null}"#).validate().unwrap();
+        assert_eq!(changes.due_at, Some(None));
+        let changes = parse(r#"{"due_at": "2024-10-18T10:00:00Z"}"#)
+            .validate()
+            .unwrap();
+        assert!(changes.due_at.flatten().is_some());
+        assert!(parse(r#"{"due_at": "soon"}"#).validate().is_err());
+        assert!(parse("{}").validate().is_err());
+    }
+
+    #[test]
+    fn validate_update_task_parent() {
+        let parse = |body: &str| -> UpdateTaskRequest { serde_json::from_str(body).unwrap() };
+        // Missing leaves the parent as is, null makes the task top level
+        let changes = parse(r#"{"name": "Pay"}"#).validate().unwrap();
+        assert_eq!(changes.parent_task_id, None);
+        let changes = parse(r#"{"parent_task_id": null}"#).validate().unwrap();
+        assert_eq!(changes.parent_task_id, Some(None));
+        let id = Uuid::new_v4();
+        let body = format!(r#"{{"parent_task_id": "{id}", "force": true}}"#);
+        let changes = parse(&body).validate().unwrap();
+        assert_eq!(changes.parent_task_id, Some(Some(id)));
+        assert!(changes.force);
+        let body = format!(r#"{{"story_id": "{id}"}}"#);
+        let changes = parse(&body).validate().unwrap();
+        assert_eq!((changes.story_id, changes.parent_task_id), (Some(id), None));
+    }
+
+    #[test]
+    fn validate_move_tasks_request() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let req = MoveTasksRequest {
+            task_ids: vec![a, b, a],
+        };
+        assert_eq!(req.validate().unwrap(), vec![a, b]);
+        let req = MoveTasksRequest { task_ids: vec![] };
+        assert!(req.validate().is_err());
+        let req = MoveTasksRequest {
+            task_ids: (0..=MAX_MOVE_TASKS).map(|_| Uuid::new_v4()).collect(),
+        };
+        assert!(req.validate().is_err());
+    }
+
+    #[test]
+    fn validate_assign_task_request() {
+        let req = AssignTaskRequest {
+            assignee: " alice ".into(),
+        };
+        assert_eq!(req.validate().unwrap(), "alice");
+        let req = AssignTaskRequest {
+            assignee: " ".into(),
+        };
+        assert!(req.validate().is_err());
+        let req = AssignTaskRequest {
+            assignee: "a".repeat(MAX_ASSIGNEE_LEN + 1),
+        };
+        assert!(req.validate().is_err());
+    }
+
+    #[test]
+    fn validate_recurrence_request() {
+        let req = RecurrenceRequest {
+            rule: "freq=monthly;until=20250101".into(),
+        };
+        assert_eq!(
+            req.validate().unwrap().to_string(),
+            "FREQ=MONTHLY;UNTIL=20250101T235959Z"
+        );
+        let req = RecurrenceRequest {
+            rule: "FREQ=HOURLY".into(),
+        };
+        assert!(req.validate().is_err());
+    }
+
+    #[test]
+    fn validate_move_task_request() {
+        let id = Uuid::new_v4();
+        let req = MoveTaskRequest {
+            before: Some(id),
+            after: None,
+        };
+        assert_eq!(req.validate().unwrap(), Placement::Before(id));
+        let req = MoveTaskRequest {
+            before: None,
+            after: Some(id),
+        };
+        assert_eq!(req.validate().unwrap(), Placement::After(id));
+        let req = MoveTaskRequest {
+            before: Some(id),
+            after: Some(id),
+        };
+        assert!(req.validate().is_err());
+        let req = MoveTaskRequest {
+            before: None,
+            after: None,
+        };
+        assert!(req.validate().is_err());
     }
 }
@@ -0,0 +1,74 @@
+use crate::{Error, Result};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Limit template and story name size in http request body.
+const MAX_NAME_LEN: usize = 100;
+
+/// The request body for saving a story as a template
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TemplateRequest {
+    name: String,
+    /// Whether to include the story's files, sharing their contents
+    #[serde(default)]
+    include_files: bool,
+}
+
+impl TemplateRequest {
+    /// Validate a template request, returning the name and whether to include files.
+    pub fn validate(self) -> Result<(String, bool)> {
+        let name = validate_name(Some(self.name))?.unwrap_or_default();
+        Ok((name, self.include_files))
+    }
+}
+
+/// The request body for creating a story from a template
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct FromTemplateRequest {
+    /// The story name, defaulting to the name of the story the template was saved from
+    name: Option<String>,
+}
+
+impl FromTemplateRequest {
+    /// Validate a request for a story from a template, returning the story name if given.
+    pub fn validate(self) -> Result<Option<String>> {
+        validate_name(self.name)
+    }
+}
+
+/// Check the length of an optional, trimmed name.
+pub(super) fn validate_name(name: Option<String>) -> Result<Option<String>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    let name = name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(Error::invalid_args("name: invalid length"));
+    }
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_template_requests() {
+        let req = TemplateRequest {
+            name: " Onboarding ".into(),
+            include_files: true,
+        };
+        assert_eq!(req.validate().unwrap(), ("Onboarding".to_string(), true));
+        let req = TemplateRequest {
+            name: " ".into(),
+            include_files: false,
+        };
+        assert!(req.validate().is_err());
+
+        assert_eq!(FromTemplateRequest::default().validate().unwrap(), None);
+        let req = FromTemplateRequest {
+            name: Some("x".repeat(MAX_NAME_LEN + 1)),
+        };
+        assert!(req.validate().is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use crate::domain::{Story, StoryFile, Task};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Deleted stories, and tasks and files that were deleted on their own
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Trash {
+    stories: Vec<Story>,
+    tasks: Vec<Task>,
+    files: Vec<StoryFile>,
+}
+
+impl Trash {
+    // Create a new trash listing
+    pub fn new(stories: Vec<Story>, tasks: Vec<Task>, files: Vec<StoryFile>) -> Self {
+        Self {
+            stories,
+            tasks,
+            files,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    domain::{EventKind, WebhookDelivery},
+    Error, Result,
+};
+use axum::http::Uri;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::str::FromStr;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Limit url size in http request body.
+const MAX_URL_LEN: usize = 2048;
+
+/// Secret size bounds
+const MIN_SECRET_LEN: usize = 16;
+const MAX_SECRET_LEN: usize = 256;
+
+/// The POST body for creating webhooks
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub secret: String,
+    pub events: Option<Vec<String>>,
+    pub story_id: Option<Uuid>,
+}
+
+impl WebhookRequest {
+    /// Validate a webhook create request.
+    pub fn validate(&self) -> Result<(String, String, Vec<EventKind>, Option<Uuid>)> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
+        // Validate body params
+        let url = self.url.trim().to_string();
+        let valid_url = url.parse::<Uri>().is_ok_and(|uri| {
+            matches!(uri.scheme_str(), Some("http") | Some("https")) && uri.host().is_some()
+        });
+        if url.len() > MAX_URL_LEN || !valid_url {
+            messages.push("url: must be an absolute http(s) url".into());
+        }
+        if self.secret.len() < MIN_SECRET_LEN || self.secret.len() > MAX_SECRET_LEN {
+            messages.push("secret: invalid length".into());
+        }
+        let mut events = Vec::new();
+        for s in self.events.iter().flatten() {
+            match EventKind::from_str(s) {
+                Ok(kind) if !events.contains(&kind) => events.push(kind),
+                Ok(_) => {}
+                Err(err) => messages.push(format!("events: {}: {}", s, err)),
+            }
+        }
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        Ok((url, self.secret.clone(), events, self.story_id))
+    }
+}
+
+/// A page of webhook deliveries
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Deliveries {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page: Option<String>,
+    deliveries: Vec<WebhookDelivery>,
+}
+
+impl Deliveries {
+    // Create a new page of deliveries
+    pub fn new(next_page: Option<String>, deliveries: Vec<WebhookDelivery>) -> Self {
+        Self {
+            next_page,
+            deliveries,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str, secret: &str, events: Vec<&str>) -> WebhookRequest {
+        WebhookRequest {
+            url: url.into(),
+            secret: secret.into(),
+            events: Some(events.into_iter().map(String::from).collect()),
+            story_id: None,
+        }
+    }
+
+    #[test]
+    fn validate_webhook_request() {
+        let req = request(
+            " https://ci.example.com/hooks ",
+            "0123456789abcdef",
+            vec!["task_updated", "task_created", "task_updated"],
+        );
+        let (url, _, events, story_id) = req.validate().unwrap();
+        assert_eq!(url, "https://ci.example.com/hooks");
+        assert_eq!(events, vec![EventKind::TaskUpdated, EventKind::TaskCreated]);
+        assert!(story_id.is_none());
+    }
+
+    #[test]
+    fn validate_invalid_webhook_request() {
+        let req = request("ftp://example.com", "short", vec!["task_finished"]);
+        let Err(Error::InvalidArgs { messages }) = req.validate() else {
+            panic!("expected invalid args");
+        };
+        assert_eq!(messages.len(), 3);
+        assert!(request("/hooks", "0123456789abcdef", vec![])
+            .validate()
+            .is_err());
+    }
+}
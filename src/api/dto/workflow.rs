@@ -0,0 +1,134 @@
+use crate::{
+    domain::{Transition, Workflow, WorkflowState},
+    Error, Result,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Limit workflow state size in http requests.
+const MAX_STATE_LEN: usize = 30;
+
+/// Limit the number of workflow states.
+const MAX_STATES: usize = 20;
+
+/// The PUT body for replacing the workflow of a story
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WorkflowRequest {
+    /// The states tasks can be in; new tasks start in the first, which must be open
+    pub states: Vec<WorkflowState>,
+    /// Allowed status changes; tasks can move between any states if left out
+    pub transitions: Option<Vec<Transition>>,
+}
+
+impl WorkflowRequest {
+    /// Validate a workflow request.
+    pub fn validate(&self) -> Result<Workflow> {
+        // Collects error messages
+        let mut messages = Vec::new();
+
+        if self.states.is_empty() || self.states.len() > MAX_STATES {
+            messages.push(format!("states: must have 1 to {MAX_STATES} states"));
+        }
+        let mut states: Vec<WorkflowState> = Vec::new();
+        for state in &self.states {
+            let name = state.name.trim();
+            let valid = !name.is_empty()
+                && name.len() <= MAX_STATE_LEN
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                messages.push(format!("states: invalid name: {name}"));
+            } else if states.iter().any(|s| s.name == name) {
+                messages.push(format!("states: duplicate name: {name}"));
+            } else {
+                states.push(WorkflowState {
+                    name: name.to_string(),
+                    closed: state.closed,
+                });
+            }
+        }
+        if states.first().is_some_and(|s| s.closed) {
+            messages.push("states: the first state must be open".into());
+        }
+        let mut transitions: Vec<Transition> = Vec::new();
+        for t in self.transitions.iter().flatten() {
+            let transition = Transition {
+                from: t.from.trim().to_string(),
+                to: t.to.trim().to_string(),
+            };
+            let known = |name: &str| states.iter().any(|s| s.name == name);
+            if !known(&transition.from) || !known(&transition.to) {
+                messages.push(format!(
+                    "transitions: unknown state: {} -> {}",
+                    transition.from, transition.to
+                ));
+            } else if transition.from != transition.to && !transitions.contains(&transition) {
+                transitions.push(transition);
+            }
+        }
+
+        // Check for validation failures and return an error if found
+        if !messages.is_empty() {
+            return Err(Error::InvalidArgs { messages });
+        }
+
+        Ok(Workflow {
+            states,
+            transitions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str, closed: bool) -> WorkflowState {
+        WorkflowState {
+            name: name.into(),
+            closed,
+        }
+    }
+
+    fn transition(from: &str, to: &str) -> Transition {
+        Transition {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    #[test]
+    fn validate_workflow_request() {
+        let req = WorkflowRequest {
+            states: vec![state(" todo ", false), state("done", true)],
+            transitions: Some(vec![
+                transition("todo", "done"),
+                transition("todo", "done"),
+                transition("done", "done"),
+            ]),
+        };
+        let workflow = req.validate().unwrap();
+        assert_eq!(workflow.initial().name, "todo");
+        assert_eq!(workflow.transitions, vec![transition("todo", "done")]);
+
+        let req = WorkflowRequest {
+            states: vec![
+                state("done", true),
+                state("In Progress", false),
+                state("done", false),
+            ],
+            transitions: Some(vec![transition("done", "review")]),
+        };
+        let Err(Error::InvalidArgs { messages }) = req.validate() else {
+            panic!("expected invalid args");
+        };
+        assert_eq!(messages.len(), 4);
+
+        let req = WorkflowRequest {
+            states: Vec::new(),
+            transitions: None,
+        };
+        assert!(req.validate().is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{
+    api::Ctx,
+    domain::{IdempotencyClaim, IdempotencyKey},
+    Error, Result,
+};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header::CONTENT_TYPE, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{Duration, Utc};
+use http_body_util::LengthLimitError;
+use sha2::{Digest, Sha256};
+use std::{error::Error as StdError, sync::Arc};
+use tokio::time;
+use uuid::Uuid;
+
+/// The request header used by clients to make retries of a POST request safe.
+pub(crate) const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+// The response header set when a stored response is replayed.
+const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+// Limit key size in http request headers.
+const MAX_KEY_LEN: usize = 255;
+
+// Limit the size of request and response bodies buffered for requests made with a key.
+const MAX_BODY_LEN: usize = 16 * 1024 * 1024; // 16m
+
+// How long a claim outlives the request timeout before a retry can take it over, in case the
+// request was dropped or its response couldn't be saved. Covers saving the response, and clock
+// drift between servers.
+const CLAIM_LEASE_MARGIN_SECS: u64 = 30;
+
+/// Middleware that stores the response of POST requests made with an idempotency key, replaying
+/// it when the same request is retried.
+pub(crate) async fn guard(
+    State(ctx): State<Arc<Ctx>>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    if req.method() != Method::POST {
+        return Ok(next.run(req).await);
+    }
+    let key = match idempotency_key(req.headers().get(IDEMPOTENCY_KEY))? {
+        Some(key) => key,
+        None => return Ok(next.run(req).await),
+    };
+
+    // Buffer the body so the request can be fingerprinted
+    let (parts, body) = req.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_LEN).await.map_err(body_error)?;
+    let boundary = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(multipart_boundary);
+    let fingerprint = fingerprint(&parts.method, parts.uri.path(), boundary, &bytes);
+
+    // Replay the stored response if the key has already been used
+    let now = Utc::now();
+    let expired_before = now - Duration::seconds(ctx.config.idempotency_ttl_secs);
+    let timeout = ctx.config.idempotency_timeout_secs;
+    let lease = Duration::seconds((timeout + CLAIM_LEASE_MARGIN_SECS) as i64);
+    let claimed = ctx
+        .repo
+        .claim_idempotency_key(&key, &fingerprint, expired_before, now - lease)
+        .await?;
+    let claim = match claimed {
+        IdempotencyClaim::Claimed(claim) => claim,
+        IdempotencyClaim::Existing(existing) => return replay(existing, &fingerprint),
+    };
+
+    // Requests can't outlive their claim, so a retry never runs alongside them. One that times
+    // out keeps the claim until its lease runs out, since it may have made changes.
+    let req = Request::from_parts(parts, Body::from(bytes));
+    let resp = time::timeout(time::Duration::from_secs(timeout), next.run(req))
+        .await
+        .map_err(|_| Error::internal("idempotent request timed out".into()))?;
+    save(ctx, &key, claim, resp).await
+}
+
+/// Map an error reading a request body, which is too large when it's over the body limit.
+fn body_error(err: axum::Error) -> Error {
+    let mut source: Option<&(dyn StdError + 'static)> = Some(&err);
+    while let Some(inner) = source {
+        if inner.is::<LengthLimitError>() {
+            return Error::too_large(inner.to_string());
+        }
+        source = inner.source();
+    }
+    Error::invalid_args(&err.to_string())
+}
+
+/// Parse and validate an idempotency key header value.
+fn idempotency_key(value: Option<&HeaderValue>) -> Result<Option<String>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| Error::invalid_args("idempotency key: invalid characters"))?
+        .trim();
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(Error::invalid_args("idempotency key: invalid length"));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Get the boundary from a multipart content type. Clients usually generate a new boundary for
+/// each attempt, so it's excluded when fingerprinting.
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    if !content_type.starts_with("multipart/") {
+        return None;
+    }
+    content_type
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .find(|boundary| !boundary.is_empty())
+}
+
+/// Hash the parts of a request that must match when a key is reused.
+fn fingerprint(method: &Method, path: &str, boundary: Option<&str>, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(path);
+    hasher.update(b"\n");
+    match boundary.map(str::as_bytes) {
+        Some(boundary) => {
+            let mut rest = body;
+            while let Some(pos) = rest.windows(boundary.len()).position(|w| w == boundary) {
+                hasher.update(&rest[..pos]);
+                rest = &rest[pos + boundary.len()..];
+            }
+            hasher.update(rest);
+        }
+        None => hasher.update(body),
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Build a response from a previously claimed idempotency key.
+fn replay(existing: IdempotencyKey, fingerprint: &str) -> Result<Response> {
+    if existing.fingerprint != fingerprint {
+        return Err(Error::unprocessable(
+            "idempotency key was used with a different request".into(),
+        ));
+    }
+    let Some(status_code) = existing.status_code else {
+        return Err(Error::conflict(
+            "a request with this idempotency key is in progress".into(),
+        ));
+    };
+    let status =
+        StatusCode::from_u16(status_code as u16).map_err(|err| Error::internal(err.to_string()))?;
+    let mut resp = Response::new(Body::from(existing.body.unwrap_or_default()));
+    *resp.status_mut() = status;
+    let headers = resp.headers_mut();
+    if let Some(content_type) = existing.content_type {
+        if let Ok(value) = HeaderValue::from_str(&content_type) {
+            headers.insert(CONTENT_TYPE, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    Ok(resp)
+}
+
+/// Store a response for an idempotency key, or release the key when the request failed on the
+/// server so it can be retried.
+async fn save(ctx: Arc<Ctx>, key: &str, claim: Uuid, resp: Response) -> Result<Response> {
+    let (parts, body) = resp.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_LEN).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            if let Err(err) = ctx.repo.release_idempotency_key(key, claim).await {
+                tracing::error!("unable to release idempotency key {}: {}", key, err);
+            }
+            return Err(Error::internal(err.to_string()));
+        }
+    };
+    let result = if parts.status.is_server_error() {
+        ctx.repo.release_idempotency_key(key, claim).await
+    } else {
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let status_code = parts.status.as_u16() as i16;
+        ctx.repo
+            .complete_idempotency_key(key, claim, status_code, content_type, bytes.to_vec())
+            .await
+    };
+    // Don't fail the request, just log the error
+    if let Err(err) = result {
+        tracing::error!("unable to save idempotency key {}: {}", key, err);
+    }
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_idempotency_key() {
+        assert!(idempotency_key(None).unwrap().is_none());
+        let value = HeaderValue::from_static(" 8e0f6a2c ");
+        let key = idempotency_key(Some(&value)).unwrap();
+        assert_eq!(key, Some("8e0f6a2c".to_string()));
+    }
+
+    #[test]
+    fn parse_invalid_idempotency_key() {
+        let value = HeaderValue::from_static("  ");
+        assert!(idempotency_key(Some(&value)).is_err());
+        let value = HeaderValue::from_str(&"k".repeat(MAX_KEY_LEN + 1)).unwrap();
+        assert!(idempotency_key(Some(&value)).is_err());
+    }
+
+    #[tokio::test]
+    async fn limit_request_bodies() {
+        let body = Body::new(http_body_util::Limited::new(Body::from("0123456789"), 5));
+        let err = to_bytes(body, MAX_BODY_LEN).await.unwrap_err();
+        assert!(matches!(body_error(err), Error::TooLarge { .. }));
+        let body = Body::from(vec![0; MAX_BODY_LEN + 1]);
+        let err = to_bytes(body, MAX_BODY_LEN).await.unwrap_err();
+        assert!(matches!(body_error(err), Error::TooLarge { .. }));
+    }
+
+    #[test]
+    fn fingerprint_requests() {
+        let body = br#"{"name":"Books To Read"}"#;
+        let fp = fingerprint(&Method::POST, "/stories", None, body);
+        assert_eq!(fp, fingerprint(&Method::POST, "/stories", None, body));
+        assert_ne!(fp, fingerprint(&Method::POST, "/tasks", None, body));
+        assert_ne!(fp, fingerprint(&Method::POST, "/stories", None, b"{}"));
+    }
+
+    #[test]
+    fn fingerprint_multipart_requests() {
+        let content_type = "multipart/form-data; boundary=\"XyZ\"";
+        let boundary = multipart_boundary(content_type);
+        assert_eq!(boundary, Some("XyZ"));
+        assert_eq!(multipart_boundary("application/json"), None);
+
+        // Changing only the boundary doesn't change the fingerprint
+        let path = "/stories/1/files";
+        let fp = fingerprint(&Method::POST, path, boundary, b"--XyZ\r\nabc\r\n--XyZ--");
+        let other = fingerprint(&Method::POST, path, Some("AbC"), b"--AbC\r\nabc\r\n--AbC--");
+        assert_eq!(fp, other);
+        let other = fingerprint(&Method::POST, path, Some("AbC"), b"--AbC\r\nxyz\r\n--AbC--");
+        assert_ne!(fp, other);
+    }
+}
@@ -1,4 +1,4 @@
-use axum::Router;
+use axum::{middleware, Router};
 use std::sync::Arc;
 use utoipa::{openapi::OpenApi as OpenApiDocs, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
@@ -6,8 +6,12 @@ use utoipa_swagger_ui::SwaggerUi;
 mod ctx;
 pub use ctx::Ctx;
 mod dto;
+mod idempotency;
 mod routes;
-use routes::{file, status, story, task};
+use routes::{
+    activity, comment, dependency, event, file, label, report, search, status, story, task,
+    template, trash, webhook, workflow,
+};
 mod tracer;
 
 /// The top-level API
@@ -23,13 +27,26 @@ impl Api {
 
     /// Create an API service by merging internal routes with context pointer state.
     pub fn mk_service(self) -> Router {
+        let guard = middleware::from_fn_with_state(Arc::clone(&self.ctx), idempotency::guard);
         tracer::wrap(
             Router::new()
                 .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", docs()))
                 .merge(status::routes())
                 .merge(story::routes())
                 .merge(file::routes())
-                .merge(task::routes()),
+                .merge(task::routes())
+                .merge(event::routes())
+                .merge(webhook::routes())
+                .merge(activity::routes())
+                .merge(trash::routes())
+                .merge(search::routes())
+                .merge(workflow::routes())
+                .merge(label::routes())
+                .merge(dependency::routes())
+                .merge(comment::routes())
+                .merge(report::routes())
+                .merge(template::routes())
+                .layer(guard),
         )
         .with_state(self.ctx)
     }
@@ -40,5 +57,16 @@ pub fn docs() -> OpenApiDocs {
     let mut api = story::ApiDoc::openapi();
     api.merge(file::ApiDoc::openapi());
     api.merge(task::ApiDoc::openapi());
+    api.merge(event::ApiDoc::openapi());
+    api.merge(webhook::ApiDoc::openapi());
+    api.merge(activity::ApiDoc::openapi());
+    api.merge(trash::ApiDoc::openapi());
+    api.merge(search::ApiDoc::openapi());
+    api.merge(workflow::ApiDoc::openapi());
+    api.merge(label::ApiDoc::openapi());
+    api.merge(dependency::ApiDoc::openapi());
+    api.merge(comment::ApiDoc::openapi());
+    api.merge(report::ApiDoc::openapi());
+    api.merge(template::ApiDoc::openapi());
     api
 }
@@ -0,0 +1,70 @@
+use crate::{
+    action::activity::GetActivity,
+    api::dto::{Activities, PageCodec, PageParams},
+    api::Ctx,
+    domain::Activity,
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for activity routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_activity),
+    components(schemas(Activities, Activity, Errors)),
+    tags((name = "Activity"))
+)]
+pub struct ApiDoc;
+
+/// API routes for activity
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new().route("/stories/:story_id/activity", get(get_activity))
+}
+
+/// Get a page of activity for a story
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/activity",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of activity records per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of activity, oldest first", body = Activities),
+        (status = 400, description = "The page token was invalid or expired", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Activity"
+)]
+async fn get_activity(
+    params: Option<Query<PageParams>>,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let q = params.unwrap_or_default();
+    let codec = PageCodec::new(&ctx.config, format!("stories/{story_id}/activity"));
+    let cursor = codec.decode_or(&q.page_token, 1)?;
+    let (next_cursor, activity) =
+        GetActivity::execute(ctx, story_id, cursor, q.page_size()).await?;
+    let resp = Activities::new(codec.encode(next_cursor), activity);
+    Ok(Json(resp))
+}
@@ -0,0 +1,314 @@
+use crate::{
+    action::comment::{
+        CreateStoryComment, CreateTaskComment, DeleteComment, GetComment, GetCommentRevisions,
+        GetStoryComments, GetTaskComments, UpdateComment,
+    },
+    api::dto::{
+        Actor, CommentRevisions, Comments, CreateCommentRequest, PageCodec, PageParams,
+        UpdateCommentRequest,
+    },
+    api::Ctx,
+    domain::{Comment, CommentRevision},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for comment routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        get_story_comments,
+        create_story_comment,
+        get_task_comments,
+        create_task_comment,
+        get_comment,
+        update_comment,
+        delete_comment,
+        get_comment_revisions
+    ),
+    components(schemas(
+        Comment,
+        CommentRevision,
+        CommentRevisions,
+        Comments,
+        CreateCommentRequest,
+        Errors,
+        UpdateCommentRequest
+    )),
+    tags((name = "Comment"))
+)]
+pub struct ApiDoc;
+
+/// API routes for comments
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/stories/:story_id/comments", get(get_story_comments).post(create_story_comment))
+        .route("/tasks/:task_id/comments", get(get_task_comments).post(create_task_comment))
+        .route("/comments/:comment_id", get(get_comment).patch(update_comment).delete(delete_comment))
+        .route("/comments/:comment_id/revisions", get(get_comment_revisions))
+}
+
+/// Get a page of the comments on a story, not including task comments
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/comments",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of comments per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of comments and replies, oldest first", body = Comments),
+        (status = 400, description = "The page token was invalid or expired", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn get_story_comments(
+    params: Option<Query<PageParams>>,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let q = params.unwrap_or_default();
+    let codec = PageCodec::new(&ctx.config, format!("stories/{story_id}/comments"));
+    let cursor = codec.decode_or(&q.page_token, 1)?;
+    let (next_cursor, comments) =
+        GetStoryComments::execute(ctx, story_id, cursor, q.page_size()).await?;
+    let resp = Comments::new(codec.encode(next_cursor), comments);
+    Ok(Json(resp))
+}
+
+/// Comment on a story
+#[utoipa::path(
+    post,
+    path = "/stories/{story_id}/comments",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("X-User-Id" = String, Header, description = "The comment author")
+    ),
+    request_body = CreateCommentRequest,
+    responses(
+        (status = 201, description = "The comment was created", body = Comment),
+        (status = 400, description = "The user id or request body was invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn create_story_comment(
+    actor: Actor,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<CreateCommentRequest>,
+) -> Result<impl IntoResponse> {
+    let author = actor.user_id()?;
+    let (reply_to_id, body) = req.validate()?;
+    let comment = CreateStoryComment::execute(ctx, author, story_id, reply_to_id, body).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// Get a page of the comments on a task
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/comments",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of comments per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of comments and replies, oldest first", body = Comments),
+        (status = 400, description = "The page token was invalid or expired", body = Errors),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn get_task_comments(
+    params: Option<Query<PageParams>>,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let q = params.unwrap_or_default();
+    let codec = PageCodec::new(&ctx.config, format!("tasks/{task_id}/comments"));
+    let cursor = codec.decode_or(&q.page_token, 1)?;
+    let (next_cursor, comments) =
+        GetTaskComments::execute(ctx, task_id, cursor, q.page_size()).await?;
+    let resp = Comments::new(codec.encode(next_cursor), comments);
+    Ok(Json(resp))
+}
+
+/// Comment on a task
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/comments",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = String, Header, description = "The comment author")
+    ),
+    request_body = CreateCommentRequest,
+    responses(
+        (status = 201, description = "The comment was created", body = Comment),
+        (status = 400, description = "The user id or request body was invalid", body = Errors),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn create_task_comment(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<CreateCommentRequest>,
+) -> Result<impl IntoResponse> {
+    let author = actor.user_id()?;
+    let (reply_to_id, body) = req.validate()?;
+    let comment = CreateTaskComment::execute(ctx, author, task_id, reply_to_id, body).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// Get a comment
+#[utoipa::path(
+    get,
+    path = "/comments/{comment_id}",
+    params(("comment_id" = Uuid, Path, description = "The comment id")),
+    responses(
+        (status = 200, description = "The comment", body = Comment),
+        (status = 404, description = "The comment was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn get_comment(
+    Path(comment_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Json<Comment>> {
+    let comment = GetComment::execute(ctx, comment_id).await?;
+    Ok(Json(comment))
+}
+
+/// Edit a comment, keeping its previous body in the revision history
+#[utoipa::path(
+    patch,
+    path = "/comments/{comment_id}",
+    params(
+        ("comment_id" = Uuid, Path, description = "The comment id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded as the editor",
+            nullable
+        )
+    ),
+    request_body = UpdateCommentRequest,
+    responses(
+        (status = 200, description = "The comment was updated", body = Comment),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The comment was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn update_comment(
+    actor: Actor,
+    Path(comment_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<UpdateCommentRequest>,
+) -> Result<Json<Comment>> {
+    let body = req.validate()?;
+    let comment = UpdateComment::execute(ctx, &actor.0, comment_id, body).await?;
+    Ok(Json(comment))
+}
+
+/// Delete a comment and its replies
+#[utoipa::path(
+    delete,
+    path = "/comments/{comment_id}",
+    params(
+        ("comment_id" = Uuid, Path, description = "The comment id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The comment was deleted"),
+        (status = 404, description = "The comment was not found")
+    ),
+    tag = "Comment"
+)]
+async fn delete_comment(
+    actor: Actor,
+    Path(comment_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> StatusCode {
+    if let Err(err) = DeleteComment::execute(ctx, &actor.0, comment_id).await {
+        return StatusCode::from(err);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Get a page of the edit history of a comment
+#[utoipa::path(
+    get,
+    path = "/comments/{comment_id}/revisions",
+    params(
+        ("comment_id" = Uuid, Path, description = "The comment id"),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of revisions per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of previous bodies, oldest first", body = CommentRevisions),
+        (status = 400, description = "The page token was invalid or expired", body = Errors),
+        (status = 404, description = "The comment was not found", body = Errors)
+    ),
+    tag = "Comment"
+)]
+async fn get_comment_revisions(
+    params: Option<Query<PageParams>>,
+    Path(comment_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let q = params.unwrap_or_default();
+    let codec = PageCodec::new(&ctx.config, format!("comments/{comment_id}/revisions"));
+    let cursor = codec.decode_or(&q.page_token, 1)?;
+    let (next_cursor, revisions) =
+        GetCommentRevisions::execute(ctx, comment_id, cursor, q.page_size()).await?;
+    let resp = CommentRevisions::new(codec.encode(next_cursor), revisions);
+    Ok(Json(resp))
+}
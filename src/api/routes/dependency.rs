@@ -0,0 +1,134 @@
+use crate::{
+    action::dependency::{
+        AddDependency, GetDependencies, GetTasksByDependencies, RemoveDependency,
+    },
+    api::dto::Actor,
+    api::Ctx,
+    domain::{Task, TaskDependencies},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, put},
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for task dependency routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_dependencies, add_dependency, remove_dependency, get_sorted_tasks),
+    components(schemas(Errors, Task, TaskDependencies)),
+    tags((name = "Dependency"))
+)]
+pub struct ApiDoc;
+
+/// API routes for task dependencies
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/tasks/:task_id/dependencies", get(get_dependencies))
+        .route("/tasks/:task_id/dependencies/:depends_on_id", put(add_dependency).delete(remove_dependency))
+        .route("/stories/:story_id/tasks/sorted", get(get_sorted_tasks))
+}
+
+/// Get the tasks a task depends on, and the tasks that depend on it
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/dependencies",
+    params(("task_id" = Uuid, Path, description = "The task id")),
+    responses(
+        (status = 200, description = "The task dependencies", body = TaskDependencies),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Dependency"
+)]
+async fn get_dependencies(
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let dependencies = GetDependencies::execute(ctx, task_id).await?;
+    Ok(Json(dependencies))
+}
+
+/// Make a task depend on another task, possibly in another story
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}/dependencies/{depends_on_id}",
+    params(
+        ("task_id" = Uuid, Path, description = "The blocked task id"),
+        ("depends_on_id" = Uuid, Path, description = "The id of the task to complete first"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The task depends on the other task"),
+        (status = 400, description = "The task can't depend on itself", body = Errors),
+        (status = 404, description = "A task was not found", body = Errors),
+        (status = 409, description = "The dependency would form a cycle", body = Errors)
+    ),
+    tag = "Dependency"
+)]
+async fn add_dependency(
+    actor: Actor,
+    Path((task_id, depends_on_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<StatusCode> {
+    AddDependency::execute(ctx, &actor.0, task_id, depends_on_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stop a task depending on another task
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/dependencies/{depends_on_id}",
+    params(
+        ("task_id" = Uuid, Path, description = "The blocked task id"),
+        ("depends_on_id" = Uuid, Path, description = "The id of the task to complete first"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The task doesn't depend on the other task"),
+        (status = 404, description = "A task was not found", body = Errors)
+    ),
+    tag = "Dependency"
+)]
+async fn remove_dependency(
+    actor: Actor,
+    Path((task_id, depends_on_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<StatusCode> {
+    RemoveDependency::execute(ctx, &actor.0, task_id, depends_on_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the tasks of a story, each after the tasks it depends on
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/tasks/sorted",
+    params(("story_id" = Uuid, Path, description = "The story id")),
+    responses(
+        (status = 200, description = "The story tasks, blockers first", body = [Task]),
+        (status = 404, description = "The story was not found", body = Errors),
+        (status = 422, description = "The story has too many tasks to sort", body = Errors)
+    ),
+    tag = "Dependency"
+)]
+async fn get_sorted_tasks(
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let tasks = GetTasksByDependencies::execute(ctx, story_id).await?;
+    Ok(Json(tasks))
+}
@@ -0,0 +1,142 @@
+use crate::{
+    action::event::StreamEvents,
+    api::dto::EventParams,
+    api::Ctx,
+    domain::{Event, EventKind},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::HeaderMap,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::get,
+    Router,
+};
+use futures_util::stream::{BoxStream, StreamExt};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for event routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(stream_events, stream_events_ws),
+    components(schemas(Errors, Event, EventKind)),
+    tags((name = "Event"))
+)]
+pub struct ApiDoc;
+
+/// API routes for story events
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/stories/:story_id/events", get(stream_events))
+        .route("/stories/:story_id/events/ws", get(stream_events_ws))
+}
+
+/// Stream story events as server-sent events
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/events",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("Last-Event-ID" = Option<i64>,
+            Header,
+            description = "Replay events after this event id",
+            nullable
+        ),
+        ("last_event_id" = Option<i64>,
+            Query,
+            description = "Replay events after this event id (when the header can't be set)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A stream of story events", body = Event, content_type = "text/event-stream"),
+        (status = 400, description = "The last event id was invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Event"
+)]
+async fn stream_events(
+    params: Option<Query<EventParams>>,
+    headers: HeaderMap,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let last_event_id = params.unwrap_or_default().last_event_id(&headers)?;
+    let events = StreamEvents::execute(ctx, story_id, last_event_id).await?;
+    let stream = events.map(|e| {
+        SseEvent::default()
+            .id(e.id.to_string())
+            .event(&e.kind)
+            .json_data(e.as_ref())
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Stream story events over a websocket
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/events/ws",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("last_event_id" = Option<i64>,
+            Query,
+            description = "Replay events after this event id",
+            nullable
+        )
+    ),
+    responses(
+        (status = 101, description = "Switched to a websocket sending story events as json"),
+        (status = 400, description = "The last event id was invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Event"
+)]
+async fn stream_events_ws(
+    ws: WebSocketUpgrade,
+    params: Option<Query<EventParams>>,
+    headers: HeaderMap,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let last_event_id = params.unwrap_or_default().last_event_id(&headers)?;
+    let events = StreamEvents::execute(ctx, story_id, last_event_id).await?;
+    Ok(ws.on_upgrade(move |socket| forward(socket, events)))
+}
+
+/// Send events to a websocket until either side closes.
+async fn forward(mut socket: WebSocket, mut events: BoxStream<'static, Arc<Event>>) {
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else {
+                    break;
+                };
+                let text = match serde_json::to_string(event.as_ref()) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        tracing::error!("unable to serialize event {}: {}", event.id, err);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use crate::{
     action::file::{AddFiles, DeleteFile, DownloadFile, GetFile, GetFiles},
+    api::dto::{Actor, FileParams, Files, PageCodec, PageParams, PageScope},
     api::Ctx,
     domain::StoryFile,
     error::Errors,
     Result,
 };
 use axum::{
-    extract::{Multipart, Path, State},
+    extract::{Multipart, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     routing::get,
@@ -27,7 +28,7 @@ struct FileUpload {
 #[derive(utoipa::OpenApi)]
 #[openapi(
     paths(get_files, add_files, get_file, download_file, delete_file),
-    components(schemas(Errors, FileUpload, StoryFile)),
+    components(schemas(Errors, FileUpload, Files, StoryFile)),
     tags((name = "File"))
 )]
 pub struct ApiDoc;
@@ -41,46 +42,97 @@ pub fn routes() -> Router<Arc<Ctx>> {
         .route("/stories/:story_id/files/:file_id/contents", get(download_file))
 }
 
-/// List files for a story.
+/// List a page of files for a story.
 #[utoipa::path(
     get,
     path = "/stories/{story_id}/files",
-    params(("story_id" = Uuid, Path, description = "The parent story id")),
+    params(
+        ("story_id" = Uuid, Path, description = "The parent story id"),
+        ("sort" = Option<String>,
+            Query,
+            description = "The sort field: name, size, or created_at (default)",
+            nullable
+        ),
+        ("order" = Option<String>,
+            Query,
+            description = "The sort order: asc (default) or desc",
+            nullable
+        ),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of files per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response, with the same sort)",
+            nullable
+        )
+    ),
     responses(
-        (status = 200, description = "A file metadata array for the story", body = [StoryFile]),
+        (status = 200, description = "A page of file metadata for the story", body = Files),
+        (status = 400, description = "The query params were invalid", body = Errors),
         (status = 404, description = "The parent story was not found", body = Errors)
     ),
     tag = "File"
 )]
 async fn get_files(
+    params: Option<Query<FileParams>>,
+    page: Option<Query<PageParams>>,
     Path(story_id): Path<Uuid>,
     State(ctx): State<Arc<Ctx>>,
 ) -> Result<impl IntoResponse> {
-    let files = GetFiles::execute(ctx, story_id).await?;
-    Ok(Json(files))
+    let (sort, order) = params.unwrap_or_default().validate()?;
+    let q = page.unwrap_or_default();
+    let scope = PageScope::new(format!("stories/{story_id}/files"))
+        .param("sort", Some(sort))
+        .param("order", Some(order));
+    let codec = PageCodec::new(&ctx.config, scope.into());
+    let after = codec.decode_keyset(&q.page_token)?;
+    let (next_keyset, files) =
+        GetFiles::execute(ctx, story_id, sort, order, after, q.page_size()).await?;
+    let resp = Files::new(codec.encode_keyset(next_keyset), files);
+    Ok(Json(resp))
 }
 
 /// Add files to a story.
 #[utoipa::path(
     post,
     path = "/stories/{story_id}/files",
-    params(("story_id" = Uuid, Path, description = "The parent story id")),
+    params(
+        ("story_id" = Uuid, Path, description = "The parent story id"),
+        ("Idempotency-Key" = Option<String>,
+            Header,
+            description = "A client generated key that makes retries safe",
+            nullable
+        ),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     request_body(
         content_type = "multipart/form-data",
         content = FileUpload,
     ),
     responses(
         (status = 201, description = "A metadata array for the uploaded files", body = [StoryFile]),
-        (status = 404, description = "The parent story was not found", body = Errors)
+        (status = 404, description = "The parent story was not found", body = Errors),
+        (status = 409, description = "A request with the same key is in progress", body = Errors),
+        (status = 422, description = "The key was used with a different request", body = Errors)
     ),
     tag = "File"
 )]
 async fn add_files(
+    actor: Actor,
     Path(story_id): Path<Uuid>,
     State(ctx): State<Arc<Ctx>>,
     multipart: Multipart,
 ) -> Result<impl IntoResponse> {
-    let files = AddFiles::execute(ctx, story_id, multipart).await?;
+    let files = AddFiles::execute(ctx, &actor.0, story_id, multipart).await?;
     Ok((StatusCode::CREATED, Json(files)))
 }
 
@@ -134,7 +186,12 @@ async fn get_file(
     path = "/stories/{story_id}/files/{file_id}",
     params(
         ("story_id" = Uuid, Path, description = "The parent story id"),
-        ("file_id" = Uuid, Path, description = "The id of the file to delete")
+        ("file_id" = Uuid, Path, description = "The id of the file to delete"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
     ),
     responses(
         (status = 204, description = "The file was deleted successfully"),
@@ -143,10 +200,11 @@ async fn get_file(
     tag = "File"
 )]
 async fn delete_file(
+    actor: Actor,
     Path((story_id, file_id)): Path<(Uuid, Uuid)>,
     State(ctx): State<Arc<Ctx>>,
 ) -> StatusCode {
-    if let Err(err) = DeleteFile::execute(ctx, story_id, file_id).await {
+    if let Err(err) = DeleteFile::execute(ctx, &actor.0, story_id, file_id).await {
         return StatusCode::from(err);
     }
     StatusCode::NO_CONTENT
@@ -0,0 +1,299 @@
+use crate::{
+    action::label::{
+        AddStoryLabel, AddTaskLabel, CreateLabel, DeleteLabel, GetLabel, GetLabels, GetStoryLabels,
+        GetTaskLabels, RemoveStoryLabel, RemoveTaskLabel, UpdateLabel,
+    },
+    api::dto::{Actor, LabelRequest},
+    api::Ctx,
+    domain::Label,
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, put},
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for label routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        get_labels,
+        get_label,
+        create_label,
+        update_label,
+        delete_label,
+        get_story_labels,
+        add_story_label,
+        remove_story_label,
+        get_task_labels,
+        add_task_label,
+        remove_task_label
+    ),
+    components(schemas(Errors, Label, LabelRequest)),
+    tags((name = "Label"))
+)]
+pub struct ApiDoc;
+
+/// API routes for labels
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/labels", get(get_labels).post(create_label))
+        .route("/labels/:label_id", get(get_label).delete(delete_label).patch(update_label))
+        .route("/stories/:story_id/labels", get(get_story_labels))
+        .route("/stories/:story_id/labels/:label_id", put(add_story_label).delete(remove_story_label))
+        .route("/tasks/:task_id/labels", get(get_task_labels))
+        .route("/tasks/:task_id/labels/:label_id", put(add_task_label).delete(remove_task_label))
+}
+
+/// Get all labels
+#[utoipa::path(
+    get,
+    path = "/labels",
+    responses(
+        (status = 200, description = "The labels, in name order", body = [Label])
+    ),
+    tag = "Label"
+)]
+async fn get_labels(State(ctx): State<Arc<Ctx>>) -> Result<impl IntoResponse> {
+    let labels = GetLabels::execute(ctx).await?;
+    Ok(Json(labels))
+}
+
+/// Get a label
+#[utoipa::path(
+    get,
+    path = "/labels/{label_id}",
+    params(("label_id" = Uuid, Path, description = "The label id")),
+    responses(
+        (status = 200, description = "The label", body = Label),
+        (status = 404, description = "The label was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn get_label(
+    Path(label_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let label = GetLabel::execute(ctx, label_id).await?;
+    Ok(Json(label))
+}
+
+/// Create a label
+#[utoipa::path(
+    post,
+    path = "/labels",
+    request_body = LabelRequest,
+    responses(
+        (status = 201, description = "The label was created", body = Label),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 409, description = "A label with the same name exists", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn create_label(
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<LabelRequest>,
+) -> Result<impl IntoResponse> {
+    let (name, color) = req.validate()?;
+    let label = CreateLabel::execute(ctx, name, color).await?;
+    Ok((StatusCode::CREATED, Json(label)))
+}
+
+/// Update a label
+#[utoipa::path(
+    patch,
+    path = "/labels/{label_id}",
+    params(("label_id" = Uuid, Path, description = "The label id")),
+    request_body = LabelRequest,
+    responses(
+        (status = 200, description = "The label was updated", body = Label),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The label was not found", body = Errors),
+        (status = 409, description = "A label with the same name exists", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn update_label(
+    Path(label_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<LabelRequest>,
+) -> Result<Json<Label>> {
+    let (name, color) = req.validate()?;
+    let label = UpdateLabel::execute(ctx, label_id, name, color).await?;
+    Ok(Json(label))
+}
+
+/// Delete a label, removing it from all stories and tasks
+#[utoipa::path(
+    delete,
+    path = "/labels/{label_id}",
+    params(("label_id" = Uuid, Path, description = "The label id")),
+    responses(
+        (status = 204, description = "The label was deleted"),
+        (status = 404, description = "The label was not found")
+    ),
+    tag = "Label"
+)]
+async fn delete_label(Path(label_id): Path<Uuid>, State(ctx): State<Arc<Ctx>>) -> StatusCode {
+    if let Err(err) = DeleteLabel::execute(ctx, label_id).await {
+        return StatusCode::from(err);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Get the labels of a story
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/labels",
+    params(("story_id" = Uuid, Path, description = "The story id")),
+    responses(
+        (status = 200, description = "The story labels, in name order", body = [Label]),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn get_story_labels(
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let labels = GetStoryLabels::execute(ctx, story_id).await?;
+    Ok(Json(labels))
+}
+
+/// Add a label to a story
+#[utoipa::path(
+    put,
+    path = "/stories/{story_id}/labels/{label_id}",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("label_id" = Uuid, Path, description = "The label id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The story has the label"),
+        (status = 404, description = "The story or label was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn add_story_label(
+    actor: Actor,
+    Path((story_id, label_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<StatusCode> {
+    AddStoryLabel::execute(ctx, &actor.0, story_id, label_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a label from a story
+#[utoipa::path(
+    delete,
+    path = "/stories/{story_id}/labels/{label_id}",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("label_id" = Uuid, Path, description = "The label id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The story doesn't have the label"),
+        (status = 404, description = "The story or label was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn remove_story_label(
+    actor: Actor,
+    Path((story_id, label_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<StatusCode> {
+    RemoveStoryLabel::execute(ctx, &actor.0, story_id, label_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the labels of a task
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/labels",
+    params(("task_id" = Uuid, Path, description = "The task id")),
+    responses(
+        (status = 200, description = "The task labels, in name order", body = [Label]),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn get_task_labels(
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let labels = GetTaskLabels::execute(ctx, task_id).await?;
+    Ok(Json(labels))
+}
+
+/// Add a label to a task
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}/labels/{label_id}",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("label_id" = Uuid, Path, description = "The label id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The task has the label"),
+        (status = 404, description = "The task or label was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn add_task_label(
+    actor: Actor,
+    Path((task_id, label_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<StatusCode> {
+    AddTaskLabel::execute(ctx, &actor.0, task_id, label_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a label from a task
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/labels/{label_id}",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("label_id" = Uuid, Path, description = "The label id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 204, description = "The task doesn't have the label"),
+        (status = 404, description = "The task or label was not found", body = Errors)
+    ),
+    tag = "Label"
+)]
+async fn remove_task_label(
+    actor: Actor,
+    Path((task_id, label_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<StatusCode> {
+    RemoveTaskLabel::execute(ctx, &actor.0, task_id, label_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,133 @@
+use crate::{
+    action::report::{GetBurndown, GetCycleTime},
+    api::dto::{burndown_csv, cycle_time_csv, ReportFormat, ReportParams},
+    api::Ctx,
+    domain::{Burndown, CycleTime, DailyTasks},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Query, State},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// The content type of csv reports.
+const TEXT_CSV: &str = "text/csv; charset=utf-8";
+
+/// OpenApi docs for report routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_burndown, get_cycle_time),
+    components(schemas(Burndown, CycleTime, DailyTasks, Errors)),
+    tags((name = "Report"))
+)]
+pub struct ApiDoc;
+
+/// API routes for reports
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/reports/burndown", get(get_burndown))
+        .route("/reports/cycle-time", get(get_cycle_time))
+}
+
+/// Get daily created and completed task counts, and the tasks left open at the end of each day
+#[utoipa::path(
+    get,
+    path = "/reports/burndown",
+    params(
+        ("story_id" = Option<Uuid>,
+            Query,
+            description = "Only tasks in this story, instead of all stories",
+            nullable
+        ),
+        ("from" = Option<String>,
+            Query,
+            description = "The first UTC date, defaulting to 29 days before the last date",
+            nullable
+        ),
+        ("to" = Option<String>,
+            Query,
+            description = "The last UTC date, defaulting to today. At most 366 days are reported",
+            nullable
+        ),
+        ("format" = Option<String>,
+            Query,
+            description = "The response format: json (default) or csv",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "Daily task counts", body = Burndown, content_type = ["application/json", "text/csv"]),
+        (status = 400, description = "The query params were invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Report"
+)]
+async fn get_burndown(
+    params: Option<Query<ReportParams>>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Response> {
+    let today = Utc::now().date_naive();
+    let (story_id, from, to, format) = params.unwrap_or_default().validate(today)?;
+    let burndown = GetBurndown::execute(ctx, story_id, from, to).await?;
+    let resp = match format {
+        ReportFormat::Json => Json(burndown).into_response(),
+        ReportFormat::Csv => ([(CONTENT_TYPE, TEXT_CSV)], burndown_csv(&burndown)).into_response(),
+    };
+    Ok(resp)
+}
+
+/// Get percentiles of the time from creating to closing tasks, for tasks closed over a range
+/// of dates
+#[utoipa::path(
+    get,
+    path = "/reports/cycle-time",
+    params(
+        ("story_id" = Option<Uuid>,
+            Query,
+            description = "Only tasks in this story, instead of all stories",
+            nullable
+        ),
+        ("from" = Option<String>,
+            Query,
+            description = "The first UTC date, defaulting to 29 days before the last date",
+            nullable
+        ),
+        ("to" = Option<String>,
+            Query,
+            description = "The last UTC date, defaulting to today. At most 366 days are reported",
+            nullable
+        ),
+        ("format" = Option<String>,
+            Query,
+            description = "The response format: json (default) or csv",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "Cycle time percentiles in seconds", body = CycleTime, content_type = ["application/json", "text/csv"]),
+        (status = 400, description = "The query params were invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Report"
+)]
+async fn get_cycle_time(
+    params: Option<Query<ReportParams>>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Response> {
+    let today = Utc::now().date_naive();
+    let (story_id, from, to, format) = params.unwrap_or_default().validate(today)?;
+    let cycle_time = GetCycleTime::execute(ctx, story_id, from, to).await?;
+    let resp = match format {
+        ReportFormat::Json => Json(cycle_time).into_response(),
+        ReportFormat::Csv => {
+            ([(CONTENT_TYPE, TEXT_CSV)], cycle_time_csv(&cycle_time)).into_response()
+        }
+    };
+    Ok(resp)
+}
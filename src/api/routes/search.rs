@@ -0,0 +1,72 @@
+use crate::{
+    action::search::Search,
+    api::dto::{PageCodec, PageParams, PageScope, SearchParams, SearchResults},
+    api::Ctx,
+    domain::SearchHit,
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+
+/// OpenApi docs for search routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(search),
+    components(schemas(SearchResults, SearchHit, Errors)),
+    tags((name = "Search"))
+)]
+pub struct ApiDoc;
+
+/// API routes for search
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new().route("/search", get(search))
+}
+
+/// Search story, task, and file names
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(
+        ("q" = String,
+            Query,
+            description = "The search terms: words, \"quoted phrases\", or and -excluded words"
+        ),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of results per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of results, best matches first", body = SearchResults),
+        (status = 400, description = "The query params were invalid", body = Errors)
+    ),
+    tag = "Search"
+)]
+async fn search(
+    params: Option<Query<SearchParams>>,
+    page: Option<Query<PageParams>>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let q = params.unwrap_or_default().validate()?;
+    let p = page.unwrap_or_default();
+    let scope = PageScope::new("search").param("q", Some(&q));
+    let codec = PageCodec::new(&ctx.config, scope.into());
+    let cursor = codec.decode_or(&p.page_token, 1)?;
+    let (next_cursor, results) = Search::execute(ctx, &q, cursor, p.page_size()).await?;
+    let resp = SearchResults::new(codec.encode(next_cursor), results);
+    Ok(Json(resp))
+}
@@ -1,17 +1,23 @@
 use crate::{
-    action::story::{CreateStory, DeleteStory, GetStories, GetStory, UpdateStory},
+    action::story::{
+        CloneStory, CountStories, CreateStory, DeleteStory, GetStories, GetStory,
+        GetStorySummaries, GetStorySummary, RenderStoryDescription, UpdateStory,
+    },
     action::task::GetTasks,
-    api::dto::{PageParams, PageToken, Stories, StoryRequest, TaskParams},
+    api::dto::{
+        Actor, CloneStoryRequest, PageCodec, PageParams, PageScope, Stories, StoryParams,
+        StoryRequest, TaskParams, Tasks,
+    },
     api::Ctx,
-    domain::{Status, Story, Task},
+    domain::{Seek, Story, StorySummary, Task},
     error::Errors,
     Result,
 };
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
-    routing::get,
+    response::{Html, IntoResponse},
+    routing::{get, post},
     Json, Router,
 };
 use std::sync::Arc;
@@ -20,8 +26,18 @@ use uuid::Uuid;
 /// OpenApi docs for story routes
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_story, get_stories, get_tasks, create_story, update_story, delete_story),
-    components(schemas(Errors, Status, Stories, Story, StoryRequest, Task)),
+    paths(
+        get_story,
+        get_stories,
+        get_tasks,
+        get_story_summary,
+        get_story_description,
+        create_story,
+        clone_story,
+        update_story,
+        delete_story
+    ),
+    components(schemas(CloneStoryRequest, Errors, Stories, Story, StoryRequest, StorySummary, Task, Tasks)),
     tags((name = "Story"))
 )]
 pub struct ApiDoc;
@@ -33,6 +49,9 @@ pub fn routes() -> Router<Arc<Ctx>> {
         .route("/stories", get(get_stories).post(create_story))
         .route("/stories/:story_id", get(get_story).delete(delete_story).patch(update_story))
         .route("/stories/:story_id/tasks", get(get_tasks))
+        .route("/stories/:story_id/summary", get(get_story_summary))
+        .route("/stories/:story_id/description", get(get_story_description))
+        .route("/stories/:story_id/clone", post(clone_story))
 }
 
 /// Get a story
@@ -68,68 +87,287 @@ async fn get_story(
         ),
         ("page_token" = Option<String>,
             Query,
-            description = "The page cursor (next_page from response)",
+            description = "The page cursor (a next, prev, first or last page from response)",
+            nullable
+        ),
+        ("name" = Option<String>,
+            Query,
+            description = "Only stories with names containing this text (case insensitive)",
+            nullable
+        ),
+        ("created_after" = Option<String>,
+            Query,
+            description = "Only stories created at or after this RFC 3339 time",
+            nullable
+        ),
+        ("created_before" = Option<String>,
+            Query,
+            description = "Only stories created before this RFC 3339 time",
+            nullable
+        ),
+        ("updated_after" = Option<String>,
+            Query,
+            description = "Only stories updated at or after this RFC 3339 time",
+            nullable
+        ),
+        ("updated_before" = Option<String>,
+            Query,
+            description = "Only stories updated before this RFC 3339 time",
+            nullable
+        ),
+        ("has_incomplete_tasks" = Option<bool>,
+            Query,
+            description = "Only stories with (true) or without (false) incomplete tasks",
+            nullable
+        ),
+        ("label" = Option<String>,
+            Query,
+            description = "Only stories with these comma separated label names (case insensitive)",
+            nullable
+        ),
+        ("label_match" = Option<String>,
+            Query,
+            description = "Whether stories need all (default) or any of the labels",
+            nullable
+        ),
+        ("sort" = Option<String>,
+            Query,
+            description = "The sort field: name, created_at (default) or updated_at",
+            nullable
+        ),
+        ("order" = Option<String>,
+            Query,
+            description = "The sort order: asc (default) or desc",
+            nullable
+        ),
+        ("include_total" = Option<bool>,
+            Query,
+            description = "Whether to count matching stories, estimated for large tables",
+            nullable
+        ),
+        ("include" = Option<String>,
+            Query,
+            description = "Extra data to include: stats, for task and file totals per story",
             nullable
         )
     ),
     responses(
-        (status = 200, description = "A page of stories", body = Stories)
+        (status = 200, description = "A page of stories", body = Stories),
+        (status = 400, description = "The query params were invalid", body = Errors)
     ),
     tag = "Story"
 )]
 async fn get_stories(
-    params: Option<Query<PageParams>>,
+    params: Option<Query<StoryParams>>,
+    page: Option<Query<PageParams>>,
     State(ctx): State<Arc<Ctx>>,
 ) -> Result<impl IntoResponse> {
-    tracing::debug!("params: {:?}", params);
-    let q = params.unwrap_or_default();
-    let cursor = PageToken::decode_or(&q.page_token, 1)?;
-    let (next_cursor, stories) = GetStories::execute(ctx, cursor, q.page_size()).await?;
-    let resp = Stories::new(PageToken::encode(next_cursor), stories);
+    tracing::debug!("params: {:?}, page: {:?}", params, page);
+    let (filter, sort, order, include_total, include_stats) =
+        params.unwrap_or_default().validate()?;
+    let q = page.unwrap_or_default();
+    let scope = PageScope::new("stories")
+        .story_filter(&filter)
+        .param("sort", Some(sort))
+        .param("order", Some(order));
+    let codec = PageCodec::new(&ctx.config, scope.into());
+    let seek = codec.decode_seek_or(&q.page_token, Seek::First)?;
+    let (stories, more) =
+        GetStories::execute(Arc::clone(&ctx), &filter, sort, order, &seek, q.page_size()).await?;
+    let mut stats = None;
+    if include_stats {
+        stats = Some(GetStorySummaries::execute(Arc::clone(&ctx), &stories).await?);
+    }
+    let mut resp = Stories::new(&codec, sort, &seek, stories, more);
+    if let Some(stats) = stats {
+        resp = resp.with_stats(stats);
+    }
+    if include_total {
+        let (total, estimated) = CountStories::execute(ctx, &filter).await?;
+        resp = resp.with_total(total, estimated);
+    }
     Ok(Json(resp))
 }
 
-/// Get tasks for a story
+/// Get a page of tasks for a story
 #[utoipa::path(
     get,
     path = "/stories/{story_id}/tasks",
     params(
         ("story_id" = Uuid, Path, description = "The story id"),
-        ("status" = Option<String>, Query, description = "The task status filter", nullable)
+        ("status" = Option<String>,
+            Query,
+            description = "The task status filter, a state of the story workflow",
+            nullable
+        ),
+        ("due_before" = Option<String>,
+            Query,
+            description = "Only tasks due before this RFC 3339 time",
+            nullable
+        ),
+        ("due_after" = Option<String>,
+            Query,
+            description = "Only tasks due at or after this RFC 3339 time",
+            nullable
+        ),
+        ("overdue" = Option<bool>,
+            Query,
+            description = "Only tasks that are (true) or aren't (false) past due and still open",
+            nullable
+        ),
+        ("label" = Option<String>,
+            Query,
+            description = "Only tasks with these comma separated label names (case insensitive)",
+            nullable
+        ),
+        ("label_match" = Option<String>,
+            Query,
+            description = "Whether tasks need all (default) or any of the labels",
+            nullable
+        ),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of tasks per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
     ),
     responses(
-        (status = 200, description = "The tasks for the story", body = [Task]),
+        (status = 200, description = "A page of tasks for the story, in position order", body = Tasks),
+        (status = 400, description = "The query params were invalid", body = Errors),
         (status = 404, description = "The parent story was not found", body = Errors)
     ),
     tag = "Story"
 )]
 async fn get_tasks(
     params: Option<Query<TaskParams>>,
+    page: Option<Query<PageParams>>,
     Path(story_id): Path<Uuid>,
     State(ctx): State<Arc<Ctx>>,
 ) -> Result<impl IntoResponse> {
-    let status = params.unwrap_or_default().status();
-    let tasks = GetTasks::execute(ctx, story_id, status).await?;
-    Ok(Json(tasks))
+    let filter = params.unwrap_or_default().validate()?;
+    let q = page.unwrap_or_default();
+    let scope = PageScope::new(format!("stories/{story_id}/tasks")).task_filter(&filter);
+    let codec = PageCodec::new(&ctx.config, scope.into());
+    let after = codec.decode_keyset(&q.page_token)?;
+    let (next_keyset, tasks) =
+        GetTasks::execute(ctx, story_id, filter, after, q.page_size()).await?;
+    let resp = Tasks::new(codec.encode_keyset(next_keyset), tasks);
+    Ok(Json(resp))
+}
+
+/// Get task counts by status, percent complete, and file totals for a story
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/summary",
+    params(("story_id" = Uuid, Path, description = "The story id")),
+    responses(
+        (status = 200, description = "The story summary", body = StorySummary),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Story"
+)]
+async fn get_story_summary(
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Json<StorySummary>> {
+    let summary = GetStorySummary::execute(ctx, story_id).await?;
+    Ok(Json(summary))
+}
+
+/// Get a story description rendered from markdown as sanitized html
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/description",
+    params(("story_id" = Uuid, Path, description = "The story id")),
+    responses(
+        (status = 200, description = "The html, empty without a description", body = String, content_type = "text/html"),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Story"
+)]
+async fn get_story_description(
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Html<String>> {
+    let html = RenderStoryDescription::execute(ctx, story_id).await?;
+    Ok(Html(html))
 }
 
 /// Create a new story
 #[utoipa::path(
     post,
     path = "/stories",
+    params(
+        ("Idempotency-Key" = Option<String>,
+            Header,
+            description = "A client generated key that makes retries safe",
+            nullable
+        ),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     request_body = StoryRequest,
     responses(
         (status = 201, description = "The story was created", body = Story),
-        (status = 400, description = "The request body was invalid", body = Errors)
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 409, description = "A request with the same key is in progress", body = Errors),
+        (status = 422, description = "The key was used with a different request", body = Errors)
     ),
     tag = "Story"
 )]
 async fn create_story(
+    actor: Actor,
     State(ctx): State<Arc<Ctx>>,
     Json(req): Json<StoryRequest>,
 ) -> Result<impl IntoResponse> {
-    let name = req.validate()?;
-    let story = CreateStory::execute(ctx, name).await?;
+    let changes = req.validate(&ctx.config.story_metadata_schema)?;
+    let story = CreateStory::execute(ctx, &actor.0, changes).await?;
+    Ok((StatusCode::CREATED, Json(story)))
+}
+
+/// Copy a story with its workflow, labels, tasks and optionally files
+#[utoipa::path(
+    post,
+    path = "/stories/{story_id}/clone",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("Idempotency-Key" = Option<String>,
+            Header,
+            description = "A client generated key that makes retries safe",
+            nullable
+        ),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = CloneStoryRequest,
+    responses(
+        (status = 201, description = "The copy was created", body = Story),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Story"
+)]
+async fn clone_story(
+    actor: Actor,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<CloneStoryRequest>,
+) -> Result<impl IntoResponse> {
+    let (name, include_files) = req.validate()?;
+    let story = CloneStory::execute(ctx, &actor.0, story_id, name, include_files).await?;
     Ok((StatusCode::CREATED, Json(story)))
 }
 
@@ -137,7 +375,14 @@ async fn create_story(
 #[utoipa::path(
     patch,
     path = "/stories/{story_id}",
-    params(("story_id" = Uuid, Path, description = "The story id")),
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     request_body = StoryRequest,
     responses(
         (status = 200, description = "The story was updated", body = Story),
@@ -147,12 +392,13 @@ async fn create_story(
     tag = "Story"
 )]
 async fn update_story(
+    actor: Actor,
     Path(story_id): Path<Uuid>,
     State(ctx): State<Arc<Ctx>>,
     Json(req): Json<StoryRequest>,
 ) -> Result<impl IntoResponse> {
-    let name = req.validate()?;
-    let story = UpdateStory::execute(ctx, story_id, name).await?;
+    let changes = req.validate(&ctx.config.story_metadata_schema)?;
+    let story = UpdateStory::execute(ctx, &actor.0, story_id, changes).await?;
     Ok(Json(story))
 }
 
@@ -160,15 +406,26 @@ async fn update_story(
 #[utoipa::path(
     delete,
     path = "/stories/{story_id}",
-    params(("story_id" = Uuid, Path, description = "The story id")),
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     responses(
         (status = 204, description = "The story was deleted"),
         (status = 404, description = "The story was not found")
     ),
     tag = "Story"
 )]
-async fn delete_story(Path(story_id): Path<Uuid>, State(ctx): State<Arc<Ctx>>) -> StatusCode {
-    if let Err(err) = DeleteStory::execute(ctx, story_id).await {
+async fn delete_story(
+    actor: Actor,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> StatusCode {
+    if let Err(err) = DeleteStory::execute(ctx, &actor.0, story_id).await {
         return StatusCode::from(err);
     }
     StatusCode::NO_CONTENT
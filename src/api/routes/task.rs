@@ -1,16 +1,22 @@
 use crate::{
-    action::task::{CreateTask, DeleteTask, GetTask, UpdateTask},
-    api::dto::{CreateTaskRequest, UpdateTaskRequest},
+    action::task::{
+        AssignTask, CreateTask, DeleteTask, GetAssignedTasks, GetSubtasks, GetTask, MoveTask,
+        MoveTasksToStory, SetRecurrence, UpdateTask,
+    },
+    api::dto::{
+        Actor, AssignTaskRequest, CreateTaskRequest, MoveTaskRequest, MoveTasksRequest, PageCodec,
+        PageParams, PageScope, RecurrenceRequest, TaskParams, Tasks, UpdateTaskRequest,
+    },
     api::Ctx,
-    domain::{Status, Task},
+    domain::Task,
     error::Errors,
     Result,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
 use std::sync::Arc;
@@ -19,8 +25,31 @@ use uuid::Uuid;
 /// OpenApi docs for story routes
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_task, create_task, update_task, delete_task),
-    components(schemas(CreateTaskRequest, Errors, Status, Task, UpdateTaskRequest)),
+    paths(
+        get_task,
+        get_subtasks,
+        get_my_tasks,
+        create_task,
+        update_task,
+        move_task,
+        move_tasks_to_story,
+        assign_task,
+        unassign_task,
+        set_recurrence,
+        stop_recurrence,
+        delete_task
+    ),
+    components(schemas(
+        AssignTaskRequest,
+        CreateTaskRequest,
+        Errors,
+        MoveTaskRequest,
+        MoveTasksRequest,
+        RecurrenceRequest,
+        Task,
+        Tasks,
+        UpdateTaskRequest
+    )),
     tags((name = "Task"))
 )]
 pub struct ApiDoc;
@@ -31,6 +60,12 @@ pub fn routes() -> Router<Arc<Ctx>> {
     Router::new()
         .route("/tasks", post(create_task))
         .route("/tasks/:task_id", get(get_task).delete(delete_task).patch(update_task))
+        .route("/tasks/:task_id/subtasks", get(get_subtasks))
+        .route("/tasks/:task_id/move", post(move_task))
+        .route("/stories/:story_id/tasks/move", post(move_tasks_to_story))
+        .route("/tasks/:task_id/assignee", put(assign_task).delete(unassign_task))
+        .route("/tasks/:task_id/recurrence", put(set_recurrence).delete(stop_recurrence))
+        .route("/me/tasks", get(get_my_tasks))
 }
 
 /// Get a task
@@ -49,23 +84,195 @@ async fn get_task(Path(task_id): Path<Uuid>, State(ctx): State<Arc<Ctx>>) -> Res
     Ok(Json(task))
 }
 
+/// Get a page of the subtasks of a task
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/subtasks",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("status" = Option<String>,
+            Query,
+            description = "The task status filter, a workflow state",
+            nullable
+        ),
+        ("due_before" = Option<String>,
+            Query,
+            description = "Only tasks due before this RFC 3339 time",
+            nullable
+        ),
+        ("due_after" = Option<String>,
+            Query,
+            description = "Only tasks due at or after this RFC 3339 time",
+            nullable
+        ),
+        ("overdue" = Option<bool>,
+            Query,
+            description = "Only tasks that are (true) or aren't (false) past due and still open",
+            nullable
+        ),
+        ("label" = Option<String>,
+            Query,
+            description = "Only tasks with these comma separated label names (case insensitive)",
+            nullable
+        ),
+        ("label_match" = Option<String>,
+            Query,
+            description = "Whether tasks need all (default) or any of the labels",
+            nullable
+        ),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of tasks per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of subtasks, in position order", body = Tasks),
+        (status = 400, description = "The query params were invalid", body = Errors),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn get_subtasks(
+    Path(task_id): Path<Uuid>,
+    params: Option<Query<TaskParams>>,
+    page: Option<Query<PageParams>>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let filter = params.unwrap_or_default().validate()?;
+    let q = page.unwrap_or_default();
+    let scope = PageScope::new(format!("tasks/{task_id}/subtasks")).task_filter(&filter);
+    let codec = PageCodec::new(&ctx.config, scope.into());
+    let after = codec.decode_keyset(&q.page_token)?;
+    let (next_keyset, tasks) =
+        GetSubtasks::execute(ctx, task_id, filter, after, q.page_size()).await?;
+    let resp = Tasks::new(codec.encode_keyset(next_keyset), tasks);
+    Ok(Json(resp))
+}
+
+/// Get a page of tasks assigned to the caller, across all stories
+#[utoipa::path(
+    get,
+    path = "/me/tasks",
+    params(
+        ("X-User-Id" = String, Header, description = "The caller whose tasks are listed"),
+        ("status" = Option<String>,
+            Query,
+            description = "The task status filter, a workflow state",
+            nullable
+        ),
+        ("due_before" = Option<String>,
+            Query,
+            description = "Only tasks due before this RFC 3339 time",
+            nullable
+        ),
+        ("due_after" = Option<String>,
+            Query,
+            description = "Only tasks due at or after this RFC 3339 time",
+            nullable
+        ),
+        ("overdue" = Option<bool>,
+            Query,
+            description = "Only tasks that are (true) or aren't (false) past due and still open",
+            nullable
+        ),
+        ("label" = Option<String>,
+            Query,
+            description = "Only tasks with these comma separated label names (case insensitive)",
+            nullable
+        ),
+        ("label_match" = Option<String>,
+            Query,
+            description = "Whether tasks need all (default) or any of the labels",
+            nullable
+        ),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of tasks per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of assigned tasks, soonest due first", body = Tasks),
+        (status = 400, description = "The user id or query params were invalid", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn get_my_tasks(
+    actor: Actor,
+    params: Option<Query<TaskParams>>,
+    page: Option<Query<PageParams>>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let assignee = actor.user_id()?;
+    let filter = params.unwrap_or_default().validate()?;
+    let q = page.unwrap_or_default();
+    let scope = PageScope::new("me/tasks")
+        .param("assignee", Some(&assignee))
+        .task_filter(&filter);
+    let codec = PageCodec::new(&ctx.config, scope.into());
+    let after = codec.decode_keyset(&q.page_token)?;
+    let (next_keyset, tasks) =
+        GetAssignedTasks::execute(ctx, assignee, filter, after, q.page_size()).await?;
+    let resp = Tasks::new(codec.encode_keyset(next_keyset), tasks);
+    Ok(Json(resp))
+}
+
 /// Create a task
 #[utoipa::path(
     post,
     path = "/tasks",
+    params(
+        ("Idempotency-Key" = Option<String>,
+            Header,
+            description = "A client generated key that makes retries safe",
+            nullable
+        ),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     request_body = CreateTaskRequest,
     responses(
         (status = 201, description = "The task was created", body = Task),
-        (status = 400, description = "The requesst body was invalid", body = Errors)
+        (status = 400, description = "The requesst body was invalid", body = Errors),
+        (status = 409, description = "A request with the same key is in progress", body = Errors),
+        (status = 422, description = "The key was used with a different request", body = Errors)
     ),
     tag = "Task"
 )]
 async fn create_task(
+    actor: Actor,
     State(ctx): State<Arc<Ctx>>,
     Json(req): Json<CreateTaskRequest>,
 ) -> Result<impl IntoResponse> {
-    let (story_id, name, status) = req.validate()?;
-    let task = CreateTask::execute(ctx, story_id, name, status).await?;
+    let (story_id, parent_task_id, name, status, due_at) = req.validate()?;
+    let task = CreateTask::execute(
+        ctx,
+        &actor.0,
+        story_id,
+        parent_task_id,
+        name,
+        status,
+        due_at,
+    )
+    .await?;
     Ok((StatusCode::CREATED, Json(task)))
 }
 
@@ -73,22 +280,211 @@ async fn create_task(
 #[utoipa::path(
     patch,
     path = "/tasks/{task_id}",
-    params(("task_id" = Uuid, Path, description = "The task id")),
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     request_body = UpdateTaskRequest,
     responses(
         (status = 200, description = "The task was updated", body = Task),
         (status = 400, description = "The request body was invalid", body = Errors),
-        (status = 404, description = "The task was not found", body = Errors)
+        (status = 404, description = "The task or new story was not found", body = Errors),
+        (status = 409, description = "The status change isn't allowed, open tasks block it, or the new story's workflow lacks a task state", body = Errors)
     ),
     tag = "Task"
 )]
 async fn update_task(
+    actor: Actor,
     Path(task_id): Path<Uuid>,
     State(ctx): State<Arc<Ctx>>,
     Json(req): Json<UpdateTaskRequest>,
 ) -> Result<Json<Task>> {
-    let (name, status) = req.validate()?;
-    let task = UpdateTask::execute(ctx, task_id, name, status).await?;
+    let changes = req.validate()?;
+    let task = UpdateTask::execute(ctx, &actor.0, task_id, changes).await?;
+    Ok(Json(task))
+}
+
+/// Move a task just before or after a sibling task in the same story
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/move",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = MoveTaskRequest,
+    responses(
+        (status = 200, description = "The task was moved", body = Task),
+        (status = 400, description = "The request body or sibling was invalid", body = Errors),
+        (status = 404, description = "The task was not found", body = Errors),
+        (status = 409, description = "The task moved to another story meanwhile", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn move_task(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<MoveTaskRequest>,
+) -> Result<Json<Task>> {
+    let placement = req.validate()?;
+    let task = MoveTask::execute(ctx, &actor.0, task_id, placement).await?;
+    Ok(Json(task))
+}
+
+/// Move tasks, with their subtasks, to the end of a story as top level tasks, all or none
+#[utoipa::path(
+    post,
+    path = "/stories/{story_id}/tasks/move",
+    params(
+        ("story_id" = Uuid, Path, description = "The story to move the tasks to"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = MoveTasksRequest,
+    responses(
+        (status = 200, description = "The moved tasks, in request order", body = [Task]),
+        (status = 400, description = "The request body was invalid, or a task is already in the story", body = Errors),
+        (status = 404, description = "The story or a task was not found", body = Errors),
+        (status = 409, description = "The story's workflow lacks a task state", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn move_tasks_to_story(
+    actor: Actor,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<MoveTasksRequest>,
+) -> Result<Json<Vec<Task>>> {
+    let task_ids = req.validate()?;
+    let tasks = MoveTasksToStory::execute(ctx, &actor.0, story_id, task_ids).await?;
+    Ok(Json(tasks))
+}
+
+/// Assign a task to a user
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}/assignee",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = AssignTaskRequest,
+    responses(
+        (status = 200, description = "The task was assigned", body = Task),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn assign_task(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<AssignTaskRequest>,
+) -> Result<Json<Task>> {
+    let assignee = req.validate()?;
+    let task = AssignTask::execute(ctx, &actor.0, task_id, Some(assignee)).await?;
+    Ok(Json(task))
+}
+
+/// Unassign a task
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/assignee",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "The task was unassigned", body = Task),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn unassign_task(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Json<Task>> {
+    let task = AssignTask::execute(ctx, &actor.0, task_id, None).await?;
+    Ok(Json(task))
+}
+
+/// Make a task recur: completing it creates the next occurrence, which carries the rule forward
+#[utoipa::path(
+    put,
+    path = "/tasks/{task_id}/recurrence",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = RecurrenceRequest,
+    responses(
+        (status = 200, description = "The task recurrence was set", body = Task),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn set_recurrence(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<RecurrenceRequest>,
+) -> Result<Json<Task>> {
+    let recurrence = req.validate()?;
+    let task = SetRecurrence::execute(ctx, &actor.0, task_id, Some(recurrence)).await?;
+    Ok(Json(task))
+}
+
+/// Stop a task recurring, ending its series
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/recurrence",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "The task no longer recurs", body = Task),
+        (status = 404, description = "The task was not found", body = Errors)
+    ),
+    tag = "Task"
+)]
+async fn stop_recurrence(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Json<Task>> {
+    let task = SetRecurrence::execute(ctx, &actor.0, task_id, None).await?;
     Ok(Json(task))
 }
 
@@ -96,15 +492,26 @@ async fn update_task(
 #[utoipa::path(
     delete,
     path = "/tasks/{task_id}",
-    params(("task_id" = Uuid, Path, description = "The task id")),
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
     responses(
         (status = 204, description = "The task was deleted"),
         (status = 404, description = "The task was not found")
     ),
     tag = "Task"
 )]
-async fn delete_task(Path(task_id): Path<Uuid>, State(ctx): State<Arc<Ctx>>) -> StatusCode {
-    if let Err(err) = DeleteTask::execute(ctx, task_id).await {
+async fn delete_task(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> StatusCode {
+    if let Err(err) = DeleteTask::execute(ctx, &actor.0, task_id).await {
         return StatusCode::from(err);
     }
     StatusCode::NO_CONTENT
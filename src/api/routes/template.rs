@@ -0,0 +1,154 @@
+use crate::{
+    action::template::{
+        CreateStoryFromTemplate, DeleteTemplate, GetTemplate, GetTemplates, SaveTemplate,
+    },
+    api::dto::{Actor, FromTemplateRequest, TemplateRequest},
+    api::Ctx,
+    domain::{Story, StoryTemplate},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for template routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        get_templates,
+        get_template,
+        save_template,
+        delete_template,
+        create_story
+    ),
+    components(schemas(Errors, FromTemplateRequest, Story, StoryTemplate, TemplateRequest)),
+    tags((name = "Template"))
+)]
+pub struct ApiDoc;
+
+/// API routes for story templates
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/templates", get(get_templates))
+        .route("/templates/:template_id", get(get_template).delete(delete_template))
+        .route("/templates/:template_id/stories", post(create_story))
+        .route("/stories/:story_id/templates", post(save_template))
+}
+
+/// Get all templates
+#[utoipa::path(
+    get,
+    path = "/templates",
+    responses(
+        (status = 200, description = "The templates, in name order", body = [StoryTemplate])
+    ),
+    tag = "Template"
+)]
+async fn get_templates(State(ctx): State<Arc<Ctx>>) -> Result<impl IntoResponse> {
+    let templates = GetTemplates::execute(ctx).await?;
+    Ok(Json(templates))
+}
+
+/// Get a template
+#[utoipa::path(
+    get,
+    path = "/templates/{template_id}",
+    params(("template_id" = Uuid, Path, description = "The template id")),
+    responses(
+        (status = 200, description = "The template", body = StoryTemplate),
+        (status = 404, description = "The template was not found", body = Errors)
+    ),
+    tag = "Template"
+)]
+async fn get_template(
+    Path(template_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let template = GetTemplate::execute(ctx, template_id).await?;
+    Ok(Json(template))
+}
+
+/// Save a story, its tasks and ordering, and optionally its files, as a template
+#[utoipa::path(
+    post,
+    path = "/stories/{story_id}/templates",
+    params(("story_id" = Uuid, Path, description = "The story id")),
+    request_body = TemplateRequest,
+    responses(
+        (status = 201, description = "The template was saved", body = StoryTemplate),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Template"
+)]
+async fn save_template(
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<TemplateRequest>,
+) -> Result<impl IntoResponse> {
+    let (name, include_files) = req.validate()?;
+    let template = SaveTemplate::execute(ctx, story_id, name, include_files).await?;
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+/// Delete a template
+#[utoipa::path(
+    delete,
+    path = "/templates/{template_id}",
+    params(("template_id" = Uuid, Path, description = "The template id")),
+    responses(
+        (status = 204, description = "The template was deleted"),
+        (status = 404, description = "The template was not found")
+    ),
+    tag = "Template"
+)]
+async fn delete_template(Path(template_id): Path<Uuid>, State(ctx): State<Arc<Ctx>>) -> StatusCode {
+    if let Err(err) = DeleteTemplate::execute(ctx, template_id).await {
+        return StatusCode::from(err);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Create a new story from a template
+#[utoipa::path(
+    post,
+    path = "/templates/{template_id}/stories",
+    params(
+        ("template_id" = Uuid, Path, description = "The template id"),
+        ("Idempotency-Key" = Option<String>,
+            Header,
+            description = "A client generated key that makes retries safe",
+            nullable
+        ),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = FromTemplateRequest,
+    responses(
+        (status = 201, description = "The story was created", body = Story),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The template was not found", body = Errors)
+    ),
+    tag = "Template"
+)]
+async fn create_story(
+    actor: Actor,
+    Path(template_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<FromTemplateRequest>,
+) -> Result<impl IntoResponse> {
+    let name = req.validate()?;
+    let story = CreateStoryFromTemplate::execute(ctx, &actor.0, template_id, name).await?;
+    Ok((StatusCode::CREATED, Json(story)))
+}
@@ -0,0 +1,133 @@
+use crate::{
+    action::trash::{GetTrash, RestoreFile, RestoreStory, RestoreTask},
+    api::dto::{Actor, Trash},
+    api::Ctx,
+    domain::{Story, StoryFile, Task},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for trash routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_trash, restore_story, restore_task, restore_file),
+    components(schemas(Errors, Story, StoryFile, Task, Trash)),
+    tags((name = "Trash"))
+)]
+pub struct ApiDoc;
+
+/// API routes for trash
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/trash", get(get_trash))
+        .route("/stories/:story_id/restore", post(restore_story))
+        .route("/tasks/:task_id/restore", post(restore_task))
+        .route("/stories/:story_id/files/:file_id/restore", post(restore_file))
+}
+
+/// Get deleted stories, tasks, and files
+#[utoipa::path(
+    get,
+    path = "/trash",
+    responses(
+        (status = 200, description = "The most recently deleted items", body = Trash)
+    ),
+    tag = "Trash"
+)]
+async fn get_trash(State(ctx): State<Arc<Ctx>>) -> Result<impl IntoResponse> {
+    let (stories, tasks, files) = GetTrash::execute(ctx).await?;
+    Ok(Json(Trash::new(stories, tasks, files)))
+}
+
+/// Restore a story, along with the tasks and files deleted with it
+#[utoipa::path(
+    post,
+    path = "/stories/{story_id}/restore",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "The story was restored", body = Story),
+        (status = 404, description = "The story was not found in the trash", body = Errors)
+    ),
+    tag = "Trash"
+)]
+async fn restore_story(
+    actor: Actor,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let story = RestoreStory::execute(ctx, &actor.0, story_id).await?;
+    Ok(Json(story))
+}
+
+/// Restore a task
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/restore",
+    params(
+        ("task_id" = Uuid, Path, description = "The task id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "The task was restored", body = Task),
+        (status = 404, description = "The task was not found in the trash", body = Errors),
+        (status = 409, description = "The parent story is deleted", body = Errors)
+    ),
+    tag = "Trash"
+)]
+async fn restore_task(
+    actor: Actor,
+    Path(task_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let task = RestoreTask::execute(ctx, &actor.0, task_id).await?;
+    Ok(Json(task))
+}
+
+/// Restore a file
+#[utoipa::path(
+    post,
+    path = "/stories/{story_id}/files/{file_id}/restore",
+    params(
+        ("story_id" = Uuid, Path, description = "The parent story id"),
+        ("file_id" = Uuid, Path, description = "The id of the file to restore"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "The file was restored", body = StoryFile),
+        (status = 404, description = "The file was not found in the trash", body = Errors),
+        (status = 409, description = "The parent story is deleted", body = Errors)
+    ),
+    tag = "Trash"
+)]
+async fn restore_file(
+    actor: Actor,
+    Path((story_id, file_id)): Path<(Uuid, Uuid)>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let file = RestoreFile::execute(ctx, &actor.0, story_id, file_id).await?;
+    Ok(Json(file))
+}
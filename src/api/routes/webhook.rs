@@ -0,0 +1,155 @@
+use crate::{
+    action::webhook::{CreateWebhook, DeleteWebhook, GetDeliveries, GetWebhook, GetWebhooks},
+    api::dto::{Deliveries, PageCodec, PageParams, WebhookRequest},
+    api::Ctx,
+    domain::{DeliveryStatus, EventKind, Webhook, WebhookDelivery},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for webhook routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_webhooks, get_webhook, create_webhook, delete_webhook, get_deliveries),
+    components(schemas(
+        Deliveries,
+        DeliveryStatus,
+        Errors,
+        EventKind,
+        Webhook,
+        WebhookDelivery,
+        WebhookRequest
+    )),
+    tags((name = "Webhook"))
+)]
+pub struct ApiDoc;
+
+/// API routes for webhooks
+#[rustfmt::skip]
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new()
+        .route("/webhooks", get(get_webhooks).post(create_webhook))
+        .route("/webhooks/:webhook_id", get(get_webhook).delete(delete_webhook))
+        .route("/webhooks/:webhook_id/deliveries", get(get_deliveries))
+}
+
+/// Get all webhooks
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses(
+        (status = 200, description = "The webhooks", body = [Webhook])
+    ),
+    tag = "Webhook"
+)]
+async fn get_webhooks(State(ctx): State<Arc<Ctx>>) -> Result<impl IntoResponse> {
+    let webhooks = GetWebhooks::execute(ctx).await?;
+    Ok(Json(webhooks))
+}
+
+/// Get a webhook
+#[utoipa::path(
+    get,
+    path = "/webhooks/{webhook_id}",
+    params(("webhook_id" = Uuid, Path, description = "The webhook id")),
+    responses(
+        (status = 200, description = "The webhook", body = Webhook),
+        (status = 404, description = "The webhook was not found", body = Errors)
+    ),
+    tag = "Webhook"
+)]
+async fn get_webhook(
+    Path(webhook_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let webhook = GetWebhook::execute(ctx, webhook_id).await?;
+    Ok(Json(webhook))
+}
+
+/// Create a webhook
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = WebhookRequest,
+    responses(
+        (status = 201, description = "The webhook was created", body = Webhook),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The scoped story was not found", body = Errors)
+    ),
+    tag = "Webhook"
+)]
+async fn create_webhook(
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<WebhookRequest>,
+) -> Result<impl IntoResponse> {
+    let (url, secret, events, story_id) = req.validate()?;
+    let webhook = CreateWebhook::execute(ctx, url, secret, events, story_id).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// Delete a webhook
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{webhook_id}",
+    params(("webhook_id" = Uuid, Path, description = "The webhook id")),
+    responses(
+        (status = 204, description = "The webhook was deleted"),
+        (status = 404, description = "The webhook was not found")
+    ),
+    tag = "Webhook"
+)]
+async fn delete_webhook(Path(webhook_id): Path<Uuid>, State(ctx): State<Arc<Ctx>>) -> StatusCode {
+    if let Err(err) = DeleteWebhook::execute(ctx, webhook_id).await {
+        return StatusCode::from(err);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Get a page of deliveries for a webhook
+#[utoipa::path(
+    get,
+    path = "/webhooks/{webhook_id}/deliveries",
+    params(
+        ("webhook_id" = Uuid, Path, description = "The webhook id"),
+        ("page_size" = Option<i32>,
+            Query,
+            minimum = 10,
+            maximum = 1000,
+            description = "The number of deliveries per page",
+            nullable
+        ),
+        ("page_token" = Option<String>,
+            Query,
+            description = "The page cursor (next_page from response)",
+            nullable
+        )
+    ),
+    responses(
+        (status = 200, description = "A page of deliveries", body = Deliveries),
+        (status = 400, description = "The page token was invalid or expired", body = Errors),
+        (status = 404, description = "The webhook was not found", body = Errors)
+    ),
+    tag = "Webhook"
+)]
+async fn get_deliveries(
+    params: Option<Query<PageParams>>,
+    Path(webhook_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<impl IntoResponse> {
+    let q = params.unwrap_or_default();
+    let codec = PageCodec::new(&ctx.config, format!("webhooks/{webhook_id}/deliveries"));
+    let cursor = codec.decode_or(&q.page_token, 1)?;
+    let (next_cursor, deliveries) =
+        GetDeliveries::execute(ctx, webhook_id, cursor, q.page_size()).await?;
+    let resp = Deliveries::new(codec.encode(next_cursor), deliveries);
+    Ok(Json(resp))
+}
@@ -0,0 +1,83 @@
+use crate::{
+    action::workflow::{GetWorkflow, UpdateWorkflow},
+    api::dto::{Actor, WorkflowRequest},
+    api::Ctx,
+    domain::{Transition, Workflow, WorkflowState},
+    error::Errors,
+    Result,
+};
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// OpenApi docs for workflow routes
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_workflow, update_workflow),
+    components(schemas(Errors, Transition, Workflow, WorkflowRequest, WorkflowState)),
+    tags((name = "Workflow"))
+)]
+pub struct ApiDoc;
+
+/// API routes for workflows
+pub fn routes() -> Router<Arc<Ctx>> {
+    Router::new().route(
+        "/stories/:story_id/workflow",
+        get(get_workflow).put(update_workflow),
+    )
+}
+
+/// Get the workflow of a story
+#[utoipa::path(
+    get,
+    path = "/stories/{story_id}/workflow",
+    params(("story_id" = Uuid, Path, description = "The story id")),
+    responses(
+        (status = 200, description = "The story workflow, or the default workflow", body = Workflow),
+        (status = 404, description = "The story was not found", body = Errors)
+    ),
+    tag = "Workflow"
+)]
+async fn get_workflow(
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+) -> Result<Json<Workflow>> {
+    let workflow = GetWorkflow::execute(ctx, story_id).await?;
+    Ok(Json(workflow))
+}
+
+/// Replace the workflow of a story
+#[utoipa::path(
+    put,
+    path = "/stories/{story_id}/workflow",
+    params(
+        ("story_id" = Uuid, Path, description = "The story id"),
+        ("X-User-Id" = Option<String>,
+            Header,
+            description = "The caller recorded in the activity log",
+            nullable
+        )
+    ),
+    request_body = WorkflowRequest,
+    responses(
+        (status = 200, description = "The workflow was replaced", body = Workflow),
+        (status = 400, description = "The request body was invalid", body = Errors),
+        (status = 404, description = "The story was not found", body = Errors),
+        (status = 409, description = "Tasks are in states the workflow leaves out", body = Errors)
+    ),
+    tag = "Workflow"
+)]
+async fn update_workflow(
+    actor: Actor,
+    Path(story_id): Path<Uuid>,
+    State(ctx): State<Arc<Ctx>>,
+    Json(req): Json<WorkflowRequest>,
+) -> Result<Json<Workflow>> {
+    let workflow = req.validate()?;
+    let workflow = UpdateWorkflow::execute(ctx, &actor.0, story_id, workflow).await?;
+    Ok(Json(workflow))
+}
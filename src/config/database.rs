@@ -1,5 +1,5 @@
 use crate::config::Config;
-use sqlx::postgres::PgPoolOptions;
+use sqlx::postgres::{PgListener, PgPoolOptions};
 use sqlx::Executor;
 use std::sync::Arc;
 
@@ -17,4 +17,9 @@ impl Config {
                 })
             })
     }
+
+    /// Connect a listener for database notifications, using a dedicated connection.
+    pub async fn db_listener(&self) -> sqlx::Result<PgListener> {
+        PgListener::connect(&self.db_url).await
+    }
 }
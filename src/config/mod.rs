@@ -1,3 +1,4 @@
+use crate::domain::MetadataSchema;
 use std::env;
 
 // DB related config
@@ -15,6 +16,16 @@ pub struct Config {
     pub db_schema: String,
     pub storage_type: String,
     pub storage_bucket: String,
+    pub idempotency_ttl_secs: i64,
+    pub idempotency_timeout_secs: u64,
+    pub event_retention_secs: i64,
+    pub webhook_max_attempts: i32,
+    pub trash_retention_secs: i64,
+    pub page_token_secret: String,
+    pub page_token_max_age_secs: u64,
+    pub reminder_lead_secs: i64,
+    pub auto_complete_parent_tasks: bool,
+    pub story_metadata_schema: MetadataSchema,
 }
 
 /// Default for config just calls basic constructor
@@ -41,6 +52,67 @@ impl Config {
         let storage_type = env::var("STORAGE_TYPE").expect("STORAGE_TYPE not set");
         let storage_bucket = env::var("STORAGE_BUCKET").expect("STORAGE_BUCKET not set");
 
+        // idempotency settings
+        let mut idempotency_ttl_secs = 24 * 60 * 60;
+        if let Ok(s) = env::var("IDEMPOTENCY_TTL_SECS") {
+            idempotency_ttl_secs = s.parse().expect("IDEMPOTENCY_TTL_SECS could not be parsed")
+        }
+        let mut idempotency_timeout_secs = 30;
+        if let Ok(s) = env::var("IDEMPOTENCY_TIMEOUT_SECS") {
+            idempotency_timeout_secs = s
+                .parse()
+                .expect("IDEMPOTENCY_TIMEOUT_SECS could not be parsed")
+        }
+
+        // event feed settings
+        let mut event_retention_secs = 7 * 24 * 60 * 60;
+        if let Ok(s) = env::var("EVENT_RETENTION_SECS") {
+            event_retention_secs = s.parse().expect("EVENT_RETENTION_SECS could not be parsed")
+        }
+
+        // webhook settings
+        let mut webhook_max_attempts = 8;
+        if let Ok(s) = env::var("WEBHOOK_MAX_ATTEMPTS") {
+            webhook_max_attempts = s.parse().expect("WEBHOOK_MAX_ATTEMPTS could not be parsed")
+        }
+
+        // trash settings
+        let mut trash_retention_secs = 30 * 24 * 60 * 60;
+        if let Ok(s) = env::var("TRASH_RETENTION_SECS") {
+            trash_retention_secs = s.parse().expect("TRASH_RETENTION_SECS could not be parsed")
+        }
+
+        // page token settings
+        let page_token_secret = env::var("PAGE_TOKEN_SECRET").expect("PAGE_TOKEN_SECRET not set");
+        let mut page_token_max_age_secs = 60 * 60;
+        if let Ok(s) = env::var("PAGE_TOKEN_MAX_AGE_SECS") {
+            page_token_max_age_secs = s
+                .parse()
+                .expect("PAGE_TOKEN_MAX_AGE_SECS could not be parsed")
+        }
+
+        // reminder settings
+        let mut reminder_lead_secs = 15 * 60;
+        if let Ok(s) = env::var("REMINDER_LEAD_SECS") {
+            reminder_lead_secs = s.parse().expect("REMINDER_LEAD_SECS could not be parsed")
+        }
+
+        // subtask settings
+        let mut auto_complete_parent_tasks = false;
+        if let Ok(s) = env::var("AUTO_COMPLETE_PARENT_TASKS") {
+            auto_complete_parent_tasks = s
+                .parse()
+                .expect("AUTO_COMPLETE_PARENT_TASKS could not be parsed")
+        }
+
+        // story settings
+        let mut story_metadata_schema = MetadataSchema::default();
+        if let Ok(s) = env::var("STORY_METADATA_SCHEMA") {
+            story_metadata_schema = s
+                .parse()
+                .expect("STORY_METADATA_SCHEMA could not be parsed")
+        }
+
         // Create config
         Self {
             listen_addr,
@@ -49,6 +121,16 @@ impl Config {
             db_schema,
             storage_type,
             storage_bucket,
+            idempotency_ttl_secs,
+            idempotency_timeout_secs,
+            event_retention_secs,
+            webhook_max_attempts,
+            trash_retention_secs,
+            page_token_secret,
+            page_token_max_age_secs,
+            reminder_lead_secs,
+            auto_complete_parent_tasks,
+            story_metadata_schema,
         }
     }
 }
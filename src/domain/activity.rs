@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// An audit record of a change made to a story, or one of its tasks or files.
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Activity {
+    pub id: Uuid,
+    pub story_id: Uuid,
+    pub actor: String,
+    pub kind: String,
+    pub entity_id: Uuid,
+    /// Changed fields, each mapped to a before and after value
+    #[schema(value_type = Object)]
+    pub changes: Value,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A markdown comment on a story, or on one of its tasks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Comment {
+    pub id: Uuid,
+    pub story_id: Uuid,
+    /// The task commented on, if not the story itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<Uuid>,
+    /// The comment this is a reply to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to_id: Option<Uuid>,
+    /// The user who wrote the comment
+    pub author: String,
+    /// The comment text, as markdown
+    pub body: String,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The body a comment had before an edit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct CommentRevision {
+    pub id: Uuid,
+    pub comment_id: Uuid,
+    /// The replaced comment text
+    pub body: String,
+    /// The user who made the edit
+    pub editor: String,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
+    /// When the edit was made
+    pub created_at: DateTime<Utc>,
+}
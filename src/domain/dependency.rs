@@ -0,0 +1,89 @@
+use super::Task;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A task that can't be completed until another task is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Dependency {
+    /// The blocked task
+    pub task_id: Uuid,
+    /// The task that must be completed first
+    pub depends_on_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The tasks a task depends on, and the tasks that depend on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct TaskDependencies {
+    /// Tasks that must be completed first
+    pub blocked_by: Vec<Task>,
+    /// Tasks waiting on this task
+    pub blocking: Vec<Task>,
+}
+
+/// Sort tasks so each comes after the tasks it depends on, otherwise keeping their order.
+/// Dependencies on tasks not in the list are ignored.
+pub fn sort_by_dependencies(tasks: Vec<Task>, dependencies: &[Dependency]) -> Vec<Task> {
+    let ids: Vec<Uuid> = tasks.iter().map(|t| t.id).collect();
+    let mut tasks: Vec<Option<Task>> = tasks.into_iter().map(Some).collect();
+    dependency_order(&ids, dependencies)
+        .into_iter()
+        .filter_map(|i| tasks[i].take())
+        .collect()
+}
+
+/// The indexes of ids in dependency order, picking the earliest ready id at each step. Ids left
+/// over in a cycle, which the repo prevents, go last in their original order.
+fn dependency_order(ids: &[Uuid], dependencies: &[Dependency]) -> Vec<usize> {
+    let index: HashMap<Uuid, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let mut blockers = vec![0; ids.len()];
+    let mut dependents = vec![Vec::new(); ids.len()];
+    for d in dependencies {
+        let (task, blocker) = (index.get(&d.task_id), index.get(&d.depends_on_id));
+        if let (Some(&task), Some(&blocker)) = (task, blocker) {
+            blockers[task] += 1;
+            dependents[blocker].push(task);
+        }
+    }
+    let mut order = Vec::with_capacity(ids.len());
+    let mut done = vec![false; ids.len()];
+    while let Some(next) = (0..ids.len()).find(|&i| !done[i] && blockers[i] == 0) {
+        done[next] = true;
+        order.push(next);
+        for &task in &dependents[next] {
+            blockers[task] -= 1;
+        }
+    }
+    order.extend((0..ids.len()).filter(|&i| !done[i]));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_by_dependencies() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let dep = |task: usize, depends_on: usize| Dependency {
+            task_id: ids[task],
+            depends_on_id: ids[depends_on],
+            created_at: Utc::now(),
+        };
+        assert_eq!(dependency_order(&ids, &[]), vec![0, 1, 2, 3]);
+        let deps = [dep(0, 2), dep(2, 3), dep(1, 3)];
+        assert_eq!(dependency_order(&ids, &deps), vec![3, 1, 2, 0]);
+
+        // Unknown tasks are ignored, and cycles go last
+        let outside = Dependency {
+            depends_on_id: Uuid::new_v4(),
+            ..dep(0, 1)
+        };
+        assert_eq!(dependency_order(&ids, &[outside]), vec![0, 1, 2, 3]);
+        let deps = [dep(0, 1), dep(1, 0)];
+        assert_eq!(dependency_order(&ids, &deps), vec![2, 3, 0, 1]);
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The kinds of changes published for a story.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumString,
+    Display,
+    Serialize,
+    ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    StoryCreated,
+    StoryUpdated,
+    StoryDeleted,
+    StoryRestored,
+    TaskCreated,
+    TaskUpdated,
+    TaskDeleted,
+    TaskRestored,
+    TaskMoved,
+    TaskAssigned,
+    TaskUnassigned,
+    TaskReminder,
+    FileCreated,
+    FileDeleted,
+    FileRestored,
+    WorkflowUpdated,
+    LabelAdded,
+    LabelRemoved,
+    DependencyAdded,
+    DependencyRemoved,
+    CommentCreated,
+    CommentUpdated,
+    CommentDeleted,
+}
+
+/// A change to a story, or one of its tasks or files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Event {
+    pub id: i64,
+    pub story_id: Uuid,
+    pub kind: String,
+    pub entity_id: Uuid,
+    #[schema(value_type = Object)]
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Event {
+    pub fn kind(&self) -> Option<EventKind> {
+        EventKind::from_str(&self.kind).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_kind_to_string() {
+        assert_eq!(EventKind::TaskCreated.to_string(), "task_created");
+        assert_eq!(EventKind::StoryDeleted.to_string(), "story_deleted");
+        let kind = EventKind::from_str("file_created").unwrap();
+        assert_eq!(kind, EventKind::FileCreated);
+    }
+}
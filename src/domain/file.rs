@@ -1,9 +1,13 @@
+use super::Keyset;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use strum_macros::{Display, EnumString};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema, sqlx::FromRow,
+)]
 pub struct StoryFile {
     pub id: Uuid,
     pub story_id: Uuid,
@@ -11,6 +15,35 @@ pub struct StoryFile {
     pub name: String,
     pub size: i64,
     pub content_type: String,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// The fields story files can be sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum FileSort {
+    Name,
+    Size,
+    #[default]
+    CreatedAt,
+}
+
+impl StoryFile {
+    /// Get the keyset for the position of this file in a sorted list.
+    pub fn keyset(&self, sort: FileSort) -> Keyset {
+        let key = match sort {
+            FileSort::Name => self.name.clone(),
+            FileSort::Size => self.size.to_string(),
+            FileSort::CreatedAt => self.created_at.to_rfc3339(),
+        };
+        Keyset {
+            key,
+            seqno: self.seqno,
+        }
+    }
 }
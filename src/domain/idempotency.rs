@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A client supplied key, along with the stored response of the first request made with it.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub fingerprint: String,
+    pub status_code: Option<i16>,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl IdempotencyKey {
+    /// Determine whether a response has been stored for the key.
+    pub fn is_complete(&self) -> bool {
+        self.status_code.is_some()
+    }
+}
+
+/// The result of claiming an idempotency key.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// The key was claimed, and only the holder of this token can store its response.
+    Claimed(Uuid),
+    /// The key was already claimed by an earlier request.
+    Existing(IdempotencyKey),
+}
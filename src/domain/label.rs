@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use strum_macros::{Display, EnumString};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A named, coloured tag for categorising stories and tasks.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+pub struct Label {
+    pub id: Uuid,
+    pub name: String,
+    /// A hex rgb colour, like #1f883d
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How a label filter matches the labels of an item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum LabelMatch {
+    /// Items with every label
+    #[default]
+    All,
+    /// Items with at least one label
+    Any,
+}
+
+/// Select stories or tasks by label name, ignoring case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelFilter {
+    /// Lower case label names, without duplicates
+    pub names: Vec<String>,
+    pub mode: LabelMatch,
+}
+
+impl LabelFilter {
+    /// Whether every label must match.
+    pub fn match_all(&self) -> bool {
+        self.mode == LabelMatch::All
+    }
+}
@@ -0,0 +1,55 @@
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
+use std::sync::LazyLock;
+
+// Strips unsafe markup, but keeps task list items as disabled checkboxes.
+static SANITIZER: LazyLock<Builder<'static>> = LazyLock::new(|| {
+    let mut builder = Builder::default();
+    builder
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["checked"])
+        .set_tag_attribute_value("input", "type", "checkbox")
+        .set_tag_attribute_value("input", "disabled", "");
+    builder
+});
+
+/// Render markdown as html, with scripts, styles, event handlers and other unsafe markup
+/// removed, so it can be embedded in any client as is.
+pub fn render_markdown(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+    SANITIZER.clean(&unsafe_html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_sanitized_html() {
+        let html = render_markdown("# Plan\n\n- [x] *Book* [flights](https://example.com)");
+        assert!(html.starts_with("<h1>Plan</h1>"));
+        assert!(html.contains("<em>Book</em>"));
+        assert!(html.contains(r#"href="https://example.com""#));
+
+        let html = render_markdown("<script>alert(1)</script>\n\n[x](javascript:alert(1))");
+        assert!(!html.contains("script"));
+        assert!(!html.contains("javascript"));
+    }
+
+    #[test]
+    fn render_task_lists() {
+        let html = render_markdown("- [x] Book\n- [ ] Pack");
+        assert!(html.contains("checked"));
+        assert_eq!(html.matches(r#"type="checkbox""#).count(), 2);
+        assert_eq!(html.matches("disabled").count(), 2);
+
+        // Other inputs are forced to be disabled checkboxes
+        let html = render_markdown(r#"<input type="text" value="x" onfocus="alert(1)">"#);
+        assert!(html.contains(r#"type="checkbox""#));
+        assert!(!html.contains("text"));
+        assert!(!html.contains("onfocus"));
+    }
+}
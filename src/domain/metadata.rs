@@ -0,0 +1,107 @@
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use strum_macros::{Display, EnumString};
+
+/// Custom key/value pairs attached to a story.
+pub type Metadata = serde_json::Map<String, Value>;
+
+/// The kinds of values metadata keys can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum MetadataType {
+    String,
+    Number,
+    Boolean,
+}
+
+impl MetadataType {
+    /// Whether a value is of this type.
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            MetadataType::String => value.is_string(),
+            MetadataType::Number => value.is_number(),
+            MetadataType::Boolean => value.is_boolean(),
+        }
+    }
+}
+
+/// The metadata keys stories may have, and the type of each, like
+/// team:string,points:number,billable:boolean. An empty schema allows any key with a string,
+/// number or boolean value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataSchema {
+    keys: BTreeMap<String, MetadataType>,
+}
+
+impl MetadataSchema {
+    /// Check a metadata value against the schema.
+    pub fn check(&self, key: &str, value: &Value) -> Result<(), String> {
+        if self.keys.is_empty() {
+            return match value {
+                Value::String(_) | Value::Number(_) | Value::Bool(_) => Ok(()),
+                _ => Err("must be a string, number or boolean".into()),
+            };
+        }
+        match self.keys.get(key) {
+            Some(kind) if kind.matches(value) => Ok(()),
+            Some(kind) => Err(format!("must be a {kind}")),
+            None => Err("not an allowed key".into()),
+        }
+    }
+}
+
+impl FromStr for MetadataSchema {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = BTreeMap::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, kind) = part
+                .split_once(':')
+                .ok_or_else(|| format!("expected key:type: {part}"))?;
+            let kind = MetadataType::from_str(kind.trim())
+                .map_err(|_| format!("type must be string, number or boolean: {part}"))?;
+            if keys.insert(key.trim().to_string(), kind).is_some() {
+                return Err(format!("repeated key: {key}"));
+            }
+        }
+        Ok(MetadataSchema { keys })
+    }
+}
+
+impl fmt::Display for MetadataSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<_> = self.keys.iter().map(|(k, t)| format!("{k}:{t}")).collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn check_metadata() {
+        let schema =
+            MetadataSchema::from_str(" team:string, points:number,billable:boolean").unwrap();
+        assert_eq!(
+            schema.to_string(),
+            "billable:boolean,points:number,team:string"
+        );
+        assert!(schema.check("team", &json!("api")).is_ok());
+        assert!(schema.check("points", &json!(3)).is_ok());
+        assert!(schema.check("points", &json!("3")).is_err());
+        assert!(schema.check("owner", &json!("alice")).is_err());
+
+        // Without a schema, any key with a scalar value is allowed
+        let schema = MetadataSchema::default();
+        assert!(schema.check("owner", &json!("alice")).is_ok());
+        assert!(schema.check("owner", &json!(null)).is_err());
+        assert!(schema.check("owner", &json!(["alice"])).is_err());
+
+        for s in ["team", "team:text", "team:string,team:number"] {
+            assert!(MetadataSchema::from_str(s).is_err(), "{s}");
+        }
+    }
+}
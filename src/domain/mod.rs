@@ -1,9 +1,39 @@
+mod activity;
+mod comment;
+mod dependency;
+mod event;
 mod file;
+mod idempotency;
+mod label;
+mod markdown;
+mod metadata;
+mod recurrence;
+mod report;
+mod search;
+mod sort;
 mod status;
 mod story;
 mod task;
+mod template;
+mod webhook;
+mod workflow;
 
-pub use file::StoryFile;
+pub use activity::Activity;
+pub use comment::{Comment, CommentRevision};
+pub use dependency::{sort_by_dependencies, Dependency, TaskDependencies};
+pub use event::{Event, EventKind};
+pub use file::{FileSort, StoryFile};
+pub use idempotency::{IdempotencyClaim, IdempotencyKey};
+pub use label::{Label, LabelFilter, LabelMatch};
+pub use markdown::render_markdown;
+pub use metadata::{Metadata, MetadataSchema, MetadataType};
+pub use recurrence::{Frequency, Recurrence};
+pub use report::{Burndown, CycleTime, DailyTasks};
+pub use search::SearchHit;
+pub use sort::{Keyset, Seek, SortOrder};
 pub use status::Status;
-pub use story::Story;
-pub use task::Task;
+pub use story::{Story, StoryChanges, StoryFilter, StorySort, StorySummary};
+pub use task::{Placement, Task, TaskChanges, TaskFilter};
+pub use template::StoryTemplate;
+pub use webhook::{DeliveryStatus, PendingDelivery, Webhook, WebhookDelivery};
+pub use workflow::{Transition, Workflow, WorkflowState};
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, Utc};
+use std::{fmt, str::FromStr};
+use strum_macros::{Display, EnumString};
+
+// Limits how far apart occurrences can be.
+const MAX_INTERVAL: u32 = 365;
+
+// The last day a month can have.
+const MAX_MONTH_DAY: u32 = 31;
+
+/// How often a task repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// A subset of iCalendar RRULEs: a frequency with an interval, ending at a time or after a
+/// number of occurrences, and for monthly rules the day of the month. For example
+/// FREQ=WEEKLY;INTERVAL=2;COUNT=5 or FREQ=MONTHLY;BYMONTHDAY=31.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub freq: Frequency,
+    /// The number of frequency units between occurrences
+    pub interval: u32,
+    /// No occurrences after this time
+    pub until: Option<DateTime<Utc>>,
+    /// The number of occurrences left, counting the current one
+    pub count: Option<u32>,
+    /// The day of the month monthly occurrences fall on
+    pub month_day: Option<u32>,
+}
+
+impl Recurrence {
+    /// The time of the occurrence after one at a given time, along with the rule that carries
+    /// forward to it. None when the series has ended.
+    pub fn next(&self, at: DateTime<Utc>) -> Option<(DateTime<Utc>, Recurrence)> {
+        if self.count.is_some_and(|count| count <= 1) {
+            return None;
+        }
+        let mut month_day = self.month_day;
+        let next = match self.freq {
+            Frequency::Daily => at.checked_add_days(Days::new(self.interval.into())),
+            Frequency::Weekly => at.checked_add_days(Days::new(7 * u64::from(self.interval))),
+            Frequency::Monthly => {
+                // Occurrences keep to the day of the first one, clamped to the end of shorter
+                // months, so the day carries forward instead of drifting to the clamped day
+                let day = *month_day.get_or_insert(at.day());
+                let month = at
+                    .with_day(1)?
+                    .checked_add_months(Months::new(self.interval))?;
+                let last = month
+                    .checked_add_months(Months::new(1))?
+                    .checked_sub_days(Days::new(1))?
+                    .day();
+                month.with_day(day.min(last))
+            }
+        }?;
+        if self.until.is_some_and(|until| next > until) {
+            return None;
+        }
+        let rule = Recurrence {
+            count: self.count.map(|count| count - 1),
+            month_day,
+            ..self.clone()
+        };
+        Some((next, rule))
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_uppercase();
+        let s = s.strip_prefix("RRULE:").unwrap_or(&s);
+        let (mut freq, mut interval, mut until, mut count) = (None, None, None, None);
+        let mut month_day = None;
+        for part in s.split(';') {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected KEY=VALUE: {part}"))?;
+            let duplicate = match key {
+                "FREQ" => {
+                    let parsed = Frequency::from_str(value)
+                        .map_err(|_| format!("FREQ must be DAILY, WEEKLY or MONTHLY: {value}"))?;
+                    freq.replace(parsed).is_some()
+                }
+                "INTERVAL" => interval
+                    .replace(parse_number(key, value, MAX_INTERVAL)?)
+                    .is_some(),
+                "UNTIL" => until.replace(parse_until(value)?).is_some(),
+                "COUNT" => count.replace(parse_number(key, value, u32::MAX)?).is_some(),
+                "BYMONTHDAY" => month_day
+                    .replace(parse_number(key, value, MAX_MONTH_DAY)?)
+                    .is_some(),
+                _ => return Err(format!("unsupported rule part: {key}")),
+            };
+            if duplicate {
+                return Err(format!("repeated rule part: {key}"));
+            }
+        }
+        if until.is_some() && count.is_some() {
+            return Err("UNTIL and COUNT can't both be set".into());
+        }
+        let freq = freq.ok_or("FREQ is required")?;
+        if month_day.is_some() && freq != Frequency::Monthly {
+            return Err("BYMONTHDAY needs FREQ=MONTHLY".into());
+        }
+        Ok(Recurrence {
+            freq,
+            interval: interval.unwrap_or(1),
+            until,
+            count,
+            month_day,
+        })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.freq)?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        if let Some(day) = self.month_day {
+            write!(f, ";BYMONTHDAY={day}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a number from 1 up to a maximum.
+fn parse_number(key: &str, value: &str, max: u32) -> Result<u32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|n| (1..=max).contains(n))
+        .ok_or_else(|| format!("{key} must be a number from 1 to {max}"))
+}
+
+/// Parse an UNTIL value, a UTC date-time like 20241231T235959Z or a date like 20241231, which
+/// means the end of that day.
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(at) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(at.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|at| at.and_utc())
+        .ok_or_else(|| format!("UNTIL must be a UTC time like 20241231T235959Z: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    #[test]
+    fn parse_recurrence() {
+        let rule = Recurrence::from_str("rrule:freq=weekly;interval=2;count=3").unwrap();
+        assert_eq!(rule.freq, Frequency::Weekly);
+        assert_eq!((rule.interval, rule.count), (2, Some(3)));
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2;COUNT=3");
+        let rule = Recurrence::from_str("FREQ=DAILY;UNTIL=20241231").unwrap();
+        assert_eq!(rule.until, Some(time("2024-12-31T23:59:59Z")));
+        assert_eq!(rule.to_string(), "FREQ=DAILY;UNTIL=20241231T235959Z");
+        let rule = Recurrence::from_str("FREQ=MONTHLY;BYMONTHDAY=31").unwrap();
+        assert_eq!(rule.month_day, Some(31));
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;BYMONTHDAY=31");
+        for s in [
+            "",
+            "INTERVAL=2",
+            "FREQ=YEARLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=DAILY;FREQ=WEEKLY",
+            "FREQ=DAILY;COUNT=2;UNTIL=20241231",
+            "FREQ=DAILY;UNTIL=tomorrow",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+        ] {
+            assert!(Recurrence::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn next_occurrence() {
+        let at = time("2024-01-31T09:00:00Z");
+        let rule = Recurrence::from_str("FREQ=DAILY;INTERVAL=3").unwrap();
+        assert_eq!(rule.next(at).unwrap().0, time("2024-02-03T09:00:00Z"));
+        let rule = Recurrence::from_str("FREQ=WEEKLY").unwrap();
+        assert_eq!(rule.next(at).unwrap().0, time("2024-02-07T09:00:00Z"));
+
+        // Monthly occurrences are clamped to the end of shorter months, and keep to the day of
+        // the first occurrence after that
+        let rule = Recurrence::from_str("FREQ=MONTHLY").unwrap();
+        let (at, rule) = rule.next(at).unwrap();
+        assert_eq!(at, time("2024-02-29T09:00:00Z"));
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;BYMONTHDAY=31");
+        let (at, _) = rule.next(at).unwrap();
+        assert_eq!(at, time("2024-03-31T09:00:00Z"));
+        let rule = Recurrence::from_str("FREQ=MONTHLY;INTERVAL=2;BYMONTHDAY=30").unwrap();
+        assert_eq!(rule.next(at).unwrap().0, time("2024-05-30T09:00:00Z"));
+        let at = time("2024-01-31T09:00:00Z");
+
+        // Counted series carry the remaining count forward, and stop at the last occurrence
+        let rule = Recurrence::from_str("FREQ=DAILY;COUNT=2").unwrap();
+        let (_, rule) = rule.next(at).unwrap();
+        assert_eq!(rule.count, Some(1));
+        assert_eq!(rule.next(at), None);
+
+        // Series stop after the until time
+        let rule = Recurrence::from_str("FREQ=WEEKLY;UNTIL=20240206").unwrap();
+        assert_eq!(rule.next(at), None);
+        let rule = Recurrence::from_str("FREQ=WEEKLY;UNTIL=20240207").unwrap();
+        assert!(rule.next(at).is_some());
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Task throughput for a day, and the tasks left open at the end of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct DailyTasks {
+    /// The UTC date
+    pub date: NaiveDate,
+    /// Tasks created on the day
+    pub created: i64,
+    /// Tasks moved from an open to a closed state on the day
+    pub completed: i64,
+    /// Tasks open at the end of the day
+    pub remaining: i64,
+}
+
+/// Daily task counts over a range of dates, for a story or all stories.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Burndown {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub story_id: Option<Uuid>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: Vec<DailyTasks>,
+}
+
+/// Percentiles of the time from creating tasks to closing them, for tasks closed over a range
+/// of dates. Percentiles are None when no tasks were closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct CycleTime {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub story_id: Option<Uuid>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// The number of tasks closed
+    pub tasks: i64,
+    pub p50_secs: Option<i64>,
+    pub p75_secs: Option<i64>,
+    pub p90_secs: Option<i64>,
+    pub p95_secs: Option<i64>,
+}
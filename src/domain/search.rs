@@ -0,0 +1,17 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A story, task, or file matching a full-text search.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct SearchHit {
+    /// The kind of entity matched: story, task, or file
+    pub kind: String,
+    pub id: Uuid,
+    pub story_id: Uuid,
+    pub story_name: String,
+    pub name: String,
+    /// The name with matching terms wrapped in <mark> tags, and other markup escaped
+    pub highlight: String,
+    pub rank: f32,
+}
@@ -0,0 +1,33 @@
+use strum_macros::{Display, EnumString};
+
+/// The direction of a sorted list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A position in a sorted list: the sort key of the last item seen, with its seqno to break ties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyset {
+    pub key: String,
+    pub seqno: i64,
+}
+
+/// Where a page of a list starts: at either end, or just after or before a keyset in list order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Seek {
+    First,
+    Last,
+    After(Keyset),
+    Before(Keyset),
+}
+
+impl Seek {
+    /// Whether the page is selected walking back from the end of the list.
+    pub fn is_backward(&self) -> bool {
+        matches!(self, Seek::Last | Seek::Before(_))
+    }
+}
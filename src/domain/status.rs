@@ -1,9 +1,23 @@
 use serde::Serialize;
-use strum_macros::{Display, EnumString};
+use strum_macros::{AsRefStr, Display, EnumString};
 use utoipa::ToSchema;
 
+/// The states of the default workflow, for stories that haven't defined their own.
 #[derive(
-    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, Display, Serialize, ToSchema,
+    AsRefStr,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumString,
+    Display,
+    Serialize,
+    ToSchema,
 )]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
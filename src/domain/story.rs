@@ -1,14 +1,97 @@
+use super::{Keyset, LabelFilter, Metadata};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use std::collections::BTreeMap;
+use strum_macros::{Display, EnumString};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema, sqlx::FromRow)]
 pub struct Story {
     pub id: Uuid,
     pub name: String,
+    /// Long form markdown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Custom key/value pairs
+    #[schema(value_type = Object)]
+    pub metadata: serde_json::Value,
     #[serde(skip_serializing)]
     pub seqno: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// The fields stories can be sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum StorySort {
+    Name,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl Story {
+    /// Get the keyset for the position of this story in a sorted list.
+    pub fn keyset(&self, sort: StorySort) -> Keyset {
+        let key = match sort {
+            StorySort::Name => self.name.clone(),
+            StorySort::CreatedAt => self.created_at.to_rfc3339(),
+            StorySort::UpdatedAt => self.updated_at.to_rfc3339(),
+        };
+        Keyset {
+            key,
+            seqno: self.seqno,
+        }
+    }
+}
+
+/// Task and file totals for a story.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct StorySummary {
+    pub story_id: Uuid,
+    /// The number of live tasks, including subtasks
+    pub tasks: i64,
+    /// The number of tasks in closed workflow states
+    pub closed_tasks: i64,
+    /// Closed tasks as a percentage of all tasks, rounded down
+    pub percent_complete: i64,
+    /// The number of tasks in each status
+    pub statuses: BTreeMap<String, i64>,
+    /// The number of live files
+    pub files: i64,
+    /// The total size of live files in bytes
+    pub file_bytes: i64,
+}
+
+/// Changes to a story. Description and metadata are left as they are when None.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoryChanges {
+    pub name: String,
+    /// Some(None) clears the description
+    pub description: Option<Option<String>>,
+    /// Replaces all metadata
+    pub metadata: Option<Metadata>,
+}
+
+/// Filters for selecting stories.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoryFilter {
+    pub name: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    pub has_incomplete_tasks: Option<bool>,
+    pub labels: Option<LabelFilter>,
+}
+
+impl StoryFilter {
+    /// Whether the filter selects all stories.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
 }
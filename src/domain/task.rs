@@ -1,22 +1,94 @@
-use super::Status;
+use super::{Keyset, LabelFilter, Workflow, WorkflowState};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
-use std::str::FromStr;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
 pub struct Task {
     pub id: Uuid,
     pub story_id: Uuid,
+    /// The task this is a subtask of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<Uuid>,
     pub name: String,
     pub status: String,
+    /// The rank of the task within its story
+    pub position: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    /// The user the task is assigned to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// The rule for repeating the task when it's completed, like FREQ=WEEKLY;INTERVAL=2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
+    /// The number of subtasks
+    pub subtasks: i64,
+    /// The number of subtasks in closed workflow states
+    pub closed_subtasks: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl Task {
-    pub fn status(&self) -> Status {
-        Status::from_str(&self.status).unwrap_or_default()
+    /// Resolve the task status against the workflow of its story.
+    pub fn status<'a>(&self, workflow: &'a Workflow) -> Option<&'a WorkflowState> {
+        workflow.state(&self.status)
     }
+
+    /// Get the keyset for the position of this task in its story.
+    pub fn keyset(&self) -> Keyset {
+        Keyset {
+            key: self.position.to_string(),
+            seqno: self.seqno,
+        }
+    }
+
+    /// Get the keyset for this task in due time order, with undated tasks last.
+    pub fn due_keyset(&self) -> Keyset {
+        Keyset {
+            key: self.due_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            seqno: self.seqno,
+        }
+    }
+}
+
+/// Filters for selecting the tasks of a story, or of an assignee.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskFilter {
+    pub status: Option<String>,
+    pub due_before: Option<DateTime<Utc>>,
+    pub due_after: Option<DateTime<Utc>>,
+    /// Whether tasks are past due and still open
+    pub overdue: Option<bool>,
+    pub labels: Option<LabelFilter>,
+    /// Only subtasks of this task
+    pub parent_task_id: Option<Uuid>,
+}
+
+/// Changes to make to a task, where None leaves a field as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskChanges {
+    pub name: Option<String>,
+    pub status: Option<String>,
+    /// A due time, or None to clear it
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    /// A parent task, or None to make the task top level
+    pub parent_task_id: Option<Option<Uuid>>,
+    /// A story to move the task and its subtasks to
+    pub story_id: Option<Uuid>,
+    /// Close the task even if tasks it depends on are still open
+    pub force: bool,
+}
+
+/// Where to move a task, relative to a sibling task in the same story.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Before(Uuid),
+    After(Uuid),
 }
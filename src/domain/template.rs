@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A reusable outline of a story, saved from an existing story: its details, tasks in order,
+/// and optionally its files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct StoryTemplate {
+    pub id: Uuid,
+    pub name: String,
+    /// The name of stories made from the template, unless another is given
+    pub story_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[schema(value_type = Object)]
+    pub metadata: serde_json::Value,
+    /// The number of tasks, including subtasks
+    pub tasks: i64,
+    /// The number of files
+    pub files: i64,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A subscription to story events, delivered to a URL.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<String>,
+    pub story_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The state of an event delivery to a webhook.
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, Display, Serialize, ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    #[default]
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// A logged attempt to send an event to a webhook.
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event_id: i64,
+    pub event_kind: String,
+    #[schema(value_type = Object)]
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub seqno: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookDelivery {
+    pub fn status(&self) -> DeliveryStatus {
+        DeliveryStatus::from_str(&self.status).unwrap_or_default()
+    }
+}
+
+/// A delivery claimed for sending, along with the webhook endpoint.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PendingDelivery {
+    pub id: Uuid,
+    pub event_kind: String,
+    pub payload: Value,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
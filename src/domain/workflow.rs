@@ -0,0 +1,130 @@
+use super::Status;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A state tasks can be in, and whether tasks in it are closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowState {
+    pub name: String,
+    pub closed: bool,
+}
+
+/// An allowed change of task status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+}
+
+/// The states the tasks of a story move through. New tasks start in the first state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Workflow {
+    pub states: Vec<WorkflowState>,
+    /// Allowed status changes. When empty, tasks can move between any states.
+    pub transitions: Vec<Transition>,
+}
+
+impl Default for Workflow {
+    /// The workflow of stories that haven't defined their own.
+    fn default() -> Self {
+        // The task_is_closed database function falls back to these closed states for stories
+        // without a workflow, which the workflow repo tests check.
+        Self {
+            states: vec![
+                WorkflowState {
+                    name: Status::Incomplete.to_string(),
+                    closed: false,
+                },
+                WorkflowState {
+                    name: Status::Complete.to_string(),
+                    closed: true,
+                },
+            ],
+            transitions: Vec::new(),
+        }
+    }
+}
+
+impl Workflow {
+    /// Get the state new tasks start in.
+    pub fn initial(&self) -> &WorkflowState {
+        &self.states[0]
+    }
+
+    /// Look up a state by name.
+    pub fn state(&self, name: &str) -> Option<&WorkflowState> {
+        self.states.iter().find(|s| s.name == name)
+    }
+
+    /// Look up a state a task status names, or explain why it isn't valid.
+    pub fn ensure_state(&self, status: &str) -> Result<&WorkflowState, String> {
+        self.state(status)
+            .ok_or_else(|| format!("status: not a workflow state: {status}"))
+    }
+
+    /// Whether tasks can move from one state to another.
+    pub fn allows(&self, from: &str, to: &str) -> bool {
+        from == to
+            || self.transitions.is_empty()
+            || self
+                .transitions
+                .iter()
+                .any(|t| t.from == from && t.to == to)
+    }
+
+    /// Get the first closed state tasks can move to from a state.
+    pub fn closed_from(&self, from: &str) -> Option<&WorkflowState> {
+        self.states
+            .iter()
+            .find(|s| s.closed && self.allows(from, &s.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(from: &str, to: &str) -> Transition {
+        Transition {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    #[test]
+    fn default_workflow() {
+        let workflow = Workflow::default();
+        assert_eq!(workflow.initial().name, "incomplete");
+        assert!(workflow.state("complete").unwrap().closed);
+        assert!(workflow.state("done").is_none());
+        assert!(workflow.ensure_state("complete").is_ok());
+        assert_eq!(
+            workflow.ensure_state("done"),
+            Err("status: not a workflow state: done".to_string())
+        );
+        assert!(workflow.allows("complete", "incomplete"));
+        assert_eq!(workflow.closed_from("incomplete").unwrap().name, "complete");
+    }
+
+    #[test]
+    fn workflow_transitions() {
+        let state = |name: &str, closed| WorkflowState {
+            name: name.into(),
+            closed,
+        };
+        let workflow = Workflow {
+            states: vec![
+                state("todo", false),
+                state("doing", false),
+                state("done", true),
+            ],
+            transitions: vec![transition("todo", "doing"), transition("doing", "done")],
+        };
+        assert!(workflow.allows("todo", "doing"));
+        assert!(workflow.allows("done", "done"));
+        assert!(!workflow.allows("todo", "done"));
+        assert!(!workflow.allows("done", "todo"));
+        assert_eq!(workflow.closed_from("doing").unwrap().name, "done");
+        assert!(workflow.closed_from("todo").is_none());
+    }
+}
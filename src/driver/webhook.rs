@@ -0,0 +1,138 @@
+use crate::{Error, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, Client};
+use sha2::Sha256;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// The header identifying a delivery, which stays the same across retries.
+pub const WEBHOOK_ID: &str = "x-webhook-id";
+
+/// The header naming the event kind being delivered.
+pub const WEBHOOK_EVENT: &str = "x-webhook-event";
+
+/// The header carrying the delivery timestamp and signature.
+pub const WEBHOOK_SIGNATURE: &str = "x-webhook-signature";
+
+/// Sends signed webhook requests.
+pub struct WebhookClient {
+    client: Client,
+}
+
+impl WebhookClient {
+    /// Create a webhook client with a request timeout.
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let client = Client::builder().timeout(timeout).build()?;
+        Ok(Self { client })
+    }
+
+    /// POST a json body to a webhook url, returning the response status code.
+    pub async fn send(
+        &self,
+        url: &str,
+        secret: &str,
+        delivery_id: Uuid,
+        event_kind: &str,
+        body: Vec<u8>,
+    ) -> Result<u16> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = sign(secret, timestamp, &body);
+        let resp = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_ID, delivery_id.to_string())
+            .header(WEBHOOK_EVENT, event_kind)
+            .header(WEBHOOK_SIGNATURE, signature)
+            .body(body)
+            .send()
+            .await?;
+        Ok(resp.status().as_u16())
+    }
+}
+
+/// Sign a webhook body with a shared secret. The signature covers the timestamp so receivers can
+/// reject replayed requests.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    // HMAC accepts keys of any length, so this can't fail.
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac key");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let digest = hex::encode(mac.finalize().into_bytes());
+    format!("t={},v1={}", timestamp, digest)
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::internal(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use tokio::{net::TcpListener, sync::mpsc};
+
+    #[test]
+    fn sign_body() {
+        let signature = sign("secret", 1700000000, b"{}");
+        assert!(signature.starts_with("t=1700000000,v1="));
+        assert_eq!(signature, sign("secret", 1700000000, b"{}"));
+        assert_ne!(signature, sign("other", 1700000000, b"{}"));
+        assert_ne!(signature, sign("secret", 1700000001, b"{}"));
+    }
+
+    #[tokio::test]
+    async fn send_signed_request() {
+        // Start a stand-in receiver that records requests
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hooks",
+            post(|headers: HeaderMap, body: Bytes| async move {
+                tx.send((headers, body)).unwrap();
+                axum::http::StatusCode::NO_CONTENT
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // Send a delivery
+        let client = WebhookClient::new(Duration::from_secs(5)).unwrap();
+        let url = format!("http://{}/hooks", addr);
+        let delivery_id = Uuid::new_v4();
+        let body = br#"{"kind":"task_updated"}"#.to_vec();
+        let status = client
+            .send(&url, "secret", delivery_id, "task_updated", body.clone())
+            .await
+            .unwrap();
+        assert_eq!(status, 204);
+
+        // Verify the receiver got a request it can authenticate
+        let (headers, received) = rx.recv().await.unwrap();
+        assert_eq!(received.to_vec(), body);
+        let header = |name| headers.get(name).unwrap().to_str().unwrap();
+        assert_eq!(header(WEBHOOK_ID), delivery_id.to_string());
+        assert_eq!(header(WEBHOOK_EVENT), "task_updated");
+        let signature = header(WEBHOOK_SIGNATURE);
+        let timestamp: i64 = signature[2..signature.find(',').unwrap()].parse().unwrap();
+        assert_eq!(signature, sign("secret", timestamp, &body));
+    }
+
+    #[tokio::test]
+    async fn send_unreachable() {
+        let client = WebhookClient::new(Duration::from_secs(1)).unwrap();
+        let result = client
+            .send(
+                "http://127.0.0.1:1/hooks",
+                "secret",
+                Uuid::new_v4(),
+                "x",
+                vec![],
+            )
+            .await;
+        assert!(result.is_err());
+    }
+}
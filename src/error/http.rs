@@ -36,9 +36,12 @@ impl From<Error> for StatusCode {
 /// Get the http status code for an error.
 fn http_status_code(err: &Error) -> StatusCode {
     match err {
+        Error::Conflict { .. } => StatusCode::CONFLICT,
         Error::NotFound { .. } => StatusCode::NOT_FOUND,
+        Error::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
         Error::InvalidArgs { .. } => StatusCode::BAD_REQUEST,
         Error::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::Unprocessable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
     }
 }
 
@@ -46,7 +49,10 @@ fn http_status_code(err: &Error) -> StatusCode {
 fn http_errors(err: &Error) -> Errors {
     let errors = match err {
         Error::InvalidArgs { messages } => messages.to_owned(),
+        Error::Conflict { message } => vec![message.to_owned()],
         Error::NotFound { message } => vec![message.to_owned()],
+        Error::TooLarge { message } => vec![message.to_owned()],
+        Error::Unprocessable { message } => vec![message.to_owned()],
         Error::Internal { message } => {
             tracing::error!("internal error: {}", message);
             vec![message.to_owned()]
@@ -8,12 +8,18 @@ pub use http::Errors;
 /// Project level error type
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error("conflict error: {message}")]
+    Conflict { message: String },
     #[error("invalid arguments: {messages:?}")]
     InvalidArgs { messages: Vec<String> },
     #[error("internal error: {message}")]
     Internal { message: String },
     #[error("not found error: {message}")]
     NotFound { message: String },
+    #[error("too large error: {message}")]
+    TooLarge { message: String },
+    #[error("unprocessable error: {message}")]
+    Unprocessable { message: String },
 }
 
 // Error helpers
@@ -31,11 +37,17 @@ impl Error {
             messages: vec![message.into()],
         }
     }
-}
 
-impl From<base64::DecodeError> for Error {
-    fn from(err: base64::DecodeError) -> Self {
-        Error::internal(err.to_string())
+    pub fn conflict(message: String) -> Self {
+        Error::Conflict { message }
+    }
+
+    pub fn unprocessable(message: String) -> Self {
+        Error::Unprocessable { message }
+    }
+
+    pub fn too_large(message: String) -> Self {
+        Error::TooLarge { message }
     }
 }
 
@@ -45,6 +57,12 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::internal(err.to_string())
+    }
+}
+
 impl From<MultipartError> for Error {
     fn from(err: MultipartError) -> Self {
         Error::invalid_args(&err.to_string())
@@ -5,6 +5,7 @@
     clippy::unwrap_used,
     clippy::wildcard_imports
 )]
+#![cfg_attr(test, allow(clippy::print_stdout, clippy::unwrap_used))]
 
 /// Business logic
 pub mod action;
@@ -27,6 +28,9 @@ pub mod error;
 /// Postgres database logic
 pub mod repo;
 
+/// Background jobs
+pub mod worker;
+
 /// Project level error type
 pub use error::Error;
 
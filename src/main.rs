@@ -8,6 +8,7 @@ use sqlx_todos::{
     config::Config,
     driver::storage::{fs::FileStorage, Storage},
     repo::Repo,
+    worker,
 };
 use std::{error::Error, sync::Arc};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -36,11 +37,39 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Set up storage and repo
     assert_eq!(config.storage_type, "file");
     let root_dir = config.storage_bucket.clone();
-    let storage = Box::new(FileStorage::new(root_dir)) as Box<dyn Storage<Uuid>>;
+    let storage = Arc::new(Box::new(FileStorage::new(root_dir)) as Box<dyn Storage<Uuid>>);
     let repo = Arc::new(Repo::new(Arc::new(pool)));
 
+    // Set up API context
+    let ctx = Ctx::new(Arc::clone(&config), Arc::clone(&storage), Arc::clone(&repo));
+
+    // Start background jobs
+    tokio::spawn(worker::idempotency::purge(
+        Arc::clone(&config),
+        Arc::clone(&repo),
+    ));
+    tokio::spawn(worker::event::purge(Arc::clone(&config), Arc::clone(&repo)));
+    tokio::spawn(worker::reminder::remind(
+        Arc::clone(&config),
+        Arc::clone(&repo),
+    ));
+    tokio::spawn(worker::trash::purge(
+        Arc::clone(&config),
+        Arc::clone(&repo),
+        storage,
+    ));
+    tokio::spawn(worker::webhook::deliver(
+        Arc::clone(&config),
+        Arc::clone(&repo),
+        ctx.events.clone(),
+    ));
+    tokio::spawn(worker::event::relay(
+        Arc::clone(&config),
+        repo,
+        ctx.events.clone(),
+    ));
+
     // Set up API
-    let ctx = Ctx::new(Arc::new(storage), repo);
     let service = Api::new(Arc::new(ctx)).mk_service();
 
     // Start server
@@ -0,0 +1,166 @@
+use super::Repo;
+use crate::{
+    domain::{Activity, EventKind},
+    Result,
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Fields that are left out of activity changes.
+const UNTRACKED_FIELDS: [&str; 3] = ["id", "created_at", "updated_at"];
+
+// Extend repo with queries related to activity.
+impl Repo {
+    /// Select a page of activity for a story.
+    pub async fn list_activity(
+        &self,
+        story_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<Activity>)> {
+        let query = sqlx::query_as!(
+            Activity,
+            r#"SELECT id, story_id, actor, kind, entity_id, changes, seqno, created_at
+            FROM activity WHERE story_id = $1 AND seqno >= $2
+            ORDER BY seqno LIMIT $3"#,
+            story_id,
+            cursor,
+            limit as i64,
+        );
+        let activity = query.fetch_all(self.db_ref()).await?;
+        let next_cursor = activity.last().map(|a| a.seqno + 1).unwrap_or_default();
+        Ok((next_cursor, activity))
+    }
+}
+
+/// Record who changed an entity and how, as part of a transaction.
+pub(super) async fn record<T: Serialize>(
+    conn: &mut PgConnection,
+    actor: &str,
+    story_id: Uuid,
+    kind: EventKind,
+    entity_id: Uuid,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<()> {
+    let before = serde_json::to_value(before)?;
+    let after = serde_json::to_value(after)?;
+    sqlx::query!(
+        r#"INSERT INTO activity (story_id, actor, kind, entity_id, changes)
+        VALUES ($1, $2, $3, $4, $5)"#,
+        story_id,
+        actor,
+        kind.to_string(),
+        entity_id,
+        diff(&before, &after),
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Map each changed field of an entity to its before and after values.
+fn diff(before: &Value, after: &Value) -> Value {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        if UNTRACKED_FIELDS.contains(&key.as_str()) || changes.contains_key(key) {
+            continue;
+        }
+        let (b, a) = (before.get(key), after.get(key));
+        if b != a {
+            changes.insert(key.clone(), json!({ "before": b, "after": a }));
+        }
+    }
+    Value::Object(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Status, TaskChanges},
+        repo::tests,
+    };
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[test]
+    fn diff_changed_fields() {
+        let before = json!({ "id": 1, "name": "Suttree", "status": "incomplete", "updated_at": 1 });
+        let after = json!({ "id": 1, "name": "Suttree", "status": "complete", "updated_at": 2 });
+        let changes = diff(&before, &after);
+        assert_eq!(
+            changes,
+            json!({ "status": { "before": "incomplete", "after": "complete" } })
+        );
+    }
+
+    #[test]
+    fn diff_created_and_deleted() {
+        let entity = json!({ "id": 1, "name": "Suttree" });
+        let created = json!({ "name": { "before": null, "after": "Suttree" } });
+        assert_eq!(diff(&Value::Null, &entity), created);
+        let deleted = json!({ "name": { "before": "Suttree", "after": null } });
+        assert_eq!(diff(&entity, &Value::Null), deleted);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+
+        // Make some changes as different actors
+        let story = repo
+            .create_story("alice", "Reading".into(), None, Default::default())
+            .await
+            .unwrap();
+        let task = repo
+            .create_task(
+                "alice",
+                story.id,
+                None,
+                "Suttree".into(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.update_task(
+            "bob",
+            task.id,
+            &TaskChanges {
+                status: Some(Status::Complete.as_ref().into()),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+
+        // Query activity for the story, a page at a time
+        let (cursor, activity) = repo.list_activity(story.id, 1, 2).await.unwrap();
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].kind, EventKind::StoryCreated.to_string());
+        let (_, activity) = repo.list_activity(story.id, cursor, 2).await.unwrap();
+        assert_eq!(activity.len(), 1);
+
+        // Verify who completed the task
+        let completed = &activity[0];
+        assert_eq!(completed.actor, "bob");
+        assert_eq!(completed.entity_id, task.id);
+        assert_eq!(completed.changes["status"]["after"], "complete");
+
+        // Cleanup
+        repo.delete_story("alice", story.id).await.unwrap();
+    }
+}
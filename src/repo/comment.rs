@@ -0,0 +1,409 @@
+use super::{activity, event, task, Repo};
+use crate::{
+    domain::{Comment, CommentRevision, EventKind},
+    Error, Result,
+};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Extend repo with queries related to comments.
+impl Repo {
+    /// Select a comment by id, unless the story or task it's on is in the trash.
+    pub async fn fetch_comment(&self, comment_id: Uuid) -> Result<Comment> {
+        let query = sqlx::query_as!(
+            Comment,
+            r#"SELECT c.id, c.story_id, c.task_id, c.reply_to_id, c.author, c.body, c.seqno,
+            c.created_at, c.updated_at
+            FROM comments c
+            JOIN stories s ON s.id = c.story_id
+            LEFT JOIN tasks t ON t.id = c.task_id
+            WHERE c.id = $1 AND s.deleted_at IS NULL AND t.deleted_at IS NULL"#,
+            comment_id,
+        );
+        match query.fetch_optional(self.db_ref()).await? {
+            Some(comment) => Ok(comment),
+            None => Err(Error::not_found(format!("comment not found: {comment_id}"))),
+        }
+    }
+
+    /// Select a page of the comments on a story, or on one of its tasks, oldest first. Comments on
+    /// a story or task in the trash are left out.
+    pub async fn list_comments(
+        &self,
+        story_id: Uuid,
+        task_id: Option<Uuid>,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<Comment>)> {
+        let query = sqlx::query_as!(
+            Comment,
+            r#"SELECT c.id, c.story_id, c.task_id, c.reply_to_id, c.author, c.body, c.seqno,
+            c.created_at, c.updated_at
+            FROM comments c
+            JOIN stories s ON s.id = c.story_id
+            LEFT JOIN tasks t ON t.id = c.task_id
+            WHERE c.story_id = $1 AND c.task_id IS NOT DISTINCT FROM $2 AND c.seqno >= $3
+            AND s.deleted_at IS NULL AND t.deleted_at IS NULL
+            ORDER BY c.seqno LIMIT $4"#,
+            story_id,
+            task_id,
+            cursor,
+            limit as i64,
+        );
+        let comments = query.fetch_all(self.db_ref()).await?;
+        let next_cursor = comments.last().map(|c| c.seqno + 1).unwrap_or_default();
+        Ok((next_cursor, comments))
+    }
+
+    /// Insert a new comment on a story, optionally replying to another comment on the story.
+    pub async fn create_comment(
+        &self,
+        author: &str,
+        story_id: Uuid,
+        reply_to_id: Option<Uuid>,
+        body: String,
+    ) -> Result<Comment> {
+        let mut tx = self.db.begin().await?;
+        task::lock_story(&mut tx, story_id).await?;
+        let comment = insert_comment(&mut tx, author, story_id, None, reply_to_id, body).await?;
+        tx.commit().await?;
+        Ok(comment)
+    }
+
+    /// Insert a new comment on a task, on the story the task is in, optionally replying to
+    /// another comment on the task.
+    pub async fn create_task_comment(
+        &self,
+        author: &str,
+        task_id: Uuid,
+        reply_to_id: Option<Uuid>,
+        body: String,
+    ) -> Result<Comment> {
+        let mut tx = self.db.begin().await?;
+        // Locked, so the task can't move to another story without the comment
+        let task = task::lock_task(&mut tx, task_id).await?;
+        let comment = insert_comment(
+            &mut tx,
+            author,
+            task.story_id,
+            Some(task.id),
+            reply_to_id,
+            body,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(comment)
+    }
+
+    /// Update the body of a comment, keeping the previous body as a revision. Comments on a story
+    /// or task in the trash can't be updated.
+    pub async fn update_comment(
+        &self,
+        actor: &str,
+        comment_id: Uuid,
+        body: String,
+    ) -> Result<Comment> {
+        let mut tx = self.db.begin().await?;
+        let before = sqlx::query_as!(
+            Comment,
+            r#"SELECT c.id, c.story_id, c.task_id, c.reply_to_id, c.author, c.body, c.seqno,
+            c.created_at, c.updated_at
+            FROM comments c
+            JOIN stories s ON s.id = c.story_id
+            LEFT JOIN tasks t ON t.id = c.task_id
+            WHERE c.id = $1 AND s.deleted_at IS NULL AND t.deleted_at IS NULL
+            FOR UPDATE OF c"#,
+            comment_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("comment not found: {comment_id}")))?;
+        if before.body == body {
+            return Ok(before);
+        }
+        sqlx::query!(
+            "INSERT INTO comment_revisions (comment_id, body, editor) VALUES ($1, $2, $3)",
+            comment_id,
+            before.body,
+            actor,
+        )
+        .execute(&mut *tx)
+        .await?;
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"UPDATE comments SET body = $1, updated_at = now() WHERE id = $2
+            RETURNING id, story_id, task_id, reply_to_id, author, body, seqno, created_at,
+            updated_at"#,
+            body,
+            comment_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::CommentUpdated;
+        activity::record(
+            &mut tx,
+            actor,
+            comment.story_id,
+            kind,
+            comment.id,
+            Some(&before),
+            Some(&comment),
+        )
+        .await?;
+        event::publish(&mut tx, comment.story_id, kind, comment.id, &comment).await?;
+        tx.commit().await?;
+        Ok(comment)
+    }
+
+    /// Select a page of the revisions of a comment, oldest first.
+    pub async fn list_comment_revisions(
+        &self,
+        comment_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<CommentRevision>)> {
+        let query = sqlx::query_as!(
+            CommentRevision,
+            r#"SELECT id, comment_id, body, editor, seqno, created_at
+            FROM comment_revisions WHERE comment_id = $1 AND seqno >= $2
+            ORDER BY seqno LIMIT $3"#,
+            comment_id,
+            cursor,
+            limit as i64,
+        );
+        let revisions = query.fetch_all(self.db_ref()).await?;
+        let next_cursor = revisions.last().map(|r| r.seqno + 1).unwrap_or_default();
+        Ok((next_cursor, revisions))
+    }
+
+    /// Delete a comment along with its replies.
+    pub async fn delete_comment(&self, actor: &str, comment_id: Uuid) -> Result<()> {
+        let query = sqlx::query_as!(
+            Comment,
+            r#"WITH RECURSIVE thread AS (
+                SELECT id FROM comments WHERE id = $1
+                UNION
+                SELECT c.id FROM comments c JOIN thread t ON c.reply_to_id = t.id
+            )
+            DELETE FROM comments WHERE id IN (SELECT id FROM thread)
+            RETURNING id, story_id, task_id, reply_to_id, author, body, seqno, created_at,
+            updated_at"#,
+            comment_id,
+        );
+        let mut tx = self.db.begin().await?;
+        for comment in query.fetch_all(&mut *tx).await? {
+            let kind = EventKind::CommentDeleted;
+            activity::record(
+                &mut tx,
+                actor,
+                comment.story_id,
+                kind,
+                comment.id,
+                Some(&comment),
+                None,
+            )
+            .await?;
+            event::publish(&mut tx, comment.story_id, kind, comment.id, &comment).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Insert a new comment on a locked story or task, optionally replying to another comment on the
+/// same story or task.
+async fn insert_comment(
+    conn: &mut PgConnection,
+    author: &str,
+    story_id: Uuid,
+    task_id: Option<Uuid>,
+    reply_to_id: Option<Uuid>,
+    body: String,
+) -> Result<Comment> {
+    if let Some(reply_to_id) = reply_to_id {
+        let found = sqlx::query_scalar!(
+            r#"SELECT id FROM comments
+            WHERE id = $1 AND story_id = $2 AND task_id IS NOT DISTINCT FROM $3
+            FOR SHARE"#,
+            reply_to_id,
+            story_id,
+            task_id,
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if found.is_none() {
+            return Err(Error::invalid_args(
+                "reply_to_id: not a comment on the same story or task",
+            ));
+        }
+    }
+    let comment = sqlx::query_as!(
+        Comment,
+        r#"INSERT INTO comments (story_id, task_id, reply_to_id, author, body)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, story_id, task_id, reply_to_id, author, body, seqno, created_at,
+        updated_at"#,
+        story_id,
+        task_id,
+        reply_to_id,
+        author,
+        body,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let kind = EventKind::CommentCreated;
+    activity::record(
+        conn,
+        author,
+        comment.story_id,
+        kind,
+        comment.id,
+        None,
+        Some(&comment),
+    )
+    .await?;
+    event::publish(conn, comment.story_id, kind, comment.id, &comment).await?;
+    Ok(comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::Status,
+        repo::{tests, Repo},
+        Error,
+    };
+    use std::sync::Arc;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(Arc::clone(&pool));
+
+        // Set up a story with a task to comment on
+        let story = repo
+            .create_story("tester", "Trip".into(), None, Default::default())
+            .await
+            .unwrap();
+        let task = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Book flights".into(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Comment on the story, and reply
+        let question = repo
+            .create_comment("alice", story.id, None, "Where to?".into())
+            .await
+            .unwrap();
+        let answer = repo
+            .create_comment("bob", story.id, Some(question.id), "*Lisbon*".into())
+            .await
+            .unwrap();
+        assert_eq!(answer.reply_to_id, Some(question.id));
+
+        // Replies stay on the same story or task
+        let result = repo
+            .create_task_comment("bob", task.id, Some(question.id), "?".into())
+            .await;
+        assert!(result.is_err());
+        let note = repo
+            .create_task_comment("bob", task.id, None, "Window seat".into())
+            .await
+            .unwrap();
+
+        // Page through story comments, which don't include task comments
+        let (cursor, comments) = repo.list_comments(story.id, None, 1, 1).await.unwrap();
+        assert_eq!(comments, vec![question.clone()]);
+        let (_, comments) = repo
+            .list_comments(story.id, None, cursor, 10)
+            .await
+            .unwrap();
+        assert_eq!(comments, vec![answer.clone()]);
+        let (_, comments) = repo
+            .list_comments(story.id, Some(task.id), 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(comments, vec![note.clone()]);
+
+        // Edits keep the previous body
+        let edited = repo
+            .update_comment("alice", question.id, "Where to, and when?".into())
+            .await
+            .unwrap();
+        assert_eq!(edited.body, "Where to, and when?");
+        let (_, revisions) = repo
+            .list_comment_revisions(question.id, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].body, "Where to?");
+        assert_eq!(revisions[0].editor, "alice");
+
+        // Comment changes show up in activity
+        let (_, activity) = repo.list_activity(story.id, 1, 20).await.unwrap();
+        let comment_activity = activity.iter().filter(|a| a.kind.starts_with("comment_"));
+        assert_eq!(comment_activity.count(), 4);
+
+        // Comments on tasks in the trash are hidden, and can't be edited
+        repo.delete_task("tester", task.id).await.unwrap();
+        let result = repo.fetch_comment(note.id).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let result = repo.update_comment("bob", note.id, "Aisle".into()).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let result = repo
+            .create_task_comment("bob", task.id, None, "Aisle".into())
+            .await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let (_, comments) = repo
+            .list_comments(story.id, Some(task.id), 1, 10)
+            .await
+            .unwrap();
+        assert!(comments.is_empty());
+
+        // Comments on a task are on the story the task is in
+        let other = repo
+            .create_story("tester", "Other".into(), None, Default::default())
+            .await
+            .unwrap();
+        let hotel = repo
+            .create_task("tester", story.id, None, "Book hotel".into(), None, None)
+            .await
+            .unwrap();
+        repo.move_tasks_to_story("tester", &[hotel.id], other.id, None)
+            .await
+            .unwrap();
+        let river = repo
+            .create_task_comment("carol", hotel.id, None, "Near the river".into())
+            .await
+            .unwrap();
+        assert_eq!(river.story_id, other.id);
+
+        // Deleting a comment deletes its replies
+        repo.delete_comment("alice", question.id).await.unwrap();
+        assert!(repo.fetch_comment(question.id).await.is_err());
+        assert!(repo.fetch_comment(answer.id).await.is_err());
+
+        // Comments on stories in the trash are hidden too
+        let tip = repo
+            .create_comment("carol", story.id, None, "Pack light".into())
+            .await
+            .unwrap();
+        repo.delete_story("tester", story.id).await.unwrap();
+        let result = repo.fetch_comment(tip.id).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+    }
+}
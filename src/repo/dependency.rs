@@ -0,0 +1,319 @@
+use super::{activity, event, query_tasks, Repo};
+use crate::{
+    domain::{sort_by_dependencies, Dependency, EventKind, Task},
+    Error, Result,
+};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Defines a reasonable limit on the number of tasks sorted by dependencies.
+const MAX_SORTED_TASKS: i64 = 1000;
+
+// Extend repo with queries related to task dependencies.
+impl Repo {
+    /// Select the live tasks a task depends on, in story and position order.
+    pub async fn list_blockers(&self, task_id: Uuid) -> Result<Vec<Task>> {
+        let query = query_tasks!(
+            "SELECT",
+            r#"FROM tasks
+            WHERE id IN (SELECT depends_on_id FROM task_dependencies WHERE task_id = $1)
+            AND deleted_at IS NULL
+            AND EXISTS (SELECT 1 FROM stories s WHERE s.id = tasks.story_id AND s.deleted_at IS NULL)
+            ORDER BY story_id, position, seqno"#,
+            task_id,
+        );
+        let tasks = query.fetch_all(self.db_ref()).await?;
+        Ok(tasks)
+    }
+
+    /// Select the live tasks that depend on a task, in story and position order.
+    pub async fn list_dependents(&self, task_id: Uuid) -> Result<Vec<Task>> {
+        let query = query_tasks!(
+            "SELECT",
+            r#"FROM tasks
+            WHERE id IN (SELECT task_id FROM task_dependencies WHERE depends_on_id = $1)
+            AND deleted_at IS NULL
+            AND EXISTS (SELECT 1 FROM stories s WHERE s.id = tasks.story_id AND s.deleted_at IS NULL)
+            ORDER BY story_id, position, seqno"#,
+            task_id,
+        );
+        let tasks = query.fetch_all(self.db_ref()).await?;
+        Ok(tasks)
+    }
+
+    /// Count the live tasks a task depends on that aren't in closed workflow states.
+    pub async fn count_open_blockers(&self, task_id: Uuid) -> Result<i64> {
+        let mut conn = self.db.acquire().await?;
+        count_open_blockers(&mut conn, task_id).await
+    }
+
+    /// Select the live tasks of a story, each after the tasks it depends on, and otherwise in
+    /// position order. Stories with too many tasks to sort at once are rejected.
+    pub async fn list_tasks_by_dependencies(&self, story_id: Uuid) -> Result<Vec<Task>> {
+        let tasks = query_tasks!(
+            "SELECT",
+            r#"FROM tasks WHERE story_id = $1 AND deleted_at IS NULL
+            ORDER BY position, seqno LIMIT $2"#,
+            story_id,
+            MAX_SORTED_TASKS + 1,
+        )
+        .fetch_all(self.db_ref())
+        .await?;
+        if tasks.len() as i64 > MAX_SORTED_TASKS {
+            return Err(Error::unprocessable(format!(
+                "stories with more than {MAX_SORTED_TASKS} tasks can't be sorted by dependencies"
+            )));
+        }
+        let dependencies = sqlx::query_as!(
+            Dependency,
+            r#"SELECT d.task_id, d.depends_on_id, d.created_at
+            FROM task_dependencies d JOIN tasks t ON t.id = d.task_id
+            WHERE t.story_id = $1 AND t.deleted_at IS NULL"#,
+            story_id,
+        )
+        .fetch_all(self.db_ref())
+        .await?;
+        Ok(sort_by_dependencies(tasks, &dependencies))
+    }
+
+    /// Make a task depend on another task. Adding an existing dependency is a no-op, and
+    /// dependencies that would form a cycle are rejected.
+    pub async fn add_dependency(&self, actor: &str, task: &Task, depends_on: &Task) -> Result<()> {
+        if task.id == depends_on.id {
+            return Err(Error::invalid_args("a task can't depend on itself"));
+        }
+        let mut tx = self.db.begin().await?;
+
+        // Serialize dependency changes, so concurrent additions can't form a cycle together
+        sqlx::query!("SELECT pg_advisory_xact_lock(hashtext('task_dependencies'))")
+            .execute(&mut *tx)
+            .await?;
+        let cycle = sqlx::query_scalar!(
+            r#"WITH RECURSIVE upstream AS (
+                SELECT depends_on_id FROM task_dependencies WHERE task_id = $1
+                UNION
+                SELECT d.depends_on_id FROM task_dependencies d
+                JOIN upstream u ON d.task_id = u.depends_on_id
+            )
+            SELECT EXISTS (SELECT 1 FROM upstream WHERE depends_on_id = $2) AS "cycle!""#,
+            depends_on.id,
+            task.id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if cycle {
+            return Err(Error::conflict(format!(
+                "dependency cycle: {} already depends on {}",
+                depends_on.id, task.id
+            )));
+        }
+
+        let added = sqlx::query_as!(
+            Dependency,
+            r#"INSERT INTO task_dependencies (task_id, depends_on_id) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            RETURNING task_id, depends_on_id, created_at"#,
+            task.id,
+            depends_on.id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(dependency) = added {
+            let kind = EventKind::DependencyAdded;
+            record(&mut tx, actor, task.story_id, kind, &dependency).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Stop a task depending on another task.
+    pub async fn remove_dependency(
+        &self,
+        actor: &str,
+        task: &Task,
+        depends_on: &Task,
+    ) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let removed = sqlx::query_as!(
+            Dependency,
+            r#"DELETE FROM task_dependencies WHERE task_id = $1 AND depends_on_id = $2
+            RETURNING task_id, depends_on_id, created_at"#,
+            task.id,
+            depends_on.id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(dependency) = removed {
+            let kind = EventKind::DependencyRemoved;
+            record(&mut tx, actor, task.story_id, kind, &dependency).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Count the open blockers of a task on a connection. Share locks the blockers, so they can't be
+/// reopened until the transaction ends.
+pub(super) async fn count_open_blockers(conn: &mut PgConnection, task_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"WITH blockers AS (
+            SELECT t.story_id, t.status
+            FROM tasks t
+            JOIN task_dependencies d ON d.depends_on_id = t.id
+            JOIN stories s ON s.id = t.story_id
+            WHERE d.task_id = $1 AND t.deleted_at IS NULL AND s.deleted_at IS NULL
+            FOR SHARE OF t
+        )
+        SELECT count(*) FILTER (WHERE NOT task_is_closed(story_id, status)) AS "count!"
+        FROM blockers"#,
+        task_id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(count)
+}
+
+/// Record a dependency being added to or removed from a task, and publish it as an event.
+async fn record(
+    conn: &mut PgConnection,
+    actor: &str,
+    story_id: Uuid,
+    kind: EventKind,
+    dependency: &Dependency,
+) -> Result<()> {
+    let (before, after) = match kind {
+        EventKind::DependencyAdded => (None, Some(dependency)),
+        _ => (Some(dependency), None),
+    };
+    let task_id = dependency.task_id;
+    activity::record(conn, actor, story_id, kind, task_id, before, after).await?;
+    event::publish(conn, story_id, kind, task_id, dependency).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{Status, TaskChanges},
+        repo::{tests, Repo},
+        Error,
+    };
+    use std::sync::Arc;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(Arc::clone(&pool));
+
+        // Set up tasks in two stories
+        let house = repo
+            .create_story("tester", "House".into(), None, Default::default())
+            .await
+            .unwrap();
+        let money = repo
+            .create_story("tester", "Money".into(), None, Default::default())
+            .await
+            .unwrap();
+        let incomplete = Status::Incomplete.as_ref();
+        let mut tasks = Vec::new();
+        for name in ["Paint", "Plaster", "Wire"] {
+            let task = repo
+                .create_task(
+                    "tester",
+                    house.id,
+                    None,
+                    name.into(),
+                    Some(incomplete),
+                    None,
+                )
+                .await
+                .unwrap();
+            tasks.push(task);
+        }
+        let [paint, plaster, wire] = tasks.try_into().unwrap();
+        let loan = repo
+            .create_task(
+                "tester",
+                money.id,
+                None,
+                "Loan".into(),
+                Some(incomplete),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Paint after plaster, plaster after wiring, and wiring after the loan comes through
+        repo.add_dependency("tester", &paint, &plaster)
+            .await
+            .unwrap();
+        repo.add_dependency("tester", &plaster, &wire)
+            .await
+            .unwrap();
+        repo.add_dependency("tester", &plaster, &wire)
+            .await
+            .unwrap();
+        repo.add_dependency("tester", &wire, &loan).await.unwrap();
+        let blockers = repo.list_blockers(plaster.id).await.unwrap();
+        assert_eq!(blockers, vec![wire.clone()]);
+        let dependents = repo.list_dependents(loan.id).await.unwrap();
+        assert_eq!(dependents, vec![wire.clone()]);
+        let (_, activity) = repo.list_activity(house.id, 1, 20).await.unwrap();
+        assert_eq!(activity.len(), 7, "story, 3 tasks and 3 dependencies");
+
+        // Cycles are rejected
+        assert!(repo.add_dependency("tester", &paint, &paint).await.is_err());
+        assert!(repo.add_dependency("tester", &loan, &paint).await.is_err());
+
+        // Story tasks come after the tasks they depend on
+        let sorted = repo.list_tasks_by_dependencies(house.id).await.unwrap();
+        let names: Vec<_> = sorted.into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["Wire", "Plaster", "Paint"]);
+
+        // Tasks can only be closed once their blockers are, unless forced
+        assert_eq!(repo.count_open_blockers(wire.id).await.unwrap(), 1);
+        let close = |force| TaskChanges {
+            status: Some(Status::Complete.to_string()),
+            force,
+            ..Default::default()
+        };
+        let result = repo
+            .update_task("tester", wire.id, &close(false), false)
+            .await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        repo.update_task("tester", plaster.id, &close(true), false)
+            .await
+            .unwrap();
+
+        // Only open blockers count
+        repo.update_task("tester", loan.id, &close(false), false)
+            .await
+            .unwrap();
+        assert_eq!(repo.count_open_blockers(wire.id).await.unwrap(), 0);
+
+        // Removing dependencies
+        repo.remove_dependency("tester", &paint, &plaster)
+            .await
+            .unwrap();
+        assert!(repo.list_blockers(paint.id).await.unwrap().is_empty());
+        repo.add_dependency("tester", &loan, &paint).await.unwrap();
+
+        // Stories with too many tasks aren't sorted partially
+        sqlx::query!(
+            r#"INSERT INTO tasks (story_id, name, position)
+            SELECT $1, 'Chore ' || n, n FROM generate_series(1, 1000) AS n"#,
+            money.id,
+        )
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+        let result = repo.list_tasks_by_dependencies(money.id).await;
+        assert!(matches!(result, Err(Error::Unprocessable { .. })));
+    }
+}
@@ -0,0 +1,169 @@
+use super::{webhook, Repo};
+use crate::{
+    domain::{Event, EventKind},
+    Error, Result,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+/// The channel used to notify listeners of new events.
+pub(crate) const EVENTS_CHANNEL: &str = "events";
+
+// Extend repo with queries related to events.
+impl Repo {
+    /// Select an event by id
+    pub async fn fetch_event(&self, event_id: i64) -> Result<Event> {
+        let query = sqlx::query_as!(
+            Event,
+            r#"SELECT id, story_id, kind, entity_id, payload, created_at
+            FROM events WHERE id = $1"#,
+            event_id,
+        );
+        match query.fetch_optional(self.db_ref()).await? {
+            Some(event) => Ok(event),
+            None => Err(Error::not_found(format!("event not found: {event_id}"))),
+        }
+    }
+
+    /// Select a page of events for a story that occurred after an event id.
+    pub async fn list_events(&self, story_id: Uuid, after: i64, limit: i32) -> Result<Vec<Event>> {
+        let query = sqlx::query_as!(
+            Event,
+            r#"SELECT id, story_id, kind, entity_id, payload, created_at
+            FROM events WHERE story_id = $1 AND id > $2
+            ORDER BY id LIMIT $3"#,
+            story_id,
+            after,
+            limit as i64,
+        );
+        let events = query.fetch_all(self.db_ref()).await?;
+        Ok(events)
+    }
+
+    /// Select a page of events across all stories that occurred after an event id.
+    pub async fn list_events_after(&self, after: i64, limit: i32) -> Result<Vec<Event>> {
+        let query = sqlx::query_as!(
+            Event,
+            r#"SELECT id, story_id, kind, entity_id, payload, created_at
+            FROM events WHERE id > $1
+            ORDER BY id LIMIT $2"#,
+            after,
+            limit as i64,
+        );
+        let events = query.fetch_all(self.db_ref()).await?;
+        Ok(events)
+    }
+
+    /// Get the id of the most recent event.
+    pub async fn last_event_id(&self) -> Result<i64> {
+        let id = sqlx::query_scalar!(r#"SELECT coalesce(max(id), 0) AS "id!" FROM events"#)
+            .fetch_one(self.db_ref())
+            .await?;
+        Ok(id)
+    }
+
+    /// Delete events that occurred before a cutoff.
+    pub async fn purge_events(&self, expired_before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query!("DELETE FROM events WHERE created_at < $1", expired_before)
+            .execute(self.db_ref())
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Record an event for a changed entity, queue webhook deliveries, and notify listeners when the
+/// transaction commits.
+pub(super) async fn publish<T: Serialize>(
+    conn: &mut PgConnection,
+    story_id: Uuid,
+    kind: EventKind,
+    entity_id: Uuid,
+    entity: &T,
+) -> Result<()> {
+    let payload = serde_json::to_value(entity)?;
+    let event = sqlx::query_as!(
+        Event,
+        r#"INSERT INTO events (story_id, kind, entity_id, payload) VALUES ($1, $2, $3, $4)
+        RETURNING id, story_id, kind, entity_id, payload, created_at"#,
+        story_id,
+        kind.to_string(),
+        entity_id,
+        payload,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    webhook::enqueue(conn, &event).await?;
+    sqlx::query!(
+        "SELECT pg_notify($1, $2)",
+        EVENTS_CHANNEL,
+        event.id.to_string()
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::Status, repo::tests};
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+
+        // Make some changes
+        let story = repo
+            .create_story("tester", "Chores".to_string(), None, Default::default())
+            .await
+            .unwrap();
+        let task = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Laundry".to_string(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.delete_task("tester", task.id).await.unwrap();
+
+        // Query events for the story
+        let events = repo.list_events(story.id, 0, 10).await.unwrap();
+        let kinds: Vec<_> = events.iter().filter_map(|e| e.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::StoryCreated,
+                EventKind::TaskCreated,
+                EventKind::TaskDeleted
+            ]
+        );
+
+        // Query events after the first one
+        let events = repo.list_events_after(events[0].id, 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].entity_id, task.id);
+
+        // Fetch the latest event
+        let last_id = repo.last_event_id().await.unwrap();
+        let event = repo.fetch_event(last_id).await.unwrap();
+        assert_eq!(event.kind(), Some(EventKind::TaskDeleted));
+
+        // Cleanup
+        repo.delete_story("tester", story.id).await.unwrap();
+        assert_eq!(repo.purge_events(Utc::now()).await.unwrap(), 4);
+    }
+}
@@ -1,14 +1,16 @@
-use super::Repo;
-use crate::{domain::StoryFile, Error, Result};
+use super::{activity, event, task, Repo};
+use crate::{
+    domain::{EventKind, FileSort, Keyset, SortOrder, StoryFile},
+    Error, Result,
+};
+use sqlx::{postgres::PgConnection, QueryBuilder};
 use uuid::Uuid;
 
-// Defines a reasonable limit on the max files per story.
-const MAX_FILES: i16 = 100;
-
 impl Repo {
     /// Insert a new file metadata row.
     pub async fn create_file(
         &self,
+        actor: &str,
         story_id: Uuid,
         storage_id: Uuid,
         name: String,
@@ -22,37 +24,80 @@ impl Repo {
             StoryFile,
             r#"INSERT INTO story_files (story_id, storage_id, name, size, content_type)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, story_id, storage_id, name, size, content_type, created_at, updated_at"#,
+            RETURNING id, story_id, storage_id, name, size, content_type, seqno, created_at,
+            updated_at, deleted_at"#,
             story_id,
             storage_id,
             name,
             size,
             content_type,
         );
-        let story_file = query.fetch_one(self.db_ref()).await?;
-        Ok(story_file)
+        let mut tx = self.db.begin().await?;
+        // Keep the story from being trashed until the file is added
+        task::lock_story(&mut tx, story_id).await?;
+        let file = query.fetch_one(&mut *tx).await?;
+        let kind = EventKind::FileCreated;
+        activity::record(
+            &mut tx,
+            actor,
+            file.story_id,
+            kind,
+            file.id,
+            None,
+            Some(&file),
+        )
+        .await?;
+        event::publish(&mut tx, file.story_id, kind, file.id, &file).await?;
+        tx.commit().await?;
+        Ok(file)
     }
 
-    /// List all files for a story.
-    pub async fn list_files(&self, story_id: Uuid) -> Result<Vec<StoryFile>> {
-        let query = sqlx::query_as!(
-            StoryFile,
-            r#"SELECT id, story_id, storage_id, name, size, content_type, created_at, updated_at
-            FROM story_files WHERE story_id = $1
-            ORDER BY created_at LIMIT $2"#,
-            story_id,
-            MAX_FILES as i64,
+    /// Select a page of files for a story in sort order, starting after a keyset.
+    pub async fn list_files(
+        &self,
+        story_id: Uuid,
+        sort: FileSort,
+        order: SortOrder,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<StoryFile>)> {
+        // Only whitelisted identifiers are pushed into the query; all values are bound.
+        let (column, cast) = match sort {
+            FileSort::Name => ("name", "text"),
+            FileSort::Size => ("size", "bigint"),
+            FileSort::CreatedAt => ("created_at", "timestamptz"),
+        };
+        let (cmp, dir) = match order {
+            SortOrder::Asc => (">", "ASC"),
+            SortOrder::Desc => ("<", "DESC"),
+        };
+        let mut query = QueryBuilder::new(
+            r#"SELECT id, story_id, storage_id, name, size, content_type, seqno, created_at,
+            updated_at, deleted_at
+            FROM story_files WHERE deleted_at IS NULL AND story_id = "#,
         );
-        let story_files = query.fetch_all(self.db_ref()).await?;
-        Ok(story_files)
+        query.push_bind(story_id);
+        if let Some(after) = after {
+            query.push(format!(" AND ({column}, seqno) {cmp} ("));
+            query.push_bind(after.key);
+            query.push(format!("::{cast}, "));
+            query.push_bind(after.seqno);
+            query.push(")");
+        }
+        query.push(format!(" ORDER BY {column} {dir}, seqno {dir} LIMIT "));
+        query.push_bind(limit as i64);
+        let files: Vec<StoryFile> = query.build_query_as().fetch_all(self.db_ref()).await?;
+        let next_keyset = files.last().map(|f| f.keyset(sort));
+        Ok((next_keyset, files))
     }
 
     /// Select a file by id and story id
     pub async fn fetch_file(&self, story_id: Uuid, file_id: Uuid) -> Result<StoryFile> {
         let query = sqlx::query_as!(
             StoryFile,
-            r#"SELECT id, story_id, storage_id, name, size, content_type, created_at, updated_at
-            FROM story_files WHERE id = $1 AND story_id = $2"#,
+            r#"SELECT id, story_id, storage_id, name, size, content_type, seqno, created_at,
+            updated_at, deleted_at
+            FROM story_files WHERE id = $1 AND story_id = $2 AND deleted_at IS NULL"#,
             file_id,
             story_id,
         );
@@ -62,15 +107,57 @@ impl Repo {
         }
     }
 
-    /// Delete a file
-    pub async fn delete_file(&self, file: StoryFile) -> Result<StoryFile> {
-        sqlx::query!("DELETE FROM story_files WHERE id = $1", file.id)
-            .execute(self.db_ref())
-            .await?;
-        Ok(file)
+    /// Move a file to the trash. Contents stay in storage until the file is purged.
+    pub async fn delete_file(&self, actor: &str, file: StoryFile) -> Result<StoryFile> {
+        let mut tx = self.db.begin().await?;
+        // Keep the story from being trashed while the file is, so they share a deletion time
+        task::lock_story(&mut tx, file.story_id).await?;
+        let deleted = sqlx::query_as!(
+            StoryFile,
+            r#"UPDATE story_files SET deleted_at = now()
+            WHERE id = $1 AND story_id = $2 AND deleted_at IS NULL
+            RETURNING id, story_id, storage_id, name, size, content_type, seqno, created_at,
+            updated_at, deleted_at"#,
+            file.id,
+            file.story_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("file not found: {}", file.id)))?;
+        let kind = EventKind::FileDeleted;
+        activity::record(
+            &mut tx,
+            actor,
+            file.story_id,
+            kind,
+            file.id,
+            Some(&file),
+            Some(&deleted),
+        )
+        .await?;
+        event::publish(&mut tx, file.story_id, kind, file.id, &deleted).await?;
+        tx.commit().await?;
+        Ok(deleted)
     }
 }
 
+/// Filter storage ids down to contents no longer referenced by any file, as part of a
+/// transaction. Cloned stories and templates share contents with the files they came from.
+pub(super) async fn unreferenced(
+    conn: &mut PgConnection,
+    storage_ids: Vec<Uuid>,
+) -> Result<Vec<Uuid>> {
+    let storage_ids = sqlx::query_scalar!(
+        r#"SELECT DISTINCT s.id AS "id!" FROM unnest($1::uuid[]) AS s(id)
+        WHERE NOT EXISTS (SELECT 1 FROM story_files WHERE storage_id = s.id)
+        AND NOT EXISTS (SELECT 1 FROM template_files WHERE storage_id = s.id)"#,
+        &storage_ids,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(storage_ids)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,7 +177,10 @@ mod tests {
 
         // Create story
         let name = "Project Requirements".to_string();
-        let story = repo.create_story(name.clone()).await.unwrap();
+        let story = repo
+            .create_story("tester", name.clone(), None, Default::default())
+            .await
+            .unwrap();
         assert_eq!(name, story.name);
 
         // Test file metadata
@@ -101,7 +191,7 @@ mod tests {
 
         // Add file
         let inserted = repo
-            .create_file(story.id, storage_id.clone(), name, size, content_type)
+            .create_file("tester", story.id, storage_id, name, size, content_type)
             .await
             .unwrap();
 
@@ -110,16 +200,81 @@ mod tests {
         assert_eq!(file.storage_id, storage_id);
 
         // List files
-        let files = repo.list_files(story.id).await.unwrap();
+        let (_, files) = repo
+            .list_files(story.id, FileSort::default(), SortOrder::Asc, None, 10)
+            .await
+            .unwrap();
         assert_eq!(files.len(), 1);
         assert!(files.contains(&file));
 
-        // Delete file
-        repo.delete_file(file).await.unwrap();
-        let files = repo.list_files(story.id).await.unwrap();
-        assert!(files.is_empty());
+        // Add more files
+        for (name, size) in [("u.txt", 30), ("v.txt", 20), ("t.txt", 20)] {
+            repo.create_file(
+                "tester",
+                story.id,
+                Uuid::new_v4(),
+                name.into(),
+                size,
+                "text/plain".into(),
+            )
+            .await
+            .unwrap();
+        }
+
+        // Page through files sorted by name
+        let sort = FileSort::Name;
+        let (after, files) = repo
+            .list_files(story.id, sort, SortOrder::Asc, None, 2)
+            .await
+            .unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Sequence Diagrams.png", "t.txt"]);
+        let (_, files) = repo
+            .list_files(story.id, sort, SortOrder::Asc, after, 2)
+            .await
+            .unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["u.txt", "v.txt"]);
+
+        // Page through files sorted by size, largest first, with ties newest first
+        let sort = FileSort::Size;
+        let (after, files) = repo
+            .list_files(story.id, sort, SortOrder::Desc, None, 3)
+            .await
+            .unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Sequence Diagrams.png", "u.txt", "t.txt"]);
+        let (_, files) = repo
+            .list_files(story.id, sort, SortOrder::Desc, after, 3)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "v.txt");
+
+        // Page through files by date, newest first
+        let sort = FileSort::CreatedAt;
+        let (after, files) = repo
+            .list_files(story.id, sort, SortOrder::Desc, None, 2)
+            .await
+            .unwrap();
+        assert_eq!(files[0].name, "t.txt");
+        let (_, files) = repo
+            .list_files(story.id, sort, SortOrder::Desc, after, 2)
+            .await
+            .unwrap();
+        assert_eq!(files[1].id, file.id);
+
+        // Delete file, only once
+        repo.delete_file("tester", file.clone()).await.unwrap();
+        let result = repo.delete_file("tester", file).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let (_, files) = repo
+            .list_files(story.id, sort, SortOrder::Asc, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 3);
 
         // Cleanup
-        repo.delete_story(story.id).await.unwrap();
+        repo.delete_story("tester", story.id).await.unwrap();
     }
 }
@@ -0,0 +1,204 @@
+use super::Repo;
+use crate::{
+    domain::{IdempotencyClaim, IdempotencyKey},
+    Error, Result,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+// Extend repo with queries related to idempotency keys.
+impl Repo {
+    /// Claim an idempotency key for a request fingerprint, returning a token for the claim. When
+    /// the key has already been claimed, the existing (unexpired) key is returned instead. Claims
+    /// still without a response after their lease, like those of requests that were dropped, can
+    /// be taken over.
+    pub async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        expired_before: DateTime<Utc>,
+        lease_expired_before: DateTime<Utc>,
+    ) -> Result<IdempotencyClaim> {
+        let mut tx = self.db.begin().await?;
+
+        // Clear out an expired claim so the key can be reused
+        sqlx::query!(
+            r#"DELETE FROM idempotency_keys WHERE key = $1
+            AND (created_at < $2 OR (status_code IS NULL AND created_at < $3))"#,
+            key,
+            expired_before,
+            lease_expired_before,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let claim = Uuid::new_v4();
+        let claimed = sqlx::query!(
+            r#"INSERT INTO idempotency_keys (key, fingerprint, claim) VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO NOTHING"#,
+            key,
+            fingerprint,
+            claim,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        let result = if claimed {
+            IdempotencyClaim::Claimed(claim)
+        } else {
+            let query = sqlx::query_as!(
+                IdempotencyKey,
+                r#"SELECT key, fingerprint, status_code, content_type, body, created_at
+                FROM idempotency_keys WHERE key = $1"#,
+                key,
+            );
+            // The key may have been released since the insert, while its request was finishing
+            let existing = query.fetch_optional(&mut *tx).await?.ok_or_else(|| {
+                Error::conflict("a request with this idempotency key is in progress".into())
+            })?;
+            IdempotencyClaim::Existing(existing)
+        };
+
+        tx.commit().await?;
+
+        Ok(result)
+    }
+
+    /// Store the response for a claimed idempotency key. Fails when the claim was taken over.
+    pub async fn complete_idempotency_key(
+        &self,
+        key: &str,
+        claim: Uuid,
+        status_code: i16,
+        content_type: Option<String>,
+        body: Vec<u8>,
+    ) -> Result<()> {
+        let result = sqlx::query!(
+            r#"UPDATE idempotency_keys SET status_code = $1, content_type = $2, body = $3
+            WHERE key = $4 AND claim = $5 AND status_code IS NULL"#,
+            status_code,
+            content_type,
+            body,
+            key,
+            claim,
+        )
+        .execute(self.db_ref())
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(lost_claim(key));
+        }
+        Ok(())
+    }
+
+    /// Release a claimed idempotency key, allowing the request to be retried. Fails when the
+    /// claim was taken over.
+    pub async fn release_idempotency_key(&self, key: &str, claim: Uuid) -> Result<()> {
+        let result = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE key = $1 AND claim = $2",
+            key,
+            claim,
+        )
+        .execute(self.db_ref())
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(lost_claim(key));
+        }
+        Ok(())
+    }
+
+    /// Delete all expired idempotency keys.
+    pub async fn purge_idempotency_keys(&self, expired_before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE created_at < $1",
+            expired_before,
+        )
+        .execute(self.db_ref())
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// The error for a claim that was taken over by a retry, or expired.
+fn lost_claim(key: &str) -> Error {
+    Error::conflict(format!("idempotency key {key}: claim was lost"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::tests;
+    use chrono::Duration;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+        let expired_before = Utc::now() - Duration::hours(1);
+        let lease_expired_before = Utc::now() - Duration::minutes(1);
+
+        // Claim a key
+        let key = "2d1f1b36-5f1e-4a4e-9f53-0b0c7e0a6f6e";
+        let claim = |lease_expired_before| {
+            repo.claim_idempotency_key(key, "fingerprint", expired_before, lease_expired_before)
+        };
+        let IdempotencyClaim::Claimed(first) = claim(lease_expired_before).await.unwrap() else {
+            panic!("expected the key to be claimed");
+        };
+
+        // Claiming again returns the in-progress key
+        let IdempotencyClaim::Existing(existing) = claim(lease_expired_before).await.unwrap()
+        else {
+            panic!("expected the existing key");
+        };
+        assert!(!existing.is_complete());
+
+        // An abandoned claim can be taken over once its lease runs out
+        let IdempotencyClaim::Claimed(second) = claim(Utc::now()).await.unwrap() else {
+            panic!("expected the key to be claimed");
+        };
+        assert_ne!(first, second);
+
+        // The first request lost its claim, so it can't store a response or release the key
+        let body = br#"{"name":"Books To Read"}"#.to_vec();
+        let content_type = Some("application/json".to_string());
+        let result = repo
+            .complete_idempotency_key(key, first, 201, content_type.clone(), body.clone())
+            .await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        let result = repo.release_idempotency_key(key, first).await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+
+        // Store a response and ensure it comes back on the next claim
+        repo.complete_idempotency_key(key, second, 201, content_type, body.clone())
+            .await
+            .unwrap();
+        let IdempotencyClaim::Existing(existing) = claim(lease_expired_before).await.unwrap()
+        else {
+            panic!("expected the existing key");
+        };
+        assert_eq!(existing.status_code, Some(201));
+        assert_eq!(existing.body, Some(body));
+
+        // A completed claim isn't taken over after its lease
+        let existing = claim(Utc::now()).await.unwrap();
+        assert!(matches!(existing, IdempotencyClaim::Existing(_)));
+
+        // Release the key, making it claimable again
+        repo.release_idempotency_key(key, second).await.unwrap();
+        let result = claim(lease_expired_before).await.unwrap();
+        assert!(matches!(result, IdempotencyClaim::Claimed(_)));
+
+        // Expired keys are purged
+        let purged = repo.purge_idempotency_keys(Utc::now()).await.unwrap();
+        assert_eq!(purged, 1);
+    }
+}
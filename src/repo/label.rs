@@ -0,0 +1,353 @@
+use super::{activity, event, Repo};
+use crate::{
+    domain::{EventKind, Label},
+    Error, Result,
+};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Defines a reasonable limit on the number of labels.
+const MAX_LABELS: i64 = 1000;
+
+// Extend repo with queries related to labels.
+impl Repo {
+    /// Insert a new label
+    pub async fn create_label(&self, name: String, color: String) -> Result<Label> {
+        let query = sqlx::query_as!(
+            Label,
+            r#"INSERT INTO labels (name, color) VALUES ($1, $2) ON CONFLICT DO NOTHING
+            RETURNING id, name, color, created_at, updated_at"#,
+            name,
+            color,
+        );
+        match query.fetch_optional(self.db_ref()).await? {
+            Some(label) => Ok(label),
+            None => Err(Error::conflict(format!("label already exists: {name}"))),
+        }
+    }
+
+    /// Select a label by id
+    pub async fn fetch_label(&self, label_id: Uuid) -> Result<Label> {
+        let query = sqlx::query_as!(
+            Label,
+            "SELECT id, name, color, created_at, updated_at FROM labels WHERE id = $1",
+            label_id,
+        );
+        match query.fetch_optional(self.db_ref()).await? {
+            Some(label) => Ok(label),
+            None => Err(Error::not_found(format!("label not found: {label_id}"))),
+        }
+    }
+
+    /// Select all labels in name order
+    pub async fn list_labels(&self) -> Result<Vec<Label>> {
+        let query = sqlx::query_as!(
+            Label,
+            r#"SELECT id, name, color, created_at, updated_at FROM labels
+            ORDER BY lower(name) LIMIT $1"#,
+            MAX_LABELS,
+        );
+        let labels = query.fetch_all(self.db_ref()).await?;
+        Ok(labels)
+    }
+
+    /// Update label name and color
+    pub async fn update_label(&self, label_id: Uuid, name: String, color: String) -> Result<Label> {
+        let query = sqlx::query_as!(
+            Label,
+            r#"UPDATE labels SET name = $1, color = $2, updated_at = now() WHERE id = $3
+            RETURNING id, name, color, created_at, updated_at"#,
+            name,
+            color,
+            label_id,
+        );
+        match query.fetch_one(self.db_ref()).await {
+            Ok(label) => Ok(label),
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+                Err(Error::conflict(format!("label already exists: {name}")))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Delete a label, removing it from all stories and tasks.
+    pub async fn delete_label(&self, label_id: Uuid) -> Result<()> {
+        sqlx::query!("DELETE FROM labels WHERE id = $1", label_id)
+            .execute(self.db_ref())
+            .await?;
+        Ok(())
+    }
+
+    /// Select the labels of a story in name order
+    pub async fn list_story_labels(&self, story_id: Uuid) -> Result<Vec<Label>> {
+        let query = sqlx::query_as!(
+            Label,
+            r#"SELECT l.id, l.name, l.color, l.created_at, l.updated_at
+            FROM labels l JOIN story_labels sl ON sl.label_id = l.id
+            WHERE sl.story_id = $1 ORDER BY lower(l.name)"#,
+            story_id,
+        );
+        let labels = query.fetch_all(self.db_ref()).await?;
+        Ok(labels)
+    }
+
+    /// Add a label to a story. Adding a label the story already has is a no-op.
+    pub async fn add_story_label(&self, actor: &str, story_id: Uuid, label: &Label) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let added = sqlx::query!(
+            r#"INSERT INTO story_labels (story_id, label_id) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING"#,
+            story_id,
+            label.id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if added > 0 {
+            let kind = EventKind::LabelAdded;
+            record(&mut tx, actor, story_id, kind, story_id, label).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Remove a label from a story.
+    pub async fn remove_story_label(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        label: &Label,
+    ) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let removed = sqlx::query!(
+            "DELETE FROM story_labels WHERE story_id = $1 AND label_id = $2",
+            story_id,
+            label.id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if removed > 0 {
+            let kind = EventKind::LabelRemoved;
+            record(&mut tx, actor, story_id, kind, story_id, label).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Select the labels of a task in name order
+    pub async fn list_task_labels(&self, task_id: Uuid) -> Result<Vec<Label>> {
+        let query = sqlx::query_as!(
+            Label,
+            r#"SELECT l.id, l.name, l.color, l.created_at, l.updated_at
+            FROM labels l JOIN task_labels tl ON tl.label_id = l.id
+            WHERE tl.task_id = $1 ORDER BY lower(l.name)"#,
+            task_id,
+        );
+        let labels = query.fetch_all(self.db_ref()).await?;
+        Ok(labels)
+    }
+
+    /// Add a label to a task. Adding a label the task already has is a no-op.
+    pub async fn add_task_label(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        task_id: Uuid,
+        label: &Label,
+    ) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let added = sqlx::query!(
+            r#"INSERT INTO task_labels (task_id, label_id) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING"#,
+            task_id,
+            label.id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if added > 0 {
+            let kind = EventKind::LabelAdded;
+            record(&mut tx, actor, story_id, kind, task_id, label).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Remove a label from a task.
+    pub async fn remove_task_label(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        task_id: Uuid,
+        label: &Label,
+    ) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let removed = sqlx::query!(
+            "DELETE FROM task_labels WHERE task_id = $1 AND label_id = $2",
+            task_id,
+            label.id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if removed > 0 {
+            let kind = EventKind::LabelRemoved;
+            record(&mut tx, actor, story_id, kind, task_id, label).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Record a label being added to or removed from a story or task, and publish it as an event.
+async fn record(
+    conn: &mut PgConnection,
+    actor: &str,
+    story_id: Uuid,
+    kind: EventKind,
+    entity_id: Uuid,
+    label: &Label,
+) -> Result<()> {
+    let (before, after) = match kind {
+        EventKind::LabelAdded => (None, Some(label)),
+        _ => (Some(label), None),
+    };
+    activity::record(conn, actor, story_id, kind, entity_id, before, after).await?;
+    event::publish(conn, story_id, kind, entity_id, label).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{LabelFilter, LabelMatch, Status, StoryFilter, TaskFilter},
+        repo::{tests, Repo},
+    };
+    use std::sync::Arc;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(Arc::clone(&pool));
+
+        // Create labels, names are unique ignoring case
+        let backend = repo
+            .create_label("Backend".into(), "#1f883d".into())
+            .await
+            .unwrap();
+        let design = repo
+            .create_label("design".into(), "#8250df".into())
+            .await
+            .unwrap();
+        assert!(repo
+            .create_label("backend".into(), "#000000".into())
+            .await
+            .is_err());
+        assert!(repo
+            .update_label(design.id, "BACKEND".into(), "#000000".into())
+            .await
+            .is_err());
+        let labels = repo.list_labels().await.unwrap();
+        assert_eq!(labels, vec![backend.clone(), design.clone()]);
+
+        // Label stories, adding a label twice is a no-op
+        let api = repo
+            .create_story("tester", "API".into(), None, Default::default())
+            .await
+            .unwrap();
+        let site = repo
+            .create_story("tester", "Site".into(), None, Default::default())
+            .await
+            .unwrap();
+        repo.add_story_label("tester", api.id, &backend)
+            .await
+            .unwrap();
+        repo.add_story_label("tester", api.id, &backend)
+            .await
+            .unwrap();
+        repo.add_story_label("tester", api.id, &design)
+            .await
+            .unwrap();
+        repo.add_story_label("tester", site.id, &design)
+            .await
+            .unwrap();
+        let labels = repo.list_story_labels(api.id).await.unwrap();
+        assert_eq!(labels.len(), 2);
+        let (_, activity) = repo.list_activity(api.id, 1, 10).await.unwrap();
+        assert_eq!(activity.len(), 3);
+
+        // Filter stories by all or any of a set of labels
+        let labels = |mode| {
+            Some(LabelFilter {
+                names: vec!["backend".into(), "design".into()],
+                mode,
+            })
+        };
+        let filter = StoryFilter {
+            labels: labels(LabelMatch::All),
+            ..Default::default()
+        };
+        assert_eq!(repo.count_stories(&filter).await.unwrap(), (1, false));
+        let filter = StoryFilter {
+            labels: labels(LabelMatch::Any),
+            ..Default::default()
+        };
+        assert_eq!(repo.count_stories(&filter).await.unwrap(), (2, false));
+
+        // Label tasks, and filter them the same way
+        let status = Status::Incomplete.as_ref();
+        let t1 = repo
+            .create_task("tester", api.id, None, "Auth".into(), Some(status), None)
+            .await
+            .unwrap();
+        let t2 = repo
+            .create_task("tester", api.id, None, "Docs".into(), Some(status), None)
+            .await
+            .unwrap();
+        repo.add_task_label("tester", api.id, t1.id, &backend)
+            .await
+            .unwrap();
+        repo.add_task_label("tester", api.id, t1.id, &design)
+            .await
+            .unwrap();
+        repo.add_task_label("tester", api.id, t2.id, &design)
+            .await
+            .unwrap();
+        let filter = TaskFilter {
+            labels: labels(LabelMatch::All),
+            ..Default::default()
+        };
+        let (_, tasks) = repo.list_tasks(api.id, &filter, None, 10).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, t1.id);
+        let filter = TaskFilter {
+            labels: labels(LabelMatch::Any),
+            ..Default::default()
+        };
+        let (_, tasks) = repo.list_tasks(api.id, &filter, None, 10).await.unwrap();
+        assert_eq!(tasks.len(), 2);
+
+        // Removing labels
+        repo.remove_task_label("tester", api.id, t1.id, &design)
+            .await
+            .unwrap();
+        assert_eq!(repo.list_task_labels(t1.id).await.unwrap(), vec![backend]);
+        repo.remove_story_label("tester", site.id, &design)
+            .await
+            .unwrap();
+        assert!(repo.list_story_labels(site.id).await.unwrap().is_empty());
+
+        // Deleting a label removes it everywhere
+        repo.delete_label(design.id).await.unwrap();
+        assert!(repo.fetch_label(design.id).await.is_err());
+        assert_eq!(repo.list_story_labels(api.id).await.unwrap().len(), 1);
+        assert!(repo.list_task_labels(t2.id).await.unwrap().is_empty());
+    }
+}
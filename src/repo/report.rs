@@ -0,0 +1,239 @@
+use super::Repo;
+use crate::{
+    domain::{Burndown, CycleTime, DailyTasks, EventKind},
+    Result,
+};
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+// Extend repo with task reports, computed from the status history in activity.
+impl Repo {
+    /// Count tasks created and completed on each day of a date range, along with the tasks left
+    /// open at the end of each day. Only live tasks count, in one story or all stories. The tasks
+    /// left open are worked back from the current open count, so the last day always matches it
+    /// even if workflows changed since.
+    pub async fn burndown(
+        &self,
+        story_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Burndown> {
+        let days = sqlx::query_as!(
+            DailyTasks,
+            r#"WITH live AS (
+                SELECT t.id, t.story_id, t.status, t.created_at
+                FROM tasks t JOIN stories s ON s.id = t.story_id
+                WHERE t.deleted_at IS NULL AND s.deleted_at IS NULL
+                AND ($1::uuid IS NULL OR t.story_id = $1)
+            ),
+            transitions AS (
+                SELECT a.created_at,
+                coalesce(task_is_closed(a.story_id, a.changes->'status'->>'before'), false)
+                    AS closed_before,
+                coalesce(task_is_closed(a.story_id, a.changes->'status'->>'after'), false)
+                    AS closed_after
+                FROM activity a JOIN live ON live.id = a.entity_id
+                WHERE a.kind = ANY($4) AND a.changes ? 'status'
+            ),
+            changes AS (
+                SELECT (created_at AT TIME ZONE 'UTC')::date AS day, 1 AS created,
+                0 AS completed, 1 AS opened
+                FROM live
+                UNION ALL
+                SELECT (created_at AT TIME ZONE 'UTC')::date, 0, closed_after::int,
+                closed_before::int - closed_after::int
+                FROM transitions WHERE closed_before <> closed_after
+            ),
+            daily AS (
+                SELECT day, sum(created) AS created, sum(completed) AS completed,
+                sum(opened) AS opened
+                FROM changes GROUP BY day
+            ),
+            open AS (
+                SELECT count(*) FILTER (WHERE NOT task_is_closed(story_id, status)) AS n
+                FROM live
+            )
+            SELECT d::date AS "date!", coalesce(daily.created, 0)::bigint AS "created!",
+            coalesce(daily.completed, 0)::bigint AS "completed!",
+            greatest((SELECT n FROM open)
+                - (SELECT coalesce(sum(opened), 0) FROM daily WHERE daily.day > d::date), 0)::bigint
+                AS "remaining!"
+            FROM generate_series($2::date, $3::date, interval '1 day') AS d
+            LEFT JOIN daily ON daily.day = d::date
+            ORDER BY d"#,
+            story_id,
+            from,
+            to,
+            &status_change_kinds(),
+        )
+        .fetch_all(self.db_ref())
+        .await?;
+        Ok(Burndown {
+            story_id,
+            from,
+            to,
+            days,
+        })
+    }
+
+    /// Select percentiles of the time from creating to closing tasks, for the live tasks that
+    /// are closed, and were last closed in a date range, in one story or all stories.
+    pub async fn cycle_time(
+        &self,
+        story_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<CycleTime> {
+        let row = sqlx::query!(
+            r#"WITH closed AS (
+                SELECT t.created_at, max(a.created_at) AS closed_at
+                FROM tasks t
+                JOIN stories s ON s.id = t.story_id
+                JOIN activity a ON a.entity_id = t.id
+                WHERE t.deleted_at IS NULL AND s.deleted_at IS NULL
+                AND ($1::uuid IS NULL OR t.story_id = $1)
+                AND task_is_closed(t.story_id, t.status)
+                AND a.kind = ANY($4) AND a.changes ? 'status'
+                AND coalesce(task_is_closed(a.story_id, a.changes->'status'->>'after'), false)
+                AND NOT coalesce(task_is_closed(a.story_id, a.changes->'status'->>'before'), false)
+                GROUP BY t.id, t.created_at
+            ),
+            durations AS (
+                SELECT extract(epoch FROM closed_at - created_at)::float8 AS secs FROM closed
+                WHERE (closed_at AT TIME ZONE 'UTC')::date BETWEEN $2 AND $3
+            )
+            SELECT count(*) AS "tasks!",
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY secs)::bigint AS p50_secs,
+            percentile_cont(0.75) WITHIN GROUP (ORDER BY secs)::bigint AS p75_secs,
+            percentile_cont(0.9) WITHIN GROUP (ORDER BY secs)::bigint AS p90_secs,
+            percentile_cont(0.95) WITHIN GROUP (ORDER BY secs)::bigint AS p95_secs
+            FROM durations"#,
+            story_id,
+            from,
+            to,
+            &status_change_kinds(),
+        )
+        .fetch_one(self.db_ref())
+        .await?;
+        Ok(CycleTime {
+            story_id,
+            from,
+            to,
+            tasks: row.tasks,
+            p50_secs: row.p50_secs,
+            p75_secs: row.p75_secs,
+            p90_secs: row.p90_secs,
+            p95_secs: row.p95_secs,
+        })
+    }
+}
+
+/// The kinds of activity that can change the status of a task.
+fn status_change_kinds() -> Vec<String> {
+    [EventKind::TaskCreated, EventKind::TaskUpdated]
+        .iter()
+        .map(|kind| kind.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{Status, TaskChanges},
+        repo::{tests, Repo},
+    };
+    use chrono::{Days, Utc};
+    use std::sync::Arc;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(Arc::clone(&pool));
+
+        // Create three tasks, complete two of them and reopen one
+        let story = repo
+            .create_story("tester", "Sprint".into(), None, Default::default())
+            .await
+            .unwrap();
+        let (incomplete, complete) = (Status::Incomplete.as_ref(), Status::Complete.as_ref());
+        let mut tasks = Vec::new();
+        for name in ["Design", "Build", "Ship"] {
+            let task = repo
+                .create_task(
+                    "tester",
+                    story.id,
+                    None,
+                    name.into(),
+                    Some(incomplete),
+                    None,
+                )
+                .await
+                .unwrap();
+            tasks.push(task);
+        }
+        for task in &tasks[..2] {
+            repo.update_task(
+                "tester",
+                task.id,
+                &TaskChanges {
+                    status: Some(complete.into()),
+                    ..Default::default()
+                },
+                false,
+            )
+            .await
+            .unwrap();
+        }
+        let build = &tasks[1];
+        repo.update_task(
+            "tester",
+            build.id,
+            &TaskChanges {
+                status: Some(incomplete.into()),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+
+        // Tasks created closed never count as open
+        repo.create_task(
+            "tester",
+            story.id,
+            None,
+            "Retro".into(),
+            Some(complete),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Daily counts run up to today, ending with the tasks open now
+        let today = Utc::now().date_naive();
+        let from = today.checked_sub_days(Days::new(2)).unwrap();
+        let burndown = repo.burndown(Some(story.id), from, today).await.unwrap();
+        assert_eq!(burndown.days.len(), 3);
+        assert_eq!(burndown.days[0].remaining, 0);
+        let last = burndown.days.last().unwrap();
+        assert_eq!(last.date, today);
+        assert_eq!((last.created, last.completed, last.remaining), (4, 3, 2));
+        let summary = &repo.list_story_summaries(&[story.id]).await.unwrap()[0];
+        assert_eq!(last.remaining, summary.tasks - summary.closed_tasks);
+
+        // Only the tasks that are still closed count toward cycle time
+        let cycle_time = repo.cycle_time(Some(story.id), from, today).await.unwrap();
+        assert_eq!(cycle_time.tasks, 2);
+        assert!(cycle_time.p50_secs.is_some());
+        let cycle_time = repo.cycle_time(Some(story.id), from, from).await.unwrap();
+        assert_eq!(cycle_time.tasks, 0);
+        assert_eq!(cycle_time.p95_secs, None);
+    }
+}
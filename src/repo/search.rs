@@ -0,0 +1,197 @@
+use super::Repo;
+use crate::{domain::SearchHit, Result};
+
+// Markers ts_headline puts around matching terms.
+const START_SEL: char = '\u{2}';
+const STOP_SEL: char = '\u{3}';
+
+// Extend repo with full-text search.
+impl Repo {
+    /// Select a page of live stories, tasks, and files with names matching a web search style
+    /// query, best matches first. The cursor is the position of the first hit, starting at 1.
+    pub async fn search(&self, q: &str, cursor: i64, limit: i32) -> Result<(i64, Vec<SearchHit>)> {
+        let options = format!("StartSel={START_SEL}, StopSel={STOP_SEL}, HighlightAll=true");
+        let query = sqlx::query_as!(
+            SearchHit,
+            r#"WITH q AS (SELECT websearch_to_tsquery('english', $1) AS query)
+            SELECT h.kind AS "kind!", h.id AS "id!", h.story_id AS "story_id!",
+            h.story_name AS "story_name!", h.name AS "name!",
+            ts_headline('english', h.document, q.query, $2) AS "highlight!", h.rank AS "rank!"
+            FROM (
+                SELECT 'story' AS kind, s.id, s.id AS story_id, s.name AS story_name, s.name,
+                s.name AS document, ts_rank(s.search, q.query) AS rank
+                FROM stories s, q WHERE s.deleted_at IS NULL AND s.search @@ q.query
+                UNION ALL
+                SELECT 'task', t.id, s.id, s.name, t.name, t.name, ts_rank(t.search, q.query)
+                FROM tasks t JOIN stories s ON s.id = t.story_id, q
+                WHERE t.deleted_at IS NULL AND s.deleted_at IS NULL AND t.search @@ q.query
+                UNION ALL
+                SELECT 'file', f.id, s.id, s.name, f.name, translate(f.name, '._-', '   '),
+                ts_rank(f.search, q.query)
+                FROM story_files f JOIN stories s ON s.id = f.story_id, q
+                WHERE f.deleted_at IS NULL AND s.deleted_at IS NULL AND f.search @@ q.query
+            ) h, q
+            ORDER BY h.rank DESC, h.id LIMIT $3 OFFSET $4"#,
+            q,
+            options,
+            limit as i64 + 1,
+            cursor - 1,
+        );
+        let mut hits = query.fetch_all(self.db_ref()).await?;
+        let next_cursor = if hits.len() > limit as usize {
+            hits.truncate(limit as usize);
+            cursor + limit as i64
+        } else {
+            0
+        };
+        for hit in hits.iter_mut() {
+            hit.highlight = mark(&hit.name, &hit.highlight);
+        }
+        Ok((next_cursor, hits))
+    }
+}
+
+/// Rebuild a name from its headline, escaping it for html and wrapping matches in <mark> tags.
+/// Headlines are made from a copy of the name with separators swapped for spaces, so the chars
+/// between match markers are taken from the name itself.
+fn mark(name: &str, headline: &str) -> String {
+    let mut chars = name.chars();
+    let mut marked = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        let c = match c {
+            START_SEL => {
+                marked.push_str("<mark>");
+                continue;
+            }
+            STOP_SEL => {
+                marked.push_str("</mark>");
+                continue;
+            }
+            _ => match chars.next() {
+                Some(c) => c,
+                None => return escape(name),
+            },
+        };
+        marked.push_str(&escape(c.encode_utf8(&mut [0; 4])));
+    }
+    if chars.next().is_some() {
+        return escape(name);
+    }
+    marked
+}
+
+/// Escape text for html.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::Status, repo::tests};
+    use uuid::Uuid;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[test]
+    fn mark_escapes_html() {
+        let name = "<b>\"Tom & Jerry's\"</b> pay";
+        let headline = format!("<b>\"Tom & Jerry's\"</b> {START_SEL}pay{STOP_SEL}");
+        assert_eq!(
+            mark(name, &headline),
+            "&lt;b&gt;&quot;Tom &amp; Jerry&#39;s&quot;&lt;/b&gt; <mark>pay</mark>"
+        );
+    }
+
+    #[test]
+    fn mark_restores_separators() {
+        let headline = format!("{START_SEL}invoice{STOP_SEL} 2024 pdf");
+        assert_eq!(
+            mark("invoice_2024.pdf", &headline),
+            "<mark>invoice</mark>_2024.pdf"
+        );
+        // Falls back to the plain name when the headline doesn't line up
+        assert_eq!(mark("a.pdf", "a pdf extra"), "a.pdf");
+        assert_eq!(mark("a.pdf", "a"), "a.pdf");
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+
+        // Create a story with tasks and a file
+        let story = repo
+            .create_story("tester", "Finances".into(), None, Default::default())
+            .await
+            .unwrap();
+        let task = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Pay invoices".into(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.create_task(
+            "tester",
+            story.id,
+            None,
+            "Call the bank".into(),
+            Some(Status::Incomplete.as_ref()),
+            None,
+        )
+        .await
+        .unwrap();
+        let file = repo
+            .create_file(
+                "tester",
+                story.id,
+                Uuid::new_v4(),
+                "invoice.pdf".into(),
+                1024,
+                "application/pdf".into(),
+            )
+            .await
+            .unwrap();
+
+        // Search matches stemmed terms across tasks and files
+        let (next_cursor, hits) = repo.search("invoice", 1, 10).await.unwrap();
+        assert_eq!(next_cursor, 0);
+        assert_eq!(hits.len(), 2);
+        let task_hit = hits.iter().find(|h| h.kind == "task").unwrap();
+        assert_eq!(task_hit.id, task.id);
+        assert_eq!(task_hit.story_id, story.id);
+        assert_eq!(task_hit.story_name, "Finances");
+        assert_eq!(task_hit.highlight, "Pay <mark>invoices</mark>");
+        let file_hit = hits.iter().find(|h| h.kind == "file").unwrap();
+        assert_eq!(file_hit.id, file.id);
+        assert_eq!(file_hit.highlight, "<mark>invoice</mark>.pdf");
+
+        // Page through results
+        let (next_cursor, hits) = repo.search("invoice OR finance", 1, 2).await.unwrap();
+        assert_eq!(next_cursor, 3);
+        assert_eq!(hits.len(), 2);
+        let (next_cursor, hits) = repo.search("invoice OR finance", 3, 2).await.unwrap();
+        assert_eq!(next_cursor, 0);
+        assert_eq!(hits.len(), 1);
+
+        // Deleted tasks are left out
+        repo.delete_task("tester", task.id).await.unwrap();
+        let (_, hits) = repo.search("invoices -bank", 1, 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, "file");
+    }
+}
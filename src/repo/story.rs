@@ -1,14 +1,27 @@
-use super::Repo;
-use crate::{domain::Story, Error, Result};
+use super::{activity, event, Repo};
+use crate::{
+    domain::{
+        EventKind, Metadata, Seek, SortOrder, Story, StoryChanges, StoryFilter, StorySort,
+        StorySummary,
+    },
+    Error, Result,
+};
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
 use uuid::Uuid;
 
+// Above this many rows, story counts are estimated.
+const EXACT_COUNT_LIMIT: i64 = 10_000;
+
 // Extend repo with queries related to stories.
 impl Repo {
     /// Select a story by id
     pub async fn fetch_story(&self, story_id: Uuid) -> Result<Story> {
         let query = sqlx::query_as!(
             Story,
-            "SELECT id, name, seqno, created_at, updated_at FROM stories WHERE id = $1",
+            r#"SELECT id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at FROM stories
+            WHERE id = $1 AND deleted_at IS NULL"#,
             story_id
         );
         match query.fetch_optional(self.db_ref()).await? {
@@ -17,60 +30,242 @@ impl Repo {
         }
     }
 
-    /// Select a page of stories.
-    pub async fn list_stories(&self, cursor: i64, limit: i32) -> Result<(i64, Vec<Story>)> {
-        let query = sqlx::query_as!(
-            Story,
-            r#"SELECT id, name, seqno, created_at, updated_at FROM stories WHERE seqno >= $1
-            ORDER BY seqno LIMIT $2"#,
-            cursor,
-            limit as i64,
+    /// Select a page of filtered stories in sort order, from either end or just after or before a
+    /// keyset. Also returns whether there are more stories past the page, in the direction of
+    /// travel.
+    pub async fn list_stories(
+        &self,
+        filter: &StoryFilter,
+        sort: StorySort,
+        order: SortOrder,
+        seek: &Seek,
+        limit: i32,
+    ) -> Result<(Vec<Story>, bool)> {
+        // Only whitelisted identifiers are pushed into the query; all values are bound.
+        let (column, cast) = match sort {
+            StorySort::Name => ("name", "text"),
+            StorySort::CreatedAt => ("created_at", "timestamptz"),
+            StorySort::UpdatedAt => ("updated_at", "timestamptz"),
+        };
+        // Pages walking back from the end are selected in reverse, then flipped into list order.
+        let (cmp, dir) = match (order, seek.is_backward()) {
+            (SortOrder::Asc, false) | (SortOrder::Desc, true) => (">", "ASC"),
+            (SortOrder::Asc, true) | (SortOrder::Desc, false) => ("<", "DESC"),
+        };
+        let mut query = QueryBuilder::new(
+            r#"SELECT id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at FROM stories
+            WHERE deleted_at IS NULL"#,
         );
-        let stories = query.fetch_all(self.db_ref()).await?;
-        let next_cursor = stories.last().map(|s| s.seqno + 1).unwrap_or_default();
-        Ok((next_cursor, stories))
+        push_filter(&mut query, filter);
+        if let Seek::After(keyset) | Seek::Before(keyset) = seek {
+            query.push(format!(" AND ({column}, seqno) {cmp} ("));
+            query.push_bind(keyset.key.clone());
+            query.push(format!("::{cast}, "));
+            query.push_bind(keyset.seqno);
+            query.push(")");
+        }
+        query.push(format!(" ORDER BY {column} {dir}, seqno {dir} LIMIT "));
+        query.push_bind(limit as i64 + 1);
+        let mut stories: Vec<Story> = query.build_query_as().fetch_all(self.db_ref()).await?;
+        let more = stories.len() > limit as usize;
+        stories.truncate(limit as usize);
+        if seek.is_backward() {
+            stories.reverse();
+        }
+        Ok((stories, more))
+    }
+
+    /// Count filtered stories. Unfiltered counts fall back to the planner's estimate for large
+    /// tables. Returns the count, and whether it was estimated.
+    pub async fn count_stories(&self, filter: &StoryFilter) -> Result<(i64, bool)> {
+        if filter.is_empty() {
+            let estimate = sqlx::query_scalar!(
+                r#"SELECT reltuples::bigint AS "estimate!" FROM pg_class WHERE oid = 'stories'::regclass"#
+            )
+            .fetch_one(self.db_ref())
+            .await?;
+            if estimate > EXACT_COUNT_LIMIT {
+                return Ok((estimate, true));
+            }
+        }
+        let mut query = QueryBuilder::new("SELECT count(*) FROM stories WHERE deleted_at IS NULL");
+        push_filter(&mut query, filter);
+        let count: i64 = query.build_query_scalar().fetch_one(self.db_ref()).await?;
+        Ok((count, false))
+    }
+
+    /// Total up the live tasks and files of stories, returning summaries in the same order as
+    /// the story ids.
+    pub async fn list_story_summaries(&self, story_ids: &[Uuid]) -> Result<Vec<StorySummary>> {
+        let rows = sqlx::query!(
+            r#"SELECT s.id AS "story_id!", coalesce(t.tasks, 0) AS "tasks!",
+            coalesce(t.closed_tasks, 0) AS "closed_tasks!",
+            coalesce(100 * t.closed_tasks / nullif(t.tasks, 0), 0) AS "percent_complete!",
+            coalesce(t.statuses, '{}') AS "statuses!", f.files AS "files!",
+            coalesce(f.file_bytes, 0) AS "file_bytes!"
+            FROM unnest($1::uuid[]) WITH ORDINALITY AS s(id, ord)
+            LEFT JOIN LATERAL (
+                SELECT sum(n)::bigint AS tasks,
+                coalesce(sum(n) FILTER (WHERE closed), 0)::bigint AS closed_tasks,
+                jsonb_object_agg(status, n) AS statuses
+                FROM (
+                    SELECT status, task_is_closed(s.id, status) AS closed, count(*) AS n
+                    FROM tasks WHERE story_id = s.id AND deleted_at IS NULL GROUP BY status
+                ) by_status
+            ) t ON true
+            CROSS JOIN LATERAL (
+                SELECT count(*) AS files, sum(size)::bigint AS file_bytes
+                FROM story_files WHERE story_id = s.id AND deleted_at IS NULL
+            ) f
+            ORDER BY s.ord"#,
+            story_ids,
+        )
+        .fetch_all(self.db_ref())
+        .await?;
+        let mut summaries = Vec::with_capacity(rows.len());
+        for row in rows {
+            summaries.push(StorySummary {
+                story_id: row.story_id,
+                tasks: row.tasks,
+                closed_tasks: row.closed_tasks,
+                percent_complete: row.percent_complete,
+                statuses: serde_json::from_value(row.statuses)?,
+                files: row.files,
+                file_bytes: row.file_bytes,
+            });
+        }
+        Ok(summaries)
     }
 
     /// Insert a new story
-    pub async fn create_story(&self, name: String) -> Result<Story> {
+    pub async fn create_story(
+        &self,
+        actor: &str,
+        name: String,
+        description: Option<String>,
+        metadata: Metadata,
+    ) -> Result<Story> {
         let query = sqlx::query_as!(
             Story,
-            r#"INSERT INTO stories (name) VALUES ($1)
-            RETURNING id, name, seqno, created_at, updated_at"#,
-            name
+            r#"INSERT INTO stories (name, description, metadata) VALUES ($1, $2, $3)
+            RETURNING id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at"#,
+            name,
+            description,
+            Value::Object(metadata),
         );
-        let story = query.fetch_one(self.db_ref()).await?;
+        let mut tx = self.db.begin().await?;
+        let story = query.fetch_one(&mut *tx).await?;
+        let kind = EventKind::StoryCreated;
+        activity::record(&mut tx, actor, story.id, kind, story.id, None, Some(&story)).await?;
+        event::publish(&mut tx, story.id, kind, story.id, &story).await?;
+        tx.commit().await?;
         Ok(story)
     }
 
-    /// Update story name
-    pub async fn update_story(&self, story_id: Uuid, name: String) -> Result<Story> {
-        let query = sqlx::query_as!(
+    /// Update the name of a story, and its description and metadata when changed
+    pub async fn update_story(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        changes: StoryChanges,
+    ) -> Result<Story> {
+        let mut tx = self.db.begin().await?;
+        let before = sqlx::query_as!(
             Story,
-            r#"UPDATE stories SET name = $1, updated_at = now() WHERE id = $2
-            RETURNING id, name, seqno, created_at, updated_at"#,
-            name,
+            r#"SELECT id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at FROM stories
+            WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
             story_id
-        );
-        let story = query.fetch_one(self.db_ref()).await?;
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+        let story = sqlx::query_as!(
+            Story,
+            r#"UPDATE stories SET name = $1,
+            description = CASE WHEN $2 THEN $3 ELSE description END,
+            metadata = coalesce($4, metadata), updated_at = now()
+            WHERE id = $5
+            RETURNING id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at"#,
+            changes.name,
+            changes.description.is_some(),
+            changes.description.flatten(),
+            changes.metadata.map(Value::Object),
+            story_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::StoryUpdated;
+        activity::record(
+            &mut tx,
+            actor,
+            story.id,
+            kind,
+            story.id,
+            Some(&before),
+            Some(&story),
+        )
+        .await?;
+        event::publish(&mut tx, story.id, kind, story.id, &story).await?;
+        tx.commit().await?;
         Ok(story)
     }
 
-    /// Delete a story, child files, and child tasks.
-    pub async fn delete_story(&self, story_id: Uuid) -> Result<()> {
+    /// Move a story, child files, and child tasks to the trash. Children share the story's
+    /// deletion time so they can be restored along with it.
+    pub async fn delete_story(&self, actor: &str, story_id: Uuid) -> Result<()> {
         let mut tx = self.db.begin().await?;
 
-        sqlx::query!("DELETE FROM tasks WHERE story_id = $1", story_id)
-            .execute(&mut *tx)
-            .await?;
+        // Lock the story first, so no tasks or files are added to it while it's trashed
+        let before = sqlx::query_as!(
+            Story,
+            r#"SELECT id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at FROM stories
+            WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            story_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
 
-        sqlx::query!("DELETE FROM story_files WHERE story_id = $1", story_id)
-            .execute(&mut *tx)
-            .await?;
+        sqlx::query!(
+            "UPDATE tasks SET deleted_at = now() WHERE story_id = $1 AND deleted_at IS NULL",
+            story_id
+        )
+        .execute(&mut *tx)
+        .await?;
 
-        sqlx::query!("DELETE FROM stories WHERE id = $1", story_id)
-            .execute(&mut *tx)
-            .await?;
+        sqlx::query!(
+            "UPDATE story_files SET deleted_at = now() WHERE story_id = $1 AND deleted_at IS NULL",
+            story_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let story = sqlx::query_as!(
+            Story,
+            r#"UPDATE stories SET deleted_at = now() WHERE id = $1
+            RETURNING id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at"#,
+            story_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::StoryDeleted;
+        activity::record(
+            &mut tx,
+            actor,
+            story.id,
+            kind,
+            story.id,
+            Some(&before),
+            Some(&story),
+        )
+        .await?;
+        event::publish(&mut tx, story.id, kind, story.id, &story).await?;
 
         tx.commit().await?;
 
@@ -78,10 +273,59 @@ impl Repo {
     }
 }
 
+/// Add story filter conditions to a query.
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &StoryFilter) {
+    if let Some(name) = &filter.name {
+        query.push(" AND strpos(lower(name), lower(");
+        query.push_bind(name.clone());
+        query.push(")) > 0");
+    }
+    if let Some(created_after) = filter.created_after {
+        query.push(" AND created_at >= ");
+        query.push_bind(created_after);
+    }
+    if let Some(created_before) = filter.created_before {
+        query.push(" AND created_at < ");
+        query.push_bind(created_before);
+    }
+    if let Some(updated_after) = filter.updated_after {
+        query.push(" AND updated_at >= ");
+        query.push_bind(updated_after);
+    }
+    if let Some(updated_before) = filter.updated_before {
+        query.push(" AND updated_at < ");
+        query.push_bind(updated_before);
+    }
+    if let Some(has_incomplete_tasks) = filter.has_incomplete_tasks {
+        query.push(if has_incomplete_tasks {
+            " AND "
+        } else {
+            " AND NOT "
+        });
+        query.push(
+            r#"EXISTS (SELECT 1 FROM tasks WHERE tasks.story_id = stories.id
+            AND tasks.deleted_at IS NULL AND NOT task_is_closed(tasks.story_id, tasks.status))"#,
+        );
+    }
+    if let Some(labels) = &filter.labels {
+        query.push(
+            r#" AND (SELECT count(*) FROM story_labels JOIN labels ON labels.id = story_labels.label_id
+            WHERE story_labels.story_id = stories.id AND lower(labels.name) = ANY("#,
+        );
+        query.push_bind(labels.names.clone());
+        query.push(")) >= ");
+        query.push_bind(if labels.match_all() {
+            labels.names.len() as i64
+        } else {
+            1
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::repo::tests;
+    use crate::{domain::Status, repo::tests};
 
     use testcontainers::{runners::AsyncRunner, ImageExt};
     use testcontainers_modules::postgres::Postgres;
@@ -97,25 +341,207 @@ mod tests {
 
         // Create story
         let name = "Books To Read".to_string();
-        let story = repo.create_story(name.clone()).await.unwrap();
+        let story = repo
+            .create_story("tester", name.clone(), None, Default::default())
+            .await
+            .unwrap();
         assert_eq!(name, story.name);
 
         // Query stories page
-        let (_, stories) = repo.list_stories(1, 10).await.unwrap();
+        let all = StoryFilter::default();
+        let sort = StorySort::default();
+        let (stories, more) = repo
+            .list_stories(&all, sort, SortOrder::Asc, &Seek::First, 10)
+            .await
+            .unwrap();
+        assert_eq!(stories.len(), 1);
+        assert!(!more);
+        assert_eq!(repo.count_stories(&all).await.unwrap(), (1, false));
+
+        // Page forward and back through more stories, newest first
+        for name in ["Poems", "Plays", "Essays"] {
+            repo.create_story("tester", name.into(), None, Default::default())
+                .await
+                .unwrap();
+        }
+        let order = SortOrder::Desc;
+        let (stories, more) = repo
+            .list_stories(&all, sort, order, &Seek::First, 2)
+            .await
+            .unwrap();
+        assert_eq!(stories[0].name, "Essays");
+        assert!(more);
+        let after = Seek::After(stories[1].keyset(sort));
+        let (stories, more) = repo
+            .list_stories(&all, sort, order, &after, 2)
+            .await
+            .unwrap();
+        assert_eq!(stories[1].name, name);
+        assert!(!more);
+        let before = Seek::Before(stories[0].keyset(sort));
+        let (stories, more) = repo
+            .list_stories(&all, sort, order, &before, 2)
+            .await
+            .unwrap();
+        assert_eq!(stories[0].name, "Essays");
+        assert!(!more);
+        let (stories, more) = repo
+            .list_stories(&all, sort, order, &Seek::Last, 3)
+            .await
+            .unwrap();
+        assert_eq!(stories[0].name, "Plays");
+        assert!(more);
+
+        // Page through stories by name
+        let sort = StorySort::Name;
+        let (stories, _) = repo
+            .list_stories(&all, sort, SortOrder::Asc, &Seek::First, 2)
+            .await
+            .unwrap();
+        assert_eq!(stories[1].name, "Essays");
+        let after = Seek::After(stories[1].keyset(sort));
+        let (stories, more) = repo
+            .list_stories(&all, sort, SortOrder::Asc, &after, 2)
+            .await
+            .unwrap();
+        let names: Vec<_> = stories.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Plays", "Poems"]);
+        assert!(!more);
+
+        // Filter stories by name and open tasks
+        let filter = StoryFilter {
+            name: Some("P".into()),
+            ..Default::default()
+        };
+        let (stories, _) = repo
+            .list_stories(&filter, sort, SortOrder::Asc, &Seek::First, 10)
+            .await
+            .unwrap();
+        assert_eq!(stories.len(), 2);
+        let poems = &stories[1];
+        repo.create_task(
+            "tester",
+            poems.id,
+            None,
+            "Ode".into(),
+            Some(Status::Incomplete.as_ref()),
+            None,
+        )
+        .await
+        .unwrap();
+        let filter = StoryFilter {
+            has_incomplete_tasks: Some(true),
+            ..Default::default()
+        };
+        let (stories, _) = repo
+            .list_stories(&filter, sort, SortOrder::Asc, &Seek::First, 10)
+            .await
+            .unwrap();
         assert_eq!(stories.len(), 1);
+        assert_eq!(stories[0].id, poems.id);
+        assert_eq!(repo.count_stories(&filter).await.unwrap(), (1, false));
+        let filter = StoryFilter {
+            has_incomplete_tasks: Some(false),
+            created_after: Some(poems.created_at),
+            ..Default::default()
+        };
+        assert_eq!(repo.count_stories(&filter).await.unwrap(), (2, false));
 
-        // Update the name
-        let updated_name = "Books".to_string();
-        repo.update_story(story.id, updated_name).await.unwrap();
+        // Summarize tasks by status
+        repo.create_task(
+            "tester",
+            poems.id,
+            None,
+            "Sonnet".into(),
+            Some(Status::Complete.as_ref()),
+            None,
+        )
+        .await
+        .unwrap();
+        let summaries = repo
+            .list_story_summaries(&[poems.id, story.id])
+            .await
+            .unwrap();
+        let (poems_summary, books_summary) = (&summaries[0], &summaries[1]);
+        assert_eq!(poems_summary.story_id, poems.id);
+        assert_eq!(poems_summary.tasks, 2);
+        assert_eq!(poems_summary.closed_tasks, 1);
+        assert_eq!(poems_summary.percent_complete, 50);
+        assert_eq!(poems_summary.statuses.get("incomplete"), Some(&1));
+        assert_eq!((poems_summary.files, poems_summary.file_bytes), (0, 0));
+        assert_eq!(books_summary.tasks, 0);
+        assert_eq!(books_summary.percent_complete, 0);
 
-        // Fetch and verify new name
+        // Update the name, description and metadata
+        let mut metadata = Metadata::new();
+        metadata.insert("owner".into(), "alice".into());
+        let changes = StoryChanges {
+            name: "Books".into(),
+            description: Some(Some("Reading *list*".into())),
+            metadata: Some(metadata.clone()),
+        };
+        repo.update_story("tester", story.id, changes)
+            .await
+            .unwrap();
+
+        // Fetch and verify new details
         let story = repo.fetch_story(story.id).await.unwrap();
         assert_eq!(story.name, "Books");
+        assert_eq!(story.description.as_deref(), Some("Reading *list*"));
+        assert_eq!(story.metadata, Value::Object(metadata.clone()));
+
+        // Details are kept unless changed
+        let changes = StoryChanges {
+            name: "Books".into(),
+            ..Default::default()
+        };
+        let story = repo
+            .update_story("tester", story.id, changes)
+            .await
+            .unwrap();
+        assert!(story.description.is_some());
+        assert_eq!(story.metadata, Value::Object(metadata));
+        let changes = StoryChanges {
+            name: "Books".into(),
+            description: Some(None),
+            metadata: Some(Metadata::new()),
+        };
+        let story = repo
+            .update_story("tester", story.id, changes)
+            .await
+            .unwrap();
+        assert!(story.description.is_none());
+        assert_eq!(story.metadata, Value::Object(Metadata::new()));
 
         // Delete the story
-        repo.delete_story(story.id).await.unwrap();
+        repo.delete_story("tester", story.id).await.unwrap();
 
         // Assert story was deleted
         assert!(repo.fetch_story(story.id).await.is_err());
+
+        // Trashed stories can't be changed, trashed again, or given new tasks and files
+        let changes = StoryChanges {
+            name: "Trashed".into(),
+            ..Default::default()
+        };
+        let result = repo.update_story("tester", story.id, changes).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let result = repo.delete_story("tester", story.id).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let result = repo
+            .create_task("tester", story.id, None, "Dune".into(), None, None)
+            .await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+        let result = repo
+            .create_file(
+                "tester",
+                story.id,
+                Uuid::new_v4(),
+                "dune.pdf".into(),
+                1,
+                "application/pdf".into(),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
     }
 }
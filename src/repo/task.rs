@@ -1,20 +1,26 @@
-use super::Repo;
+use super::{activity, dependency, event, query_tasks, workflow, Repo};
 use crate::{
-    domain::{Status, Task},
+    domain::{EventKind, Keyset, Placement, Recurrence, Task, TaskChanges, TaskFilter, Workflow},
     Error, Result,
 };
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgConnection;
+use std::str::FromStr;
 use uuid::Uuid;
 
-// Put some reasonable upper limit when querying tasks for a story.
-const MAX_TASKS: i64 = 100;
+// The space left between task positions, so tasks can be moved without renumbering others.
+const POSITION_GAP: i64 = 65536;
+
+// How many levels deep tasks can be nested, counting top level tasks.
+const MAX_TASK_DEPTH: usize = 3;
 
 // Extend repo with queries related to tasks.
 impl Repo {
     /// Get a task by id
     pub async fn fetch_task(&self, task_id: Uuid) -> Result<Task> {
-        let query = sqlx::query_as!(
-            Task,
-            "SELECT id, story_id, name, status, created_at, updated_at FROM tasks WHERE id = $1",
+        let query = query_tasks!(
+            "SELECT",
+            "FROM tasks WHERE id = $1 AND deleted_at IS NULL",
             task_id,
         );
         match query.fetch_optional(self.db_ref()).await? {
@@ -23,67 +29,993 @@ impl Repo {
         }
     }
 
-    /// Select tasks for a story
-    pub async fn list_tasks(&self, story_id: Uuid) -> Result<Vec<Task>> {
-        let query = sqlx::query_as!(
-            Task,
-            r#"SELECT id, story_id, name, status, created_at, updated_at FROM tasks
-            WHERE story_id = $1 ORDER BY created_at LIMIT $2"#,
+    /// Select a page of tasks for a story in position order, starting after a keyset.
+    pub async fn list_tasks(
+        &self,
+        story_id: Uuid,
+        filter: &TaskFilter,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<Task>)> {
+        let (position, seqno): (Option<i64>, i64) = match after {
+            Some(keyset) => {
+                let position = keyset
+                    .key
+                    .parse()
+                    .map_err(|_| Error::invalid_args("page_token: invalid"))?;
+                (Some(position), keyset.seqno)
+            }
+            None => (None, 0),
+        };
+        let (label_names, label_count) = label_params(filter);
+        let query = query_tasks!(
+            "SELECT",
+            r#"FROM tasks WHERE story_id = $1 AND deleted_at IS NULL
+            AND ($2::text IS NULL OR status = $2)
+            AND ($3::timestamptz IS NULL OR due_at < $3)
+            AND ($4::timestamptz IS NULL OR due_at >= $4)
+            AND ($5::bool IS NULL OR $5 = coalesce(
+                due_at < now() AND NOT task_is_closed(story_id, status), false))
+            AND ($9::text[] IS NULL OR (SELECT count(*) FROM task_labels tl
+                JOIN labels l ON l.id = tl.label_id
+                WHERE tl.task_id = tasks.id AND lower(l.name) = ANY($9)) >= $10)
+            AND ($11::uuid IS NULL OR parent_task_id = $11)
+            AND ($6::bigint IS NULL OR (position, seqno) > ($6, $7))
+            ORDER BY position, seqno LIMIT $8"#,
             story_id,
-            MAX_TASKS,
+            filter.status.as_deref(),
+            filter.due_before,
+            filter.due_after,
+            filter.overdue,
+            position,
+            seqno,
+            limit as i64,
+            label_names,
+            label_count,
+            filter.parent_task_id,
         );
         let tasks = query.fetch_all(self.db_ref()).await?;
-        Ok(tasks)
+        let next = tasks.last().map(|t| t.keyset());
+        Ok((next, tasks))
     }
 
-    /// Insert a new task
-    pub async fn create_task(&self, story_id: Uuid, name: String, status: Status) -> Result<Task> {
-        let query = sqlx::query_as!(
-            Task,
-            r#"INSERT INTO tasks (story_id, name, status) VALUES ($1, $2, $3)
-            RETURNING id, story_id, name, status, created_at, updated_at"#,
+    /// Select a page of tasks assigned to a user, across all stories, in due time order with
+    /// undated tasks last, starting after a keyset.
+    pub async fn list_assigned_tasks(
+        &self,
+        assignee: &str,
+        filter: &TaskFilter,
+        after: Option<Keyset>,
+        limit: i32,
+    ) -> Result<(Option<Keyset>, Vec<Task>)> {
+        let (due_at, seqno) = match after {
+            Some(keyset) if keyset.key.is_empty() => (None, Some(keyset.seqno)),
+            Some(keyset) => {
+                let due_at = DateTime::parse_from_rfc3339(&keyset.key)
+                    .map_err(|_| Error::invalid_args("page_token: invalid"))?;
+                (Some(due_at.with_timezone(&Utc)), Some(keyset.seqno))
+            }
+            None => (None, None),
+        };
+        let (label_names, label_count) = label_params(filter);
+        let query = query_tasks!(
+            "SELECT",
+            r#"FROM tasks
+            WHERE assignee = $1 AND deleted_at IS NULL
+            AND EXISTS (SELECT 1 FROM stories s WHERE s.id = tasks.story_id AND s.deleted_at IS NULL)
+            AND ($2::text IS NULL OR status = $2)
+            AND ($3::timestamptz IS NULL OR due_at < $3)
+            AND ($4::timestamptz IS NULL OR due_at >= $4)
+            AND ($5::bool IS NULL OR $5 = coalesce(
+                due_at < now() AND NOT task_is_closed(story_id, status), false))
+            AND ($9::text[] IS NULL OR (SELECT count(*) FROM task_labels tl
+                JOIN labels l ON l.id = tl.label_id
+                WHERE tl.task_id = tasks.id AND lower(l.name) = ANY($9)) >= $10)
+            AND ($7::bigint IS NULL OR (coalesce(due_at, 'infinity'), seqno)
+                > (coalesce($6::timestamptz, 'infinity'), $7))
+            ORDER BY coalesce(due_at, 'infinity'), seqno LIMIT $8"#,
+            assignee,
+            filter.status.as_deref(),
+            filter.due_before,
+            filter.due_after,
+            filter.overdue,
+            due_at,
+            seqno,
+            limit as i64,
+            label_names,
+            label_count,
+        );
+        let tasks = query.fetch_all(self.db_ref()).await?;
+        let next = tasks.last().map(|t| t.due_keyset());
+        Ok((next, tasks))
+    }
+
+    /// Insert a new task, optionally as a subtask of another task in the story. The task starts
+    /// in the initial state of the story workflow unless given a status.
+    pub async fn create_task(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        parent_task_id: Option<Uuid>,
+        name: String,
+        status: Option<&str>,
+        due_at: Option<DateTime<Utc>>,
+    ) -> Result<Task> {
+        let mut tx = self.db.begin().await?;
+        lock_story(&mut tx, story_id).await?;
+        let workflow = workflow::select_workflow(&mut tx, story_id).await?;
+        let status = match status {
+            Some(status) => workflow
+                .ensure_state(status)
+                .map_err(|err| Error::invalid_args(&err))?,
+            None => workflow.initial(),
+        };
+        if let Some(parent_task_id) = parent_task_id {
+            ensure_parent(&mut tx, story_id, None, parent_task_id).await?;
+        }
+        let query = query_tasks!(
+            r#"INSERT INTO tasks (story_id, parent_task_id, name, status, due_at, position)
+            SELECT $1, $6, $2, $3, $4, coalesce(max(position), 0) + $5
+            FROM tasks WHERE story_id = $1
+            RETURNING"#,
+            "",
             story_id,
             name,
-            status.to_string(),
+            status.name,
+            due_at,
+            POSITION_GAP,
+            parent_task_id,
         );
-        let task = query.fetch_one(self.db_ref()).await?;
+        let task = query.fetch_one(&mut *tx).await?;
+        let kind = EventKind::TaskCreated;
+        activity::record(
+            &mut tx,
+            actor,
+            task.story_id,
+            kind,
+            task.id,
+            None,
+            Some(&task),
+        )
+        .await?;
+        event::publish(&mut tx, task.story_id, kind, task.id, &task).await?;
+        tx.commit().await?;
         Ok(task)
     }
 
-    /// Update task name and status.
-    pub async fn update_task(&self, task_id: Uuid, name: String, status: Status) -> Result<Task> {
-        let query = sqlx::query_as!(
-            Task,
-            r#"UPDATE tasks SET name = $1, status = $2, updated_at = now() WHERE id = $3
-            RETURNING id, story_id, name, status, created_at, updated_at"#,
+    /// Update task name, status, due time, parent task, and/or story. Status changes must be
+    /// allowed by the story workflow, and a task can only be closed once the tasks it depends on
+    /// are, unless forced. Closing a recurring task creates its next occurrence. Closing the last
+    /// open subtask of a task closes the task too, when asked to. Changing the due time re-arms
+    /// its reminder. Moving a task to another story takes its subtasks along, and checks the rest
+    /// of the update against the workflow of that story.
+    pub async fn update_task(
+        &self,
+        actor: &str,
+        task_id: Uuid,
+        changes: &TaskChanges,
+        complete_parents: bool,
+    ) -> Result<Task> {
+        let mut tx = self.db.begin().await?;
+        // Moves lock both stories before the task, so the task isn't locked here first
+        let current_story_id = task_story_id(&mut tx, task_id).await?;
+        let before = match changes.story_id.filter(|&id| id != current_story_id) {
+            Some(story_id) => {
+                // The task leaves its parent behind, unless given one in the new story
+                let parent_task_id = changes.parent_task_id.flatten();
+                let moved = move_tasks(&mut tx, actor, &[task_id], story_id, parent_task_id)
+                    .await?
+                    .pop()
+                    .ok_or_else(|| Error::internal(format!("moved task not found: {task_id}")))?;
+                if changes.name.is_none() && changes.status.is_none() && changes.due_at.is_none() {
+                    tx.commit().await?;
+                    return Ok(moved);
+                }
+                moved
+            }
+            None => lock_task(&mut tx, task_id).await?,
+        };
+        if changes.story_id.is_some_and(|id| id != before.story_id) {
+            return Err(Error::conflict(format!(
+                "task changed during update: {task_id}"
+            )));
+        }
+        let workflow = workflow::select_workflow(&mut tx, before.story_id).await?;
+        let status = changes.status.as_deref().unwrap_or(&before.status);
+        let mut closed = false;
+        if status != before.status {
+            let state = workflow
+                .ensure_state(status)
+                .map_err(|err| Error::invalid_args(&err))?;
+            if !workflow.allows(&before.status, status) {
+                return Err(Error::conflict(format!(
+                    "status: can't move from {} to {status}",
+                    before.status
+                )));
+            }
+            closed = state.closed && !before.status(&workflow).is_some_and(|s| s.closed);
+            if closed && !changes.force {
+                let open = dependency::count_open_blockers(&mut tx, task_id).await?;
+                if open > 0 {
+                    return Err(Error::conflict(format!(
+                        "status: blocked by {open} open task(s), use force to override"
+                    )));
+                }
+            }
+        }
+        let parent_task_id = changes.parent_task_id.unwrap_or(before.parent_task_id);
+        if let Some(parent_id) = parent_task_id.filter(|&id| before.parent_task_id != Some(id)) {
+            ensure_parent(&mut tx, before.story_id, Some(task_id), parent_id).await?;
+        }
+        let name = changes.name.as_ref().unwrap_or(&before.name);
+        let due_at = changes.due_at.unwrap_or(before.due_at);
+        let mut task = query_tasks!(
+            r#"UPDATE tasks SET name = $1, status = $2, due_at = $3, parent_task_id = $5,
+            reminded_at = CASE WHEN due_at IS DISTINCT FROM $3 THEN NULL ELSE reminded_at END,
+            updated_at = now()
+            WHERE id = $4
+            RETURNING"#,
+            "",
             name,
-            status.to_string(),
+            status,
+            due_at,
             task_id,
-        );
-        let task = query.fetch_one(self.db_ref()).await?;
+            parent_task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::TaskUpdated;
+        activity::record(
+            &mut tx,
+            actor,
+            task.story_id,
+            kind,
+            task.id,
+            Some(&before),
+            Some(&task),
+        )
+        .await?;
+        event::publish(&mut tx, task.story_id, kind, task.id, &task).await?;
+        if closed && task.recurrence.is_some() {
+            task = repeat(&mut tx, actor, &workflow, task).await?;
+        }
+        if closed && complete_parents {
+            close_parents(&mut tx, actor, &workflow, task.parent_task_id).await?;
+        }
+        tx.commit().await?;
+        Ok(task)
+    }
+
+    /// Assign a task to a user, or unassign it.
+    pub async fn assign_task(
+        &self,
+        actor: &str,
+        task_id: Uuid,
+        assignee: Option<&str>,
+    ) -> Result<Task> {
+        let mut tx = self.db.begin().await?;
+        let before = lock_task(&mut tx, task_id).await?;
+        let task = query_tasks!(
+            r#"UPDATE tasks SET assignee = $1, updated_at = now() WHERE id = $2
+            RETURNING"#,
+            "",
+            assignee,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = match assignee {
+            Some(_) => EventKind::TaskAssigned,
+            None => EventKind::TaskUnassigned,
+        };
+        activity::record(
+            &mut tx,
+            actor,
+            task.story_id,
+            kind,
+            task.id,
+            Some(&before),
+            Some(&task),
+        )
+        .await?;
+        event::publish(&mut tx, task.story_id, kind, task.id, &task).await?;
+        tx.commit().await?;
+        Ok(task)
+    }
+
+    /// Set or clear the recurrence rule of a task.
+    pub async fn set_recurrence(
+        &self,
+        actor: &str,
+        task_id: Uuid,
+        recurrence: Option<&str>,
+    ) -> Result<Task> {
+        let mut tx = self.db.begin().await?;
+        let before = lock_task(&mut tx, task_id).await?;
+        let task = query_tasks!(
+            r#"UPDATE tasks SET recurrence = $1, updated_at = now() WHERE id = $2
+            RETURNING"#,
+            "",
+            recurrence,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::TaskUpdated;
+        activity::record(
+            &mut tx,
+            actor,
+            task.story_id,
+            kind,
+            task.id,
+            Some(&before),
+            Some(&task),
+        )
+        .await?;
+        event::publish(&mut tx, task.story_id, kind, task.id, &task).await?;
+        tx.commit().await?;
+        Ok(task)
+    }
+
+    /// Move a task just before or after a sibling task.
+    pub async fn move_task(
+        &self,
+        actor: &str,
+        task_id: Uuid,
+        placement: Placement,
+    ) -> Result<Task> {
+        let mut tx = self.db.begin().await?;
+        let story_id = task_story_id(&mut tx, task_id).await?;
+
+        // Moves within a story are serialized, since they may rebalance every task in it.
+        sqlx::query!(
+            "SELECT id FROM stories WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+            story_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+        let before = query_tasks!(
+            "SELECT",
+            "FROM tasks WHERE id = $1 AND story_id = $2 AND deleted_at IS NULL FOR UPDATE",
+            task_id,
+            story_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::conflict(format!("task changed during update: {task_id}")))?;
+
+        let sibling_id = match placement {
+            Placement::Before(id) | Placement::After(id) => id,
+        };
+        if sibling_id == task_id {
+            return Err(Error::invalid_args(
+                "sibling: can't move a task next to itself",
+            ));
+        }
+        let mut rebalanced = false;
+        let position = loop {
+            let sibling = query_tasks!(
+                "SELECT",
+                "FROM tasks WHERE id = $1 AND story_id = $2 AND deleted_at IS NULL",
+                sibling_id,
+                before.story_id,
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                Error::invalid_args(&format!("sibling: not a task in the story: {sibling_id}"))
+            })?;
+            // Find the positions either side of the gap the task moves into.
+            let (lower, upper) = match placement {
+                Placement::Before(_) => {
+                    let lower = sqlx::query_scalar!(
+                        r#"SELECT max(position) FROM tasks
+                        WHERE story_id = $1 AND deleted_at IS NULL AND id <> $2
+                        AND (position, seqno) < ($3, $4)"#,
+                        before.story_id,
+                        task_id,
+                        sibling.position,
+                        sibling.seqno,
+                    )
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    (lower, Some(sibling.position))
+                }
+                Placement::After(_) => {
+                    let upper = sqlx::query_scalar!(
+                        r#"SELECT min(position) FROM tasks
+                        WHERE story_id = $1 AND deleted_at IS NULL AND id <> $2
+                        AND (position, seqno) > ($3, $4)"#,
+                        before.story_id,
+                        task_id,
+                        sibling.position,
+                        sibling.seqno,
+                    )
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    (Some(sibling.position), upper)
+                }
+            };
+            if let Some(position) = between(lower, upper) {
+                break position;
+            }
+            if rebalanced {
+                return Err(Error::internal(
+                    "no room to move task after rebalancing".into(),
+                ));
+            }
+            rebalance_tasks(&mut tx, before.story_id).await?;
+            rebalanced = true;
+        };
+
+        let task = query_tasks!(
+            r#"UPDATE tasks SET position = $1, updated_at = now() WHERE id = $2
+            RETURNING"#,
+            "",
+            position,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::TaskMoved;
+        activity::record(
+            &mut tx,
+            actor,
+            task.story_id,
+            kind,
+            task.id,
+            Some(&before),
+            Some(&task),
+        )
+        .await?;
+        event::publish(&mut tx, task.story_id, kind, task.id, &task).await?;
+        tx.commit().await?;
         Ok(task)
     }
 
-    /// Delete a task.
-    pub async fn delete_task(&self, task_id: Uuid) -> Result<()> {
-        sqlx::query!("DELETE FROM tasks WHERE id = $1", task_id)
-            .execute(self.db_ref())
+    /// Mark open tasks due before a time as reminded, publishing a reminder event for each.
+    pub async fn claim_reminders(
+        &self,
+        due_before: DateTime<Utc>,
+        limit: i32,
+    ) -> Result<Vec<Task>> {
+        let mut tx = self.db.begin().await?;
+        let tasks = query_tasks!(
+            r#"UPDATE tasks SET reminded_at = now()
+            WHERE id IN (
+                SELECT t.id FROM tasks t JOIN stories s ON s.id = t.story_id
+                WHERE t.due_at <= $1 AND t.reminded_at IS NULL AND t.deleted_at IS NULL
+                AND s.deleted_at IS NULL AND NOT task_is_closed(t.story_id, t.status)
+                ORDER BY t.due_at LIMIT $2
+                FOR UPDATE OF t SKIP LOCKED
+            )
+            RETURNING"#,
+            "",
+            due_before,
+            limit as i64,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for task in &tasks {
+            let kind = EventKind::TaskReminder;
+            event::publish(&mut tx, task.story_id, kind, task.id, task).await?;
+        }
+        tx.commit().await?;
+        Ok(tasks)
+    }
+
+    /// Move tasks, with all of their subtasks, to the end of another story, under a parent task
+    /// there or at the top level. Tasks listed along with one of their ancestors stay under their
+    /// parent. The tasks must be in states the story's workflow has, and their comments move with
+    /// them.
+    pub async fn move_tasks_to_story(
+        &self,
+        actor: &str,
+        task_ids: &[Uuid],
+        story_id: Uuid,
+        parent_task_id: Option<Uuid>,
+    ) -> Result<Vec<Task>> {
+        let mut tx = self.db.begin().await?;
+        let moved = move_tasks(&mut tx, actor, task_ids, story_id, parent_task_id).await?;
+        tx.commit().await?;
+        Ok(moved)
+    }
+
+    /// Move a task and all of its subtasks to the trash.
+    pub async fn delete_task(&self, actor: &str, task_id: Uuid) -> Result<()> {
+        let query = query_tasks!(
+            r#"WITH RECURSIVE subtree AS (
+                SELECT id FROM tasks WHERE id = $1 AND deleted_at IS NULL
+                UNION
+                SELECT t.id FROM tasks t JOIN subtree s ON t.parent_task_id = s.id
+                WHERE t.deleted_at IS NULL
+            )
+            UPDATE tasks SET deleted_at = now() WHERE id IN (SELECT id FROM subtree)
+            RETURNING"#,
+            "",
+            task_id,
+        );
+        let mut tx = self.db.begin().await?;
+        lock_task(&mut tx, task_id).await?;
+        for task in query.fetch_all(&mut *tx).await? {
+            let before = Task {
+                deleted_at: None,
+                ..task.clone()
+            };
+            let kind = EventKind::TaskDeleted;
+            activity::record(
+                &mut tx,
+                actor,
+                task.story_id,
+                kind,
+                task.id,
+                Some(&before),
+                Some(&task),
+            )
             .await?;
+            event::publish(&mut tx, task.story_id, kind, task.id, &task).await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 }
 
+/// Close the ancestors of a task whose subtasks are now all closed, nearest first, stopping at
+/// the first that stays open. Parents that are blocked, or have no closed state to move to, stay
+/// open.
+async fn close_parents(
+    conn: &mut PgConnection,
+    actor: &str,
+    workflow: &Workflow,
+    mut parent_task_id: Option<Uuid>,
+) -> Result<()> {
+    while let Some(task_id) = parent_task_id {
+        let before = match lock_task(conn, task_id).await {
+            Ok(before) => before,
+            Err(Error::NotFound { .. }) => break,
+            Err(err) => return Err(err),
+        };
+        let open = before.status(workflow).is_some_and(|s| !s.closed);
+        if !open || before.closed_subtasks < before.subtasks {
+            break;
+        }
+        if dependency::count_open_blockers(conn, before.id).await? > 0 {
+            break;
+        }
+        let Some(state) = workflow.closed_from(&before.status) else {
+            break;
+        };
+        let task = query_tasks!(
+            r#"UPDATE tasks SET status = $1, updated_at = now() WHERE id = $2
+            RETURNING"#,
+            "",
+            state.name,
+            task_id,
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        let kind = EventKind::TaskUpdated;
+        activity::record(
+            conn,
+            actor,
+            task.story_id,
+            kind,
+            task.id,
+            Some(&before),
+            Some(&task),
+        )
+        .await?;
+        event::publish(conn, task.story_id, kind, task.id, &task).await?;
+        let task = repeat(conn, actor, workflow, task).await?;
+        parent_task_id = task.parent_task_id;
+    }
+    Ok(())
+}
+
+/// Hand the recurrence rule of a task that was just closed over to its next occurrence, unless
+/// its series ended: a copy of the task in the initial workflow state, with the same labels, due
+/// one interval after the closed task, or after now if it had no due time. Returns the closed
+/// task, without its rule when it was handed over.
+async fn repeat(
+    conn: &mut PgConnection,
+    actor: &str,
+    workflow: &Workflow,
+    before: Task,
+) -> Result<Task> {
+    let Some(rule) = &before.recurrence else {
+        return Ok(before);
+    };
+    let rule = Recurrence::from_str(rule).map_err(|err| {
+        Error::internal(format!("invalid recurrence of task {}: {err}", before.id))
+    })?;
+    let Some((due_at, rule)) = rule.next(before.due_at.unwrap_or_else(Utc::now)) else {
+        return Ok(before);
+    };
+    let task = query_tasks!(
+        r#"UPDATE tasks SET recurrence = NULL, updated_at = now() WHERE id = $1
+        RETURNING"#,
+        "",
+        before.id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let next = query_tasks!(
+        r#"INSERT INTO tasks
+        (story_id, parent_task_id, name, status, due_at, assignee, recurrence, position)
+        SELECT $1, $2, $3, $4, $5, $6, $7, coalesce(max(position), 0) + $8
+        FROM tasks WHERE story_id = $1
+        RETURNING"#,
+        "",
+        task.story_id,
+        task.parent_task_id,
+        task.name,
+        workflow.initial().name,
+        due_at,
+        task.assignee,
+        rule.to_string(),
+        POSITION_GAP,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO task_labels (task_id, label_id)
+        SELECT $1, label_id FROM task_labels WHERE task_id = $2"#,
+        next.id,
+        task.id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let kind = EventKind::TaskUpdated;
+    activity::record(
+        conn,
+        actor,
+        task.story_id,
+        kind,
+        task.id,
+        Some(&before),
+        Some(&task),
+    )
+    .await?;
+    event::publish(conn, task.story_id, kind, task.id, &task).await?;
+    let kind = EventKind::TaskCreated;
+    activity::record(conn, actor, next.story_id, kind, next.id, None, Some(&next)).await?;
+    event::publish(conn, next.story_id, kind, next.id, &next).await?;
+    Ok(task)
+}
+
+/// Move tasks, with all of their subtasks, to the end of another story on a connection. Returns
+/// the listed tasks, in the order listed.
+async fn move_tasks(
+    conn: &mut PgConnection,
+    actor: &str,
+    task_ids: &[Uuid],
+    story_id: Uuid,
+    parent_task_id: Option<Uuid>,
+) -> Result<Vec<Task>> {
+    // The stories the tasks move out of and into are locked in id order, before any task, so
+    // opposite moves can't deadlock. Moves into a story are serialized, like moves within it and
+    // workflow changes, and moves out of a story share lock it, like other task updates.
+    let mut story_ids = sqlx::query_scalar!(
+        "SELECT DISTINCT story_id FROM tasks WHERE id = ANY($1) AND deleted_at IS NULL",
+        task_ids,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    story_ids.push(story_id);
+    story_ids.sort_unstable();
+    story_ids.dedup();
+    for &id in &story_ids {
+        if id != story_id {
+            lock_story(conn, id).await?;
+            continue;
+        }
+        sqlx::query!(
+            "SELECT id FROM stories WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+            story_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+    }
+    let found = sqlx::query!(
+        r#"SELECT id, story_id FROM tasks WHERE id = ANY($1) AND deleted_at IS NULL
+        FOR UPDATE"#,
+        task_ids,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for &task_id in task_ids {
+        match found.iter().find(|t| t.id == task_id) {
+            None => return Err(Error::not_found(format!("task not found: {task_id}"))),
+            Some(t) if t.story_id == story_id => {
+                return Err(Error::invalid_args(&format!(
+                    "task_ids: already in the story: {task_id}"
+                )))
+            }
+            Some(t) if !story_ids.contains(&t.story_id) => {
+                return Err(Error::conflict(format!(
+                    "task changed during update: {task_id}"
+                )))
+            }
+            Some(_) => {}
+        }
+    }
+    // The tasks and their subtasks, including those in the trash, so a restore can't leave
+    // a subtask behind in the old story.
+    let before = query_tasks!(
+        r#"WITH RECURSIVE subtree AS (
+            SELECT id FROM tasks WHERE id = ANY($1)
+            UNION
+            SELECT t.id FROM tasks t JOIN subtree s ON t.parent_task_id = s.id
+        )
+        SELECT"#,
+        r#"FROM tasks WHERE id IN (SELECT id FROM subtree)
+        ORDER BY position, seqno FOR UPDATE"#,
+        task_ids,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Listed tasks under another moved task keep their parent, the others are reparented
+    let ids: Vec<Uuid> = before.iter().map(|t| t.id).collect();
+    let roots: Vec<Uuid> = before
+        .iter()
+        .filter(|t| task_ids.contains(&t.id))
+        .filter(|t| !t.parent_task_id.is_some_and(|id| ids.contains(&id)))
+        .map(|t| t.id)
+        .collect();
+    if let Some(parent_task_id) = parent_task_id {
+        for &task_id in &roots {
+            ensure_parent(conn, story_id, Some(task_id), parent_task_id).await?;
+        }
+    }
+    let workflow = workflow::select_workflow(conn, story_id).await?;
+    let mut missing: Vec<&str> = before
+        .iter()
+        .map(|t| t.status.as_str())
+        .filter(|&status| workflow.state(status).is_none())
+        .collect();
+    if !missing.is_empty() {
+        missing.sort_unstable();
+        missing.dedup();
+        return Err(Error::conflict(format!(
+            "tasks are in states missing from the story workflow: {}",
+            missing.join(", ")
+        )));
+    }
+
+    // Append the tasks to the story, keeping their order
+    sqlx::query!(
+        r#"UPDATE tasks t SET story_id = $2,
+        parent_task_id = CASE WHEN t.id = ANY($3) THEN $4 ELSE t.parent_task_id END,
+        position = (SELECT coalesce(max(position), 0) FROM tasks WHERE story_id = $2)
+            + m.n * $5,
+        updated_at = now()
+        FROM unnest($1::uuid[]) WITH ORDINALITY AS m(id, n)
+        WHERE t.id = m.id"#,
+        &ids,
+        story_id,
+        &roots,
+        parent_task_id,
+        POSITION_GAP,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE comments SET story_id = $1 WHERE task_id = ANY($2)",
+        story_id,
+        &ids,
+    )
+    .execute(&mut *conn)
+    .await?;
+    let after = query_tasks!(
+        "SELECT",
+        "FROM tasks WHERE id = ANY($1) ORDER BY position, seqno",
+        &ids,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Positions kept their order, so before and after line up. Both the old and new stories
+    // get the move in their activity and events.
+    let kind = EventKind::TaskMoved;
+    for (before, task) in before.iter().zip(&after) {
+        if task.deleted_at.is_some() {
+            continue;
+        }
+        for story_id in [before.story_id, task.story_id] {
+            activity::record(
+                conn,
+                actor,
+                story_id,
+                kind,
+                task.id,
+                Some(before),
+                Some(task),
+            )
+            .await?;
+            event::publish(conn, story_id, kind, task.id, task).await?;
+        }
+    }
+    let moved = task_ids
+        .iter()
+        .filter_map(|&task_id| after.iter().find(|t| t.id == task_id).cloned())
+        .collect();
+    Ok(moved)
+}
+
+/// Share lock a story, so its workflow can't be replaced until the transaction ends.
+pub(super) async fn lock_story(conn: &mut PgConnection, story_id: Uuid) -> Result<()> {
+    sqlx::query!(
+        "SELECT id FROM stories WHERE id = $1 AND deleted_at IS NULL FOR SHARE",
+        story_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+    Ok(())
+}
+
+/// Select the story of a live task, without locking either.
+async fn task_story_id(conn: &mut PgConnection, task_id: Uuid) -> Result<Uuid> {
+    sqlx::query_scalar!(
+        "SELECT story_id FROM tasks WHERE id = $1 AND deleted_at IS NULL",
+        task_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| Error::not_found(format!("task not found: {task_id}")))
+}
+
+/// Lock a task for update, after share locking its story. Stories are always locked before their
+/// tasks, as workflow changes do. A transaction that locks a task this way must not lock another
+/// story afterwards: moves lock all of their stories up front, in id order, instead.
+pub(super) async fn lock_task(conn: &mut PgConnection, task_id: Uuid) -> Result<Task> {
+    let story_id = task_story_id(conn, task_id).await?;
+    lock_story(conn, story_id).await?;
+    query_tasks!(
+        "SELECT",
+        "FROM tasks WHERE id = $1 AND story_id = $2 AND deleted_at IS NULL FOR UPDATE",
+        task_id,
+        story_id,
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| Error::conflict(format!("task changed during update: {task_id}")))
+}
+
+/// Check a task can be placed under a parent: the parent must be a live task in the same story,
+/// the move must not create a cycle, and the result must not nest deeper than the limit. The
+/// task is None when it's being created. Locks the parent, so it can't be deleted concurrently.
+async fn ensure_parent(
+    conn: &mut PgConnection,
+    story_id: Uuid,
+    task_id: Option<Uuid>,
+    parent_task_id: Uuid,
+) -> Result<()> {
+    let parent = sqlx::query_scalar!(
+        r#"SELECT id FROM tasks WHERE id = $1 AND story_id = $2 AND deleted_at IS NULL
+        FOR UPDATE"#,
+        parent_task_id,
+        story_id,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if parent.is_none() {
+        return Err(Error::invalid_args(
+            "parent_task_id: not a task in the story",
+        ));
+    }
+
+    // The parent and its ancestors, nearest first
+    let ancestors = sqlx::query_scalar!(
+        r#"WITH RECURSIVE ancestors AS (
+            SELECT id, parent_task_id FROM tasks WHERE id = $1
+            UNION
+            SELECT t.id, t.parent_task_id FROM tasks t JOIN ancestors a ON t.id = a.parent_task_id
+        )
+        SELECT id AS "id!" FROM ancestors"#,
+        parent_task_id,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // How many levels the task brings with it, counting itself
+    let mut height = 1;
+    if let Some(task_id) = task_id {
+        if ancestors.contains(&task_id) {
+            return Err(Error::invalid_args(
+                "parent_task_id: a task can't be nested under itself or its subtasks",
+            ));
+        }
+        height = sqlx::query_scalar!(
+            r#"WITH RECURSIVE subtree AS (
+                SELECT id, 1 AS depth FROM tasks WHERE id = $1
+                UNION
+                SELECT t.id, s.depth + 1 FROM tasks t JOIN subtree s ON t.parent_task_id = s.id
+                WHERE t.deleted_at IS NULL
+            )
+            SELECT max(depth) AS "height!" FROM subtree"#,
+            task_id,
+        )
+        .fetch_one(&mut *conn)
+        .await? as usize;
+    }
+    if ancestors.len() + height > MAX_TASK_DEPTH {
+        return Err(Error::invalid_args(&format!(
+            "parent_task_id: tasks can be nested at most {MAX_TASK_DEPTH} levels deep"
+        )));
+    }
+    Ok(())
+}
+
+/// Get the label names a task filter selects by, and how many of them a task must have.
+fn label_params(filter: &TaskFilter) -> (Option<&[String]>, i64) {
+    match &filter.labels {
+        Some(labels) if labels.match_all() => (Some(&labels.names), labels.names.len() as i64),
+        Some(labels) => (Some(&labels.names), 1),
+        None => (None, 0),
+    }
+}
+
+/// Pick a position strictly between two neighbours, where None is an open end of the list.
+/// Returns None when the neighbours are too close, and positions need rebalancing.
+fn between(lower: Option<i64>, upper: Option<i64>) -> Option<i64> {
+    match (lower, upper) {
+        (None, None) => Some(POSITION_GAP),
+        (Some(lower), None) => lower.checked_add(POSITION_GAP),
+        (None, Some(upper)) => upper.checked_sub(POSITION_GAP),
+        (Some(lower), Some(upper)) if upper - lower > 1 => Some(lower + (upper - lower) / 2),
+        _ => None,
+    }
+}
+
+/// Spread the positions of a story's tasks out evenly, keeping their order.
+async fn rebalance_tasks(conn: &mut PgConnection, story_id: Uuid) -> Result<()> {
+    tracing::info!("rebalancing task positions for story {}", story_id);
+    sqlx::query!(
+        r#"UPDATE tasks SET position = ranked.position
+        FROM (
+            SELECT id, row_number() OVER (ORDER BY position, seqno) * $2 AS position
+            FROM tasks WHERE story_id = $1 AND deleted_at IS NULL
+        ) ranked
+        WHERE tasks.id = ranked.id"#,
+        story_id,
+        POSITION_GAP,
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::between;
     use crate::{
-        domain::Status,
+        domain::{Placement, Status, TaskChanges, TaskFilter, Workflow, WorkflowState},
         repo::{tests, Repo},
+        Error,
     };
+    use chrono::Utc;
     use std::sync::Arc;
 
     use testcontainers::{runners::AsyncRunner, ImageExt};
     use testcontainers_modules::postgres::Postgres;
 
+    #[test]
+    fn position_between_neighbours() {
+        assert_eq!(between(None, None), Some(65536));
+        assert_eq!(between(Some(10), None), Some(10 + 65536));
+        assert_eq!(between(None, Some(10)), Some(10 - 65536));
+        assert_eq!(between(Some(10), Some(20)), Some(15));
+        assert_eq!(between(Some(10), Some(12)), Some(11));
+        assert_eq!(between(Some(10), Some(11)), None);
+        assert_eq!(between(Some(10), Some(10)), None);
+        assert_eq!(between(Some(i64::MAX), None), None);
+    }
+
     #[ignore]
     #[tokio::test]
     async fn integration_test() {
@@ -95,13 +1027,23 @@ mod tests {
 
         // Set up a story to put tasks under
         let name = "Books To Read".to_string();
-        let story = repo.create_story(name.clone()).await.unwrap();
+        let story = repo
+            .create_story("tester", name.clone(), None, Default::default())
+            .await
+            .unwrap();
         let story_id = story.id;
 
         // Create task, ensuring status is incomplete
-        let status = Status::Incomplete;
+        let status = Status::Incomplete.as_ref();
         let task = repo
-            .create_task(story_id.clone(), "Suttree".to_string(), status)
+            .create_task(
+                "tester",
+                story_id,
+                None,
+                "Suttree".to_string(),
+                Some(status),
+                None,
+            )
             .await
             .unwrap();
         assert_eq!(task.status, Status::Incomplete.to_string());
@@ -110,25 +1052,556 @@ mod tests {
         assert!(repo.fetch_task(task.id).await.is_ok());
 
         // Set task status to complete
-        repo.update_task(task.id, task.name, Status::Complete)
-            .await
-            .unwrap();
+        repo.update_task(
+            "tester",
+            task.id,
+            &TaskChanges {
+                status: Some(Status::Complete.as_ref().into()),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
 
         // Fetch task and assert status was updated
         let task = repo.fetch_task(task.id).await.unwrap();
         assert_eq!(task.status, Status::Complete.to_string());
 
-        // Query tasks for story.
-        let tasks = repo.list_tasks(story_id).await.unwrap();
+        // Add newer incomplete tasks
+        for name in ["Blood Meridian", "The Road", "Outer Dark"] {
+            repo.create_task(
+                "tester",
+                story_id,
+                None,
+                name.into(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Query tasks for story, filtering by status
+        let all = TaskFilter::default();
+        let (_, tasks) = repo.list_tasks(story_id, &all, None, 10).await.unwrap();
+        assert_eq!(tasks.len(), 4);
+        let complete = TaskFilter {
+            status: Some(Status::Complete.to_string()),
+            ..Default::default()
+        };
+        let (_, tasks) = repo
+            .list_tasks(story_id, &complete, None, 10)
+            .await
+            .unwrap();
         assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, task.id);
 
-        // Delete the task
-        repo.delete_task(task.id).await.unwrap();
+        // Page through incomplete tasks
+        let incomplete = TaskFilter {
+            status: Some(Status::Incomplete.to_string()),
+            ..Default::default()
+        };
+        let (after, tasks) = repo
+            .list_tasks(story_id, &incomplete, None, 2)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "Blood Meridian");
+        let (_, tasks) = repo
+            .list_tasks(story_id, &incomplete, after, 2)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "Outer Dark");
+
+        // Move tasks around, listing them in position order
+        let names = |tasks: Vec<crate::domain::Task>| -> Vec<String> {
+            tasks.into_iter().map(|t| t.name).collect()
+        };
+        let (_, tasks) = repo.list_tasks(story_id, &all, None, 10).await.unwrap();
+        let ids: Vec<_> = tasks.iter().map(|t| t.id).collect();
+        let moved = repo
+            .move_task("tester", ids[3], Placement::Before(ids[0]))
+            .await
+            .unwrap();
+        assert!(moved.position < tasks[0].position);
+        repo.move_task("tester", ids[0], Placement::After(ids[1]))
+            .await
+            .unwrap();
+        let (_, tasks) = repo.list_tasks(story_id, &all, None, 10).await.unwrap();
+        assert_eq!(
+            names(tasks),
+            vec!["Outer Dark", "Blood Meridian", "Suttree", "The Road"]
+        );
+
+        // Moving between adjacent tasks many times forces a rebalance
+        for _ in 0..20 {
+            repo.move_task("tester", ids[2], Placement::After(ids[3]))
+                .await
+                .unwrap();
+            repo.move_task("tester", ids[1], Placement::After(ids[3]))
+                .await
+                .unwrap();
+        }
+        let (_, tasks) = repo.list_tasks(story_id, &all, None, 10).await.unwrap();
+        assert_eq!(
+            names(tasks),
+            vec!["Outer Dark", "Blood Meridian", "The Road", "Suttree"]
+        );
+
+        // Siblings must be other tasks in the same story
+        let result = repo
+            .move_task("tester", ids[0], Placement::Before(ids[0]))
+            .await;
+        assert!(result.is_err());
+        let result = repo
+            .move_task("tester", ids[0], Placement::Before(story_id))
+            .await;
+        assert!(result.is_err());
+
+        // Filter tasks by due time
+        let now = Utc::now();
+        let hours = |h| now + chrono::Duration::hours(h);
+        let in_10m = now + chrono::Duration::minutes(10);
+        let incomplete = Status::Incomplete.as_ref();
+        let late = repo
+            .create_task(
+                "tester",
+                story_id,
+                None,
+                "Late".into(),
+                Some(incomplete),
+                Some(hours(-1)),
+            )
+            .await
+            .unwrap();
+        let soon = repo
+            .create_task(
+                "tester",
+                story_id,
+                None,
+                "Soon".into(),
+                Some(incomplete),
+                Some(in_10m),
+            )
+            .await
+            .unwrap();
+        let complete = Status::Complete.as_ref();
+        repo.create_task(
+            "tester",
+            story_id,
+            None,
+            "Done".into(),
+            Some(complete),
+            Some(hours(-1)),
+        )
+        .await
+        .unwrap();
+        let overdue = TaskFilter {
+            overdue: Some(true),
+            ..Default::default()
+        };
+        let (_, tasks) = repo.list_tasks(story_id, &overdue, None, 10).await.unwrap();
+        assert_eq!(names(tasks), vec!["Late"]);
+        let due = TaskFilter {
+            due_after: Some(hours(-2)),
+            due_before: Some(hours(1)),
+            ..Default::default()
+        };
+        let (_, tasks) = repo.list_tasks(story_id, &due, None, 10).await.unwrap();
+        assert_eq!(tasks.len(), 3);
+
+        // Open tasks coming due are reminded once
+        let mut reminded: Vec<_> = repo
+            .claim_reminders(hours(1), 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        reminded.sort();
+        let mut expected = vec![late.id, soon.id];
+        expected.sort();
+        assert_eq!(reminded, expected);
+        assert!(repo.claim_reminders(hours(1), 10).await.unwrap().is_empty());
+
+        // Changing the due time re-arms the reminder
+        repo.update_task(
+            "tester",
+            soon.id,
+            &TaskChanges {
+                status: Some(incomplete.into()),
+                due_at: Some(Some(hours(2))),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(repo.claim_reminders(hours(1), 10).await.unwrap().is_empty());
+        let reminded = repo.claim_reminders(hours(3), 10).await.unwrap();
+        assert_eq!(reminded.len(), 1);
+        assert_eq!(reminded[0].id, soon.id);
+
+        // Assigned tasks are listed soonest due first, with undated tasks last
+        for id in [task.id, soon.id, late.id] {
+            repo.assign_task("tester", id, Some("alice")).await.unwrap();
+        }
+        let (after, tasks) = repo
+            .list_assigned_tasks("alice", &all, None, 2)
+            .await
+            .unwrap();
+        assert_eq!(names(tasks), vec!["Late", "Soon"]);
+        let (_, tasks) = repo
+            .list_assigned_tasks("alice", &all, after, 2)
+            .await
+            .unwrap();
+        assert_eq!(names(tasks), vec!["Suttree"]);
+        let (_, tasks) = repo
+            .list_assigned_tasks("alice", &overdue, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(names(tasks), vec!["Late"]);
+        let (_, tasks) = repo
+            .list_assigned_tasks("bob", &all, None, 10)
+            .await
+            .unwrap();
+        assert!(tasks.is_empty());
+
+        // Unassigned tasks drop out of the list
+        let unassigned = repo.assign_task("tester", late.id, None).await.unwrap();
+        assert_eq!(unassigned.assignee, None);
+        let (_, tasks) = repo
+            .list_assigned_tasks("alice", &all, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(names(tasks), vec!["Soon", "Suttree"]);
+
+        // Nest subtasks, and track how many of them are closed
+        let sub = |parent, name: &str| {
+            let repo = &repo;
+            let name = name.to_string();
+            async move {
+                repo.create_task(
+                    "tester",
+                    story_id,
+                    Some(parent),
+                    name,
+                    Some(incomplete),
+                    None,
+                )
+                .await
+            }
+        };
+        let part1 = sub(late.id, "Part 1").await.unwrap();
+        let part2 = sub(late.id, "Part 2").await.unwrap();
+        let chapter = sub(part1.id, "Chapter 1").await.unwrap();
+        assert_eq!(chapter.parent_task_id, Some(part1.id));
+        repo.update_task(
+            "tester",
+            part2.id,
+            &TaskChanges {
+                status: Some(complete.into()),
+                parent_task_id: Some(Some(late.id)),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        let parent = repo.fetch_task(late.id).await.unwrap();
+        assert_eq!((parent.subtasks, parent.closed_subtasks), (2, 1));
+        let by_parent = TaskFilter {
+            parent_task_id: Some(late.id),
+            ..Default::default()
+        };
+        let (_, tasks) = repo
+            .list_tasks(story_id, &by_parent, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(names(tasks), vec!["Part 1", "Part 2"]);
+
+        // Nesting is limited in depth, can't form cycles, and stays within a story
+        assert!(sub(chapter.id, "Page 1").await.is_err());
+        let result = repo
+            .update_task(
+                "tester",
+                late.id,
+                &TaskChanges {
+                    status: Some(incomplete.into()),
+                    parent_task_id: Some(Some(part1.id)),
+                    ..Default::default()
+                },
+                false,
+            )
+            .await;
+        assert!(result.is_err());
+        let other = repo
+            .create_story("tester", "Other".into(), None, Default::default())
+            .await
+            .unwrap();
+        let result = repo
+            .create_task(
+                "tester",
+                other.id,
+                Some(late.id),
+                "X".into(),
+                Some(incomplete),
+                None,
+            )
+            .await;
+        assert!(result.is_err());
+
+        // Closing the last open subtask of a task closes it too, when asked to
+        let trip = repo
+            .create_story("tester", "Trip".into(), None, Default::default())
+            .await
+            .unwrap();
+        let travel = repo
+            .create_task("tester", trip.id, None, "Travel".into(), None, None)
+            .await
+            .unwrap();
+        let pack = repo
+            .create_task(
+                "tester",
+                trip.id,
+                Some(travel.id),
+                "Pack".into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let book = repo
+            .create_task(
+                "tester",
+                trip.id,
+                Some(travel.id),
+                "Book".into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let close = TaskChanges {
+            status: Some(complete.into()),
+            ..Default::default()
+        };
+        repo.update_task("tester", pack.id, &close, true)
+            .await
+            .unwrap();
+        assert_eq!(repo.fetch_task(travel.id).await.unwrap().status, incomplete);
+        repo.update_task("tester", book.id, &close, false)
+            .await
+            .unwrap();
+        assert_eq!(repo.fetch_task(travel.id).await.unwrap().status, incomplete);
+        let reopen = TaskChanges {
+            status: Some(incomplete.into()),
+            ..Default::default()
+        };
+        repo.update_task("tester", book.id, &reopen, false)
+            .await
+            .unwrap();
+        repo.update_task("tester", book.id, &close, true)
+            .await
+            .unwrap();
+        assert_eq!(repo.fetch_task(travel.id).await.unwrap().status, complete);
+
+        // Deleting a task deletes its subtree
+        repo.delete_task("tester", part1.id).await.unwrap();
+        assert!(repo.fetch_task(chapter.id).await.is_err());
+        let parent = repo.fetch_task(late.id).await.unwrap();
+        assert_eq!((parent.subtasks, parent.closed_subtasks), (1, 1));
+
+        // Closing a recurring task hands its rule over to the next occurrence
+        let rule = "FREQ=WEEKLY;COUNT=2";
+        let chore = repo
+            .set_recurrence("tester", soon.id, Some(rule))
+            .await
+            .unwrap();
+        assert_eq!(chore.recurrence.as_deref(), Some(rule));
+        let close = TaskChanges {
+            status: Some(complete.into()),
+            ..Default::default()
+        };
+        let closed = repo
+            .update_task("tester", chore.id, &close, false)
+            .await
+            .unwrap();
+        assert_eq!(closed.recurrence, None);
+        let (_, tasks) = repo.list_tasks(story_id, &all, None, 20).await.unwrap();
+        let next = tasks
+            .into_iter()
+            .find(|t| t.name == "Soon" && t.id != chore.id)
+            .unwrap();
+        assert_eq!(next.status, incomplete);
+        let week = chrono::Duration::weeks(1);
+        assert_eq!(next.due_at, closed.due_at.map(|at| at + week));
+        assert_eq!(next.assignee.as_deref(), Some("alice"));
+        assert_eq!(next.recurrence.as_deref(), Some("FREQ=WEEKLY;COUNT=1"));
+
+        // The last occurrence of a series keeps its rule when closed
+        let closed = repo
+            .update_task("tester", next.id, &close, false)
+            .await
+            .unwrap();
+        assert_eq!(closed.recurrence.as_deref(), Some("FREQ=WEEKLY;COUNT=1"));
+        let next = repo.set_recurrence("tester", next.id, None).await.unwrap();
+        assert_eq!(next.recurrence, None);
+
+        // Tasks without a due time repeat one interval after they're closed
+        let plants = repo
+            .create_task("tester", trip.id, None, "Plants".into(), None, None)
+            .await
+            .unwrap();
+        repo.set_recurrence("tester", plants.id, Some("FREQ=DAILY"))
+            .await
+            .unwrap();
+        let closed_at = Utc::now();
+        repo.update_task("tester", plants.id, &close, false)
+            .await
+            .unwrap();
+        let (_, tasks) = repo.list_tasks(trip.id, &all, None, 10).await.unwrap();
+        let next = tasks
+            .into_iter()
+            .find(|t| t.name == "Plants" && t.id != plants.id)
+            .unwrap();
+        let day = chrono::Duration::days(1);
+        let due_at = next.due_at.unwrap();
+        assert!(due_at >= closed_at + day && due_at <= Utc::now() + day);
+
+        // Tasks move to other stories with their subtasks, including those in the trash, but
+        // only into workflows that have their states
+        let strict = repo
+            .create_story("tester", "Strict".into(), None, Default::default())
+            .await
+            .unwrap();
+        let workflow = Workflow {
+            states: vec![WorkflowState {
+                name: "todo".into(),
+                closed: false,
+            }],
+            transitions: vec![],
+        };
+        repo.replace_workflow("tester", strict.id, workflow)
+            .await
+            .unwrap();
+        let result = repo
+            .move_tasks_to_story("tester", &[late.id], strict.id, None)
+            .await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        let moved = repo
+            .move_tasks_to_story("tester", &[late.id], other.id, None)
+            .await
+            .unwrap();
+        assert_eq!((moved[0].story_id, moved[0].subtasks), (other.id, 1));
+        let (_, tasks) = repo.list_tasks(other.id, &all, None, 10).await.unwrap();
+        assert_eq!(names(tasks), vec!["Late", "Part 2"]);
+        let trash = repo.list_deleted_tasks().await.unwrap();
+        let chapter = trash.iter().find(|t| t.id == chapter.id).unwrap();
+        assert_eq!(chapter.story_id, other.id);
+        let result = repo
+            .move_tasks_to_story("tester", &[late.id], other.id, None)
+            .await;
+        assert!(result.is_err());
+        let (_, activity) = repo.list_activity(story_id, 1, 100).await.unwrap();
+        assert!(activity
+            .iter()
+            .any(|a| a.entity_id == late.id && a.kind == "task_moved"));
+
+        // Tasks listed along with their parent stay under it
+        let pack = repo
+            .create_task(
+                "tester",
+                story_id,
+                None,
+                "Pack".into(),
+                Some(incomplete),
+                None,
+            )
+            .await
+            .unwrap();
+        let socks = sub(pack.id, "Socks").await.unwrap();
+        let moved = repo
+            .move_tasks_to_story("tester", &[socks.id, pack.id], other.id, None)
+            .await
+            .unwrap();
+        assert_eq!((moved[0].id, moved[1].id), (socks.id, pack.id));
+        assert_eq!(moved[0].parent_task_id, Some(pack.id));
+        assert_eq!(moved[1].parent_task_id, None);
+        assert!(moved.iter().all(|t| t.story_id == other.id));
+
+        // Updates that move a task are all or nothing
+        let move_to = |story_id| TaskChanges {
+            status: Some(complete.into()),
+            story_id: Some(story_id),
+            ..Default::default()
+        };
+        let result = repo
+            .update_task("tester", pack.id, &move_to(strict.id), false)
+            .await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        let unmoved = repo.fetch_task(pack.id).await.unwrap();
+        assert_eq!((unmoved.story_id, unmoved.status), (other.id, pack.status));
+        let moved = repo
+            .update_task("tester", socks.id, &move_to(story_id), false)
+            .await
+            .unwrap();
+        assert_eq!(moved.story_id, story_id);
+        assert_eq!(moved.parent_task_id, None);
+        assert_eq!(moved.status, complete);
+
+        // Delete the task, only once
+        repo.delete_task("tester", task.id).await.unwrap();
+        let result = repo.delete_task("tester", task.id).await;
+        assert!(matches!(result, Err(Error::NotFound { .. })));
 
         // Assert task was deleted
         assert!(repo.fetch_task(task.id).await.is_err());
 
         // Cleanup
-        repo.delete_story(story_id).await.unwrap();
+        repo.delete_story("tester", story_id).await.unwrap();
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn concurrent_opposite_moves() {
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool_with(&container, 4).await;
+        let repo = Repo::new(Arc::clone(&pool));
+
+        let story =
+            |name: &str| repo.create_story("tester", name.to_string(), None, Default::default());
+        let (left, right) = (story("Left").await.unwrap(), story("Right").await.unwrap());
+        let task = |story_id, name: &str| {
+            repo.create_task("tester", story_id, None, name.to_string(), None, None)
+        };
+        let (a, b) = (
+            task(left.id, "A").await.unwrap(),
+            task(right.id, "B").await.unwrap(),
+        );
+
+        // Tasks swapping stories lock both stories in the same order, so neither move fails
+        // as a deadlock victim
+        let move_to = |story_id| TaskChanges {
+            story_id: Some(story_id),
+            ..Default::default()
+        };
+        for round in 0..10 {
+            let (to_a, to_b) = if round % 2 == 0 {
+                (right.id, left.id)
+            } else {
+                (left.id, right.id)
+            };
+            let (changes_a, changes_b) = (move_to(to_a), move_to(to_b));
+            let (moved_a, moved_b) = tokio::join!(
+                repo.update_task("tester", a.id, &changes_a, false),
+                repo.update_task("tester", b.id, &changes_b, false),
+            );
+            assert_eq!(moved_a.unwrap().story_id, to_a);
+            assert_eq!(moved_b.unwrap().story_id, to_b);
+        }
     }
 }
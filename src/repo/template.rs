@@ -0,0 +1,472 @@
+use super::{activity, event, file, query_tasks, Repo};
+use crate::{
+    domain::{EventKind, Story, StoryFile, StoryTemplate, Workflow},
+    Error, Result,
+};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Extend repo with story templates, and copies of stories.
+impl Repo {
+    /// Select a template by id
+    pub async fn fetch_template(&self, template_id: Uuid) -> Result<StoryTemplate> {
+        let mut conn = self.db.acquire().await?;
+        select_template(&mut conn, template_id).await
+    }
+
+    /// Select all templates, by name.
+    pub async fn list_templates(&self) -> Result<Vec<StoryTemplate>> {
+        let query = sqlx::query_as!(
+            StoryTemplate,
+            r#"SELECT id, name, story_name, description, metadata,
+            (SELECT count(*) FROM template_tasks WHERE template_id = t.id) AS "tasks!",
+            (SELECT count(*) FROM template_files WHERE template_id = t.id) AS "files!",
+            seqno, created_at
+            FROM story_templates t ORDER BY lower(name), seqno"#,
+        );
+        let templates = query.fetch_all(self.db_ref()).await?;
+        Ok(templates)
+    }
+
+    /// Save the details and live tasks of a story as a template, optionally with its live files.
+    pub async fn create_template(
+        &self,
+        story_id: Uuid,
+        name: String,
+        include_files: bool,
+    ) -> Result<StoryTemplate> {
+        let mut tx = self.db.begin().await?;
+        let template_id = sqlx::query_scalar!(
+            r#"INSERT INTO story_templates (name, story_name, description, metadata)
+            SELECT $2, name, description, metadata FROM stories
+            WHERE id = $1 AND deleted_at IS NULL FOR SHARE
+            RETURNING id"#,
+            story_id,
+            name,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+        let (old_ids, new_ids) = copy_ids(&mut tx, story_id).await?;
+        sqlx::query!(
+            r#"INSERT INTO template_tasks (id, template_id, parent_id, name, position)
+            SELECT m.new_id, $1, p.new_id, t.name, t.position
+            FROM unnest($2::uuid[], $3::uuid[]) AS m(old_id, new_id)
+            JOIN tasks t ON t.id = m.old_id AND t.story_id = $4 AND t.deleted_at IS NULL
+            LEFT JOIN unnest($2::uuid[], $3::uuid[]) AS p(old_id, new_id)
+                ON p.old_id = t.parent_task_id"#,
+            template_id,
+            &old_ids,
+            &new_ids,
+            story_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if include_files {
+            sqlx::query!(
+                r#"INSERT INTO template_files (template_id, storage_id, name, size, content_type)
+                SELECT $1, storage_id, name, size, content_type FROM story_files
+                WHERE story_id = $2 AND deleted_at IS NULL ORDER BY seqno"#,
+                template_id,
+                story_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        let template = select_template(&mut tx, template_id).await?;
+        tx.commit().await?;
+        Ok(template)
+    }
+
+    /// Delete a template. Returns the storage ids of template files that no other file shares,
+    /// so their contents can be removed.
+    pub async fn delete_template(&self, template_id: Uuid) -> Result<Vec<Uuid>> {
+        let mut tx = self.db.begin().await?;
+        let storage_ids = sqlx::query_scalar!(
+            "DELETE FROM template_files WHERE template_id = $1 RETURNING storage_id",
+            template_id,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM story_templates WHERE id = $1 RETURNING id",
+            template_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("template not found: {template_id}")))?;
+        let storage_ids = file::unreferenced(&mut tx, storage_ids).await?;
+        tx.commit().await?;
+        Ok(storage_ids)
+    }
+
+    /// Insert a new story made from a template, named after the template unless a name is given.
+    /// Tasks start in the first state of the default workflow, and files share contents with
+    /// the template.
+    pub async fn create_story_from_template(
+        &self,
+        actor: &str,
+        template_id: Uuid,
+        name: Option<String>,
+    ) -> Result<Story> {
+        let mut tx = self.db.begin().await?;
+        let story = sqlx::query_as!(
+            Story,
+            r#"INSERT INTO stories (name, description, metadata)
+            SELECT coalesce($2, story_name), description, metadata FROM story_templates
+            WHERE id = $1 FOR SHARE
+            RETURNING id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at"#,
+            template_id,
+            name,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("template not found: {template_id}")))?;
+        let old_ids = sqlx::query_scalar!(
+            "SELECT id FROM template_tasks WHERE template_id = $1",
+            template_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let new_ids: Vec<Uuid> = old_ids.iter().map(|_| Uuid::new_v4()).collect();
+        let workflow = Workflow::default();
+        sqlx::query!(
+            r#"INSERT INTO tasks (id, story_id, parent_task_id, name, status, position)
+            SELECT m.new_id, $1, p.new_id, t.name, $4, t.position
+            FROM unnest($2::uuid[], $3::uuid[]) AS m(old_id, new_id)
+            JOIN template_tasks t ON t.id = m.old_id
+            LEFT JOIN unnest($2::uuid[], $3::uuid[]) AS p(old_id, new_id)
+                ON p.old_id = t.parent_id
+            ORDER BY t.position"#,
+            story.id,
+            &old_ids,
+            &new_ids,
+            workflow.initial().name,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO story_files (story_id, storage_id, name, size, content_type)
+            SELECT $1, storage_id, name, size, content_type FROM template_files
+            WHERE template_id = $2"#,
+            story.id,
+            template_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        record_copies(&mut tx, actor, &story).await?;
+        tx.commit().await?;
+        Ok(story)
+    }
+
+    /// Copy a story with its workflow, labels, and live tasks, keeping their statuses, nesting,
+    /// labels and dependencies on each other. Live files are copied too when asked, sharing
+    /// contents with the originals. The copy keeps the story name unless another is given.
+    pub async fn clone_story(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        name: Option<String>,
+        include_files: bool,
+    ) -> Result<Story> {
+        let mut tx = self.db.begin().await?;
+        let story = sqlx::query_as!(
+            Story,
+            r#"INSERT INTO stories (name, description, metadata)
+            SELECT coalesce($2, name), description, metadata FROM stories
+            WHERE id = $1 AND deleted_at IS NULL FOR SHARE
+            RETURNING id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at"#,
+            story_id,
+            name,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+
+        // Workflow and labels
+        sqlx::query!(
+            r#"INSERT INTO workflow_states (story_id, name, closed, position)
+            SELECT $1, name, closed, position FROM workflow_states WHERE story_id = $2"#,
+            story.id,
+            story_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO workflow_transitions (story_id, from_state, to_state)
+            SELECT $1, from_state, to_state FROM workflow_transitions WHERE story_id = $2"#,
+            story.id,
+            story_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO story_labels (story_id, label_id)
+            SELECT $1, label_id FROM story_labels WHERE story_id = $2"#,
+            story.id,
+            story_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Tasks, along with their labels and dependencies on each other
+        let (old_ids, new_ids) = copy_ids(&mut tx, story_id).await?;
+        sqlx::query!(
+            r#"INSERT INTO tasks
+            (id, story_id, parent_task_id, name, status, position, due_at, assignee, recurrence)
+            SELECT m.new_id, $1, p.new_id, t.name, t.status, t.position, t.due_at, t.assignee,
+            t.recurrence
+            FROM unnest($2::uuid[], $3::uuid[]) AS m(old_id, new_id)
+            JOIN tasks t ON t.id = m.old_id AND t.story_id = $4 AND t.deleted_at IS NULL
+            LEFT JOIN unnest($2::uuid[], $3::uuid[]) AS p(old_id, new_id)
+                ON p.old_id = t.parent_task_id
+            ORDER BY t.position, t.seqno"#,
+            story.id,
+            &old_ids,
+            &new_ids,
+            story_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO task_labels (task_id, label_id)
+            SELECT m.new_id, l.label_id FROM task_labels l
+            JOIN unnest($1::uuid[], $2::uuid[]) AS m(old_id, new_id) ON m.old_id = l.task_id"#,
+            &old_ids,
+            &new_ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO task_dependencies (task_id, depends_on_id)
+            SELECT a.new_id, b.new_id FROM task_dependencies d
+            JOIN unnest($1::uuid[], $2::uuid[]) AS a(old_id, new_id) ON a.old_id = d.task_id
+            JOIN unnest($1::uuid[], $2::uuid[]) AS b(old_id, new_id)
+                ON b.old_id = d.depends_on_id"#,
+            &old_ids,
+            &new_ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if include_files {
+            sqlx::query!(
+                r#"INSERT INTO story_files (story_id, storage_id, name, size, content_type)
+                SELECT $1, storage_id, name, size, content_type FROM story_files
+                WHERE story_id = $2 AND deleted_at IS NULL ORDER BY seqno"#,
+                story.id,
+                story_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        record_copies(&mut tx, actor, &story).await?;
+        tx.commit().await?;
+        Ok(story)
+    }
+}
+
+/// Select a template by id, with counts of its tasks and files.
+async fn select_template(conn: &mut PgConnection, template_id: Uuid) -> Result<StoryTemplate> {
+    let query = sqlx::query_as!(
+        StoryTemplate,
+        r#"SELECT id, name, story_name, description, metadata,
+        (SELECT count(*) FROM template_tasks WHERE template_id = t.id) AS "tasks!",
+        (SELECT count(*) FROM template_files WHERE template_id = t.id) AS "files!",
+        seqno, created_at
+        FROM story_templates t WHERE id = $1"#,
+        template_id,
+    );
+    match query.fetch_optional(&mut *conn).await? {
+        Some(template) => Ok(template),
+        None => Err(Error::not_found(format!(
+            "template not found: {template_id}"
+        ))),
+    }
+}
+
+/// Select the ids of the live tasks of a story, along with new ids for their copies. Share locks
+/// the tasks, so they can't be changed, moved or trashed while they're copied.
+async fn copy_ids(conn: &mut PgConnection, story_id: Uuid) -> Result<(Vec<Uuid>, Vec<Uuid>)> {
+    let old_ids = sqlx::query_scalar!(
+        "SELECT id FROM tasks WHERE story_id = $1 AND deleted_at IS NULL ORDER BY id FOR SHARE",
+        story_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let new_ids = old_ids.iter().map(|_| Uuid::new_v4()).collect();
+    Ok((old_ids, new_ids))
+}
+
+/// Record the creation of a copied story, its tasks and its files, and publish them as events.
+async fn record_copies(conn: &mut PgConnection, actor: &str, story: &Story) -> Result<()> {
+    let kind = EventKind::StoryCreated;
+    activity::record(conn, actor, story.id, kind, story.id, None, Some(story)).await?;
+    event::publish(conn, story.id, kind, story.id, story).await?;
+    let tasks = query_tasks!(
+        "SELECT",
+        "FROM tasks WHERE story_id = $1 ORDER BY position, seqno",
+        story.id,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let kind = EventKind::TaskCreated;
+    for task in tasks {
+        activity::record(conn, actor, story.id, kind, task.id, None, Some(&task)).await?;
+        event::publish(conn, story.id, kind, task.id, &task).await?;
+    }
+    let files = sqlx::query_as!(
+        StoryFile,
+        r#"SELECT id, story_id, storage_id, name, size, content_type, seqno, created_at,
+        updated_at, deleted_at
+        FROM story_files WHERE story_id = $1 ORDER BY seqno"#,
+        story.id,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let kind = EventKind::FileCreated;
+    for file in files {
+        activity::record(conn, actor, story.id, kind, file.id, None, Some(&file)).await?;
+        event::publish(conn, story.id, kind, file.id, &file).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::Status,
+        repo::{tests, Repo},
+    };
+    use chrono::Utc;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(Arc::clone(&pool));
+
+        // Set up a story with a subtask, a dependency, a label and a file
+        let story = repo
+            .create_story("tester", "Onboarding".into(), None, Default::default())
+            .await
+            .unwrap();
+        let (incomplete, complete) = (Status::Incomplete.as_ref(), Status::Complete.as_ref());
+        let laptop = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Laptop".into(),
+                Some(complete),
+                None,
+            )
+            .await
+            .unwrap();
+        let accounts = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Accounts".into(),
+                Some(incomplete),
+                None,
+            )
+            .await
+            .unwrap();
+        let email = repo
+            .create_task(
+                "tester",
+                story.id,
+                Some(accounts.id),
+                "Email".into(),
+                Some(incomplete),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.add_dependency("tester", &accounts, &laptop)
+            .await
+            .unwrap();
+        let label = repo
+            .create_label("hr".into(), "#1f883d".into())
+            .await
+            .unwrap();
+        repo.add_task_label("tester", story.id, email.id, &label)
+            .await
+            .unwrap();
+        let storage_id = Uuid::new_v4();
+        let handbook = repo
+            .create_file(
+                "tester",
+                story.id,
+                storage_id,
+                "handbook.pdf".into(),
+                1024,
+                "application/pdf".into(),
+            )
+            .await
+            .unwrap();
+
+        // Clone the story, keeping statuses, nesting, labels and dependencies
+        let copy = repo
+            .clone_story("tester", story.id, Some("Onboarding Ada".into()), true)
+            .await
+            .unwrap();
+        assert_eq!(copy.name, "Onboarding Ada");
+        let tasks = repo.list_tasks_by_dependencies(copy.id).await.unwrap();
+        let names: Vec<_> = tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Laptop", "Accounts", "Email"]);
+        assert_eq!(tasks[0].status, complete);
+        assert_eq!(tasks[2].parent_task_id, Some(tasks[1].id));
+        assert_eq!(
+            repo.list_blockers(tasks[1].id).await.unwrap()[0].id,
+            tasks[0].id
+        );
+        let labels = repo.list_task_labels(tasks[2].id).await.unwrap();
+        assert_eq!(labels, vec![label]);
+        let (_, activity) = repo.list_activity(copy.id, 1, 20).await.unwrap();
+        assert_eq!(activity.len(), 5, "story, 3 tasks and a file");
+
+        // Save a template with files, and make a story from it with fresh tasks
+        let template = repo
+            .create_template(story.id, "New hire".into(), true)
+            .await
+            .unwrap();
+        assert_eq!(template.story_name, "Onboarding");
+        assert_eq!((template.tasks, template.files), (3, 1));
+        assert_eq!(repo.list_templates().await.unwrap(), vec![template.clone()]);
+        let made = repo
+            .create_story_from_template("tester", template.id, None)
+            .await
+            .unwrap();
+        assert_eq!(made.name, "Onboarding");
+        let tasks = repo.list_tasks_by_dependencies(made.id).await.unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert!(tasks.iter().all(|t| t.status == incomplete));
+        assert_eq!(tasks[2].parent_task_id, Some(tasks[1].id));
+
+        // Shared contents are only purged once no file refers to them
+        let storage_ids = repo.delete_template(template.id).await.unwrap();
+        assert!(storage_ids.is_empty());
+        assert!(repo.fetch_template(template.id).await.is_err());
+        for story_id in [story.id, copy.id] {
+            repo.delete_story("tester", story_id).await.unwrap();
+        }
+        let storage_ids = repo.purge_trash(Utc::now()).await.unwrap();
+        assert!(storage_ids.is_empty());
+        repo.delete_story("tester", made.id).await.unwrap();
+        let storage_ids = repo.purge_trash(Utc::now()).await.unwrap();
+        assert_eq!(storage_ids, vec![handbook.storage_id]);
+    }
+}
@@ -0,0 +1,407 @@
+use super::{activity, event, file, query_tasks, task, Repo};
+use crate::{
+    domain::{EventKind, Story, StoryFile, Task},
+    Error, Result,
+};
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Put some reasonable upper limit when listing trash.
+const MAX_TRASH: i64 = 100;
+
+// Extend repo with queries related to deleted stories, tasks, and files.
+impl Repo {
+    /// Select the most recently deleted stories.
+    pub async fn list_deleted_stories(&self) -> Result<Vec<Story>> {
+        let query = sqlx::query_as!(
+            Story,
+            r#"SELECT id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at FROM stories
+            WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC LIMIT $1"#,
+            MAX_TRASH,
+        );
+        let stories = query.fetch_all(self.db_ref()).await?;
+        Ok(stories)
+    }
+
+    /// Select the most recently deleted tasks that can be restored on their own.
+    pub async fn list_deleted_tasks(&self) -> Result<Vec<Task>> {
+        let query = query_tasks!(
+            "SELECT",
+            r#"FROM tasks
+            WHERE deleted_at IS NOT NULL
+            AND EXISTS (SELECT 1 FROM stories s WHERE s.id = tasks.story_id AND s.deleted_at IS NULL)
+            ORDER BY deleted_at DESC LIMIT $1"#,
+            MAX_TRASH,
+        );
+        let tasks = query.fetch_all(self.db_ref()).await?;
+        Ok(tasks)
+    }
+
+    /// Select the most recently deleted files that can be restored on their own.
+    pub async fn list_deleted_files(&self) -> Result<Vec<StoryFile>> {
+        let query = sqlx::query_as!(
+            StoryFile,
+            r#"SELECT f.id, f.story_id, f.storage_id, f.name, f.size, f.content_type, f.seqno,
+            f.created_at, f.updated_at, f.deleted_at
+            FROM story_files f JOIN stories s ON s.id = f.story_id
+            WHERE f.deleted_at IS NOT NULL AND s.deleted_at IS NULL
+            ORDER BY f.deleted_at DESC LIMIT $1"#,
+            MAX_TRASH,
+        );
+        let files = query.fetch_all(self.db_ref()).await?;
+        Ok(files)
+    }
+
+    /// Restore a deleted story, along with the tasks and files deleted with it.
+    pub async fn restore_story(&self, actor: &str, story_id: Uuid) -> Result<Story> {
+        let mut tx = self.db.begin().await?;
+        let before = sqlx::query_as!(
+            Story,
+            r#"SELECT id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at FROM stories
+            WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE"#,
+            story_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("deleted story not found: {story_id}")))?;
+
+        sqlx::query!(
+            "UPDATE tasks SET deleted_at = NULL WHERE story_id = $1 AND deleted_at = $2",
+            story_id,
+            before.deleted_at,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE story_files SET deleted_at = NULL WHERE story_id = $1 AND deleted_at = $2",
+            story_id,
+            before.deleted_at,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let story = sqlx::query_as!(
+            Story,
+            r#"UPDATE stories SET deleted_at = NULL WHERE id = $1
+            RETURNING id, name, description, metadata, seqno, created_at,
+            updated_at, deleted_at"#,
+            story_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::StoryRestored;
+        activity::record(
+            &mut tx,
+            actor,
+            story.id,
+            kind,
+            story.id,
+            Some(&before),
+            Some(&story),
+        )
+        .await?;
+        event::publish(&mut tx, story.id, kind, story.id, &story).await?;
+        tx.commit().await?;
+        Ok(story)
+    }
+
+    /// Restore a deleted task, along with the subtasks that were deleted with it.
+    pub async fn restore_task(&self, actor: &str, task_id: Uuid) -> Result<Task> {
+        let mut tx = self.db.begin().await?;
+        let story_id = sqlx::query_scalar!(
+            "SELECT story_id FROM tasks WHERE id = $1 AND deleted_at IS NOT NULL",
+            task_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("deleted task not found: {task_id}")))?;
+        lock_live_story(&mut tx, story_id).await?;
+        let root = query_tasks!(
+            "SELECT",
+            r#"FROM tasks
+            WHERE id = $1 AND story_id = $2 AND deleted_at IS NOT NULL FOR UPDATE"#,
+            task_id,
+            story_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::conflict(format!("task changed during update: {task_id}")))?;
+        if let Some(parent_id) = root.parent_task_id {
+            ensure_task_not_deleted(&mut tx, parent_id).await?;
+        }
+
+        // Subtasks deleted along with the task share its deletion time
+        let befores = query_tasks!(
+            r#"WITH RECURSIVE subtree AS (
+                SELECT id FROM tasks WHERE id = $1
+                UNION
+                SELECT t.id FROM tasks t JOIN subtree s ON t.parent_task_id = s.id
+                WHERE t.deleted_at = $2
+            )
+            SELECT"#,
+            r#"FROM tasks
+            WHERE id IN (SELECT id FROM subtree) ORDER BY seqno FOR UPDATE"#,
+            task_id,
+            root.deleted_at,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let task_ids: Vec<Uuid> = befores.iter().map(|t| t.id).collect();
+        sqlx::query!(
+            "UPDATE tasks SET deleted_at = NULL WHERE id = ANY($1)",
+            &task_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+        let tasks = query_tasks!(
+            "SELECT",
+            r#"FROM tasks
+            WHERE id = ANY($1) ORDER BY seqno"#,
+            &task_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let kind = EventKind::TaskRestored;
+        for (before, task) in befores.iter().zip(&tasks) {
+            activity::record(
+                &mut tx,
+                actor,
+                task.story_id,
+                kind,
+                task.id,
+                Some(before),
+                Some(task),
+            )
+            .await?;
+            event::publish(&mut tx, task.story_id, kind, task.id, task).await?;
+        }
+        tx.commit().await?;
+        let task = tasks.into_iter().find(|t| t.id == task_id);
+        task.ok_or_else(|| Error::internal(format!("restored task not found: {task_id}")))
+    }
+
+    /// Restore a deleted file.
+    pub async fn restore_file(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        file_id: Uuid,
+    ) -> Result<StoryFile> {
+        let mut tx = self.db.begin().await?;
+        lock_live_story(&mut tx, story_id).await?;
+        let before = sqlx::query_as!(
+            StoryFile,
+            r#"SELECT id, story_id, storage_id, name, size, content_type, seqno, created_at,
+            updated_at, deleted_at
+            FROM story_files WHERE id = $1 AND story_id = $2 AND deleted_at IS NOT NULL
+            FOR UPDATE"#,
+            file_id,
+            story_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("deleted file not found: {file_id}")))?;
+        let file = sqlx::query_as!(
+            StoryFile,
+            r#"UPDATE story_files SET deleted_at = NULL WHERE id = $1
+            RETURNING id, story_id, storage_id, name, size, content_type, seqno, created_at,
+            updated_at, deleted_at"#,
+            file_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let kind = EventKind::FileRestored;
+        activity::record(
+            &mut tx,
+            actor,
+            file.story_id,
+            kind,
+            file.id,
+            Some(&before),
+            Some(&file),
+        )
+        .await?;
+        event::publish(&mut tx, file.story_id, kind, file.id, &file).await?;
+        tx.commit().await?;
+        Ok(file)
+    }
+
+    /// Permanently delete stories, tasks, and files deleted before a cutoff. Returns the storage
+    /// ids of purged files that no other file shares, so their contents can be removed.
+    pub async fn purge_trash(&self, expired_before: DateTime<Utc>) -> Result<Vec<Uuid>> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM tasks WHERE deleted_at < $1
+            OR story_id IN (SELECT id FROM stories WHERE deleted_at < $1)"#,
+            expired_before
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let storage_ids = sqlx::query_scalar!(
+            r#"DELETE FROM story_files WHERE deleted_at < $1
+            OR story_id IN (SELECT id FROM stories WHERE deleted_at < $1)
+            RETURNING storage_id"#,
+            expired_before
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM stories WHERE deleted_at < $1", expired_before)
+            .execute(&mut *tx)
+            .await?;
+        let storage_ids = file::unreferenced(&mut tx, storage_ids).await?;
+
+        tx.commit().await?;
+
+        Ok(storage_ids)
+    }
+}
+
+/// Children can't be restored into a deleted story. Share locks the story, before its tasks or
+/// files, so it can't be trashed while they're restored.
+async fn lock_live_story(conn: &mut PgConnection, story_id: Uuid) -> Result<()> {
+    match task::lock_story(conn, story_id).await {
+        Err(Error::NotFound { .. }) => Err(Error::conflict(format!(
+            "story is deleted: restore {story_id} first"
+        ))),
+        result => result,
+    }
+}
+
+/// Subtasks can't be restored under a deleted task. Share locks the parent, so it can't be
+/// trashed while they're restored.
+async fn ensure_task_not_deleted(conn: &mut PgConnection, task_id: Uuid) -> Result<()> {
+    let deleted = sqlx::query_scalar!(
+        r#"SELECT deleted_at IS NOT NULL AS "deleted!" FROM tasks WHERE id = $1 FOR SHARE"#,
+        task_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if deleted {
+        return Err(Error::conflict(format!(
+            "parent task is deleted: restore {task_id} first"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{FileSort, SortOrder, Status, TaskFilter},
+        repo::tests,
+    };
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+
+        // Create a story with a task and a file
+        let story = repo
+            .create_story("tester", "Garden".into(), None, Default::default())
+            .await
+            .unwrap();
+        let task = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Weed".into(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+        let storage_id = Uuid::new_v4();
+        let file = repo
+            .create_file(
+                "tester",
+                story.id,
+                storage_id,
+                "plot.png".into(),
+                512,
+                "image/png".into(),
+            )
+            .await
+            .unwrap();
+
+        // Delete the task on its own, and it shows up in the trash
+        repo.delete_task("tester", task.id).await.unwrap();
+        assert!(repo.fetch_task(task.id).await.is_err());
+        let tasks = repo.list_deleted_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks[0].deleted_at.is_some());
+
+        // Restore the task
+        let task = repo.restore_task("tester", task.id).await.unwrap();
+        assert!(task.deleted_at.is_none());
+        assert!(repo.restore_task("tester", task.id).await.is_err());
+
+        // Subtasks deleted with their parent come back with it, but not on their own
+        let status = Status::Incomplete.as_ref();
+        let sub = repo
+            .create_task(
+                "tester",
+                story.id,
+                Some(task.id),
+                "Roots".into(),
+                Some(status),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.delete_task("tester", task.id).await.unwrap();
+        assert_eq!(repo.list_deleted_tasks().await.unwrap().len(), 2);
+        let result = repo.restore_task("tester", sub.id).await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        let task = repo.restore_task("tester", task.id).await.unwrap();
+        assert_eq!(task.subtasks, 1);
+        assert!(repo.fetch_task(sub.id).await.is_ok());
+        repo.delete_task("tester", sub.id).await.unwrap();
+
+        // Deleting the story hides its children, which can't be restored on their own
+        repo.delete_story("tester", story.id).await.unwrap();
+        assert!(repo.list_deleted_tasks().await.unwrap().is_empty());
+        assert!(repo.list_deleted_files().await.unwrap().is_empty());
+        assert_eq!(repo.list_deleted_stories().await.unwrap().len(), 1);
+        let result = repo.restore_file("tester", story.id, file.id).await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+
+        // Restoring the story brings back its children
+        repo.restore_story("tester", story.id).await.unwrap();
+        let (_, tasks) = repo
+            .list_tasks(story.id, &TaskFilter::default(), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        let (_, files) = repo
+            .list_files(story.id, FileSort::default(), SortOrder::Asc, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+
+        // Purge only removes expired trash
+        repo.delete_story("tester", story.id).await.unwrap();
+        let cutoff = Utc::now() - chrono::Duration::hours(1);
+        assert!(repo.purge_trash(cutoff).await.unwrap().is_empty());
+        let storage_ids = repo.purge_trash(Utc::now()).await.unwrap();
+        assert_eq!(storage_ids, vec![storage_id]);
+        assert!(repo.list_deleted_stories().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use super::Repo;
+use crate::{
+    domain::{DeliveryStatus, Event, PendingDelivery, Webhook, WebhookDelivery},
+    Error, Result,
+};
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Defines a reasonable limit on the number of webhooks.
+const MAX_WEBHOOKS: i64 = 100;
+
+// Extend repo with queries related to webhooks.
+impl Repo {
+    /// Insert a new webhook
+    pub async fn create_webhook(
+        &self,
+        url: String,
+        secret: String,
+        events: Vec<String>,
+        story_id: Option<Uuid>,
+    ) -> Result<Webhook> {
+        let query = sqlx::query_as!(
+            Webhook,
+            r#"INSERT INTO webhooks (url, secret, events, story_id) VALUES ($1, $2, $3, $4)
+            RETURNING id, url, secret, events, story_id, created_at, updated_at"#,
+            url,
+            secret,
+            &events,
+            story_id,
+        );
+        let webhook = query.fetch_one(self.db_ref()).await?;
+        Ok(webhook)
+    }
+
+    /// Select a webhook by id
+    pub async fn fetch_webhook(&self, webhook_id: Uuid) -> Result<Webhook> {
+        let query = sqlx::query_as!(
+            Webhook,
+            r#"SELECT id, url, secret, events, story_id, created_at, updated_at
+            FROM webhooks WHERE id = $1"#,
+            webhook_id,
+        );
+        match query.fetch_optional(self.db_ref()).await? {
+            Some(webhook) => Ok(webhook),
+            None => Err(Error::not_found(format!("webhook not found: {webhook_id}"))),
+        }
+    }
+
+    /// Select all webhooks
+    pub async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        let query = sqlx::query_as!(
+            Webhook,
+            r#"SELECT id, url, secret, events, story_id, created_at, updated_at
+            FROM webhooks ORDER BY created_at LIMIT $1"#,
+            MAX_WEBHOOKS,
+        );
+        let webhooks = query.fetch_all(self.db_ref()).await?;
+        Ok(webhooks)
+    }
+
+    /// Delete a webhook and its delivery log.
+    pub async fn delete_webhook(&self, webhook_id: Uuid) -> Result<()> {
+        sqlx::query!("DELETE FROM webhooks WHERE id = $1", webhook_id)
+            .execute(self.db_ref())
+            .await?;
+        Ok(())
+    }
+
+    /// Select a page of deliveries for a webhook.
+    pub async fn list_deliveries(
+        &self,
+        webhook_id: Uuid,
+        cursor: i64,
+        limit: i32,
+    ) -> Result<(i64, Vec<WebhookDelivery>)> {
+        let query = sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT id, webhook_id, event_id, event_kind, payload, status, attempts,
+                response_status, last_error, next_attempt_at, seqno, created_at, updated_at
+            FROM webhook_deliveries WHERE webhook_id = $1 AND seqno >= $2
+            ORDER BY seqno LIMIT $3"#,
+            webhook_id,
+            cursor,
+            limit as i64,
+        );
+        let deliveries = query.fetch_all(self.db_ref()).await?;
+        let next_cursor = deliveries.last().map(|d| d.seqno + 1).unwrap_or_default();
+        Ok((next_cursor, deliveries))
+    }
+
+    /// Claim pending deliveries that are due for an attempt. Claimed deliveries are leased until
+    /// a given time, after which they can be claimed again if no result was recorded.
+    pub async fn claim_deliveries(
+        &self,
+        limit: i32,
+        leased_until: DateTime<Utc>,
+    ) -> Result<Vec<PendingDelivery>> {
+        let query = sqlx::query_as!(
+            PendingDelivery,
+            r#"WITH claimed AS (
+                UPDATE webhook_deliveries
+                SET attempts = attempts + 1, next_attempt_at = $1, updated_at = now()
+                WHERE id IN (
+                    SELECT id FROM webhook_deliveries
+                    WHERE status = $2 AND next_attempt_at <= now()
+                    ORDER BY next_attempt_at LIMIT $3
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, webhook_id, event_kind, payload, attempts
+            )
+            SELECT c.id, c.event_kind, c.payload, c.attempts, w.url, w.secret
+            FROM claimed c JOIN webhooks w ON w.id = c.webhook_id"#,
+            leased_until,
+            DeliveryStatus::Pending.to_string(),
+            limit as i64,
+        );
+        let deliveries = query.fetch_all(self.db_ref()).await?;
+        Ok(deliveries)
+    }
+
+    /// Record a successful delivery.
+    pub async fn complete_delivery(&self, delivery_id: Uuid, response_status: i32) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE webhook_deliveries
+            SET status = $1, response_status = $2, last_error = NULL, updated_at = now()
+            WHERE id = $3"#,
+            DeliveryStatus::Delivered.to_string(),
+            response_status,
+            delivery_id,
+        )
+        .execute(self.db_ref())
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed delivery attempt, scheduling a retry when a time is given.
+    pub async fn fail_delivery(
+        &self,
+        delivery_id: Uuid,
+        response_status: Option<i32>,
+        error: String,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let status = match retry_at {
+            Some(_) => DeliveryStatus::Pending,
+            None => DeliveryStatus::Failed,
+        };
+        sqlx::query!(
+            r#"UPDATE webhook_deliveries
+            SET status = $1, response_status = $2, last_error = $3,
+                next_attempt_at = coalesce($4, next_attempt_at), updated_at = now()
+            WHERE id = $5"#,
+            status.to_string(),
+            response_status,
+            error,
+            retry_at,
+            delivery_id,
+        )
+        .execute(self.db_ref())
+        .await?;
+        Ok(())
+    }
+}
+
+/// Queue deliveries of an event to all matching webhooks, as part of a transaction.
+pub(super) async fn enqueue(conn: &mut PgConnection, event: &Event) -> Result<()> {
+    let payload = serde_json::to_value(event)?;
+    sqlx::query!(
+        r#"INSERT INTO webhook_deliveries (webhook_id, event_id, event_kind, payload)
+        SELECT id, $1, $2, $3 FROM webhooks
+        WHERE (story_id IS NULL OR story_id = $4)
+            AND (cardinality(events) = 0 OR $2 = ANY(events))"#,
+        event.id,
+        event.kind,
+        payload,
+        event.story_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{EventKind, Status, TaskChanges},
+        repo::tests,
+    };
+    use chrono::Duration;
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+
+        // Subscribe to completed tasks for a story
+        let story = repo
+            .create_story("tester", "Releases".to_string(), None, Default::default())
+            .await
+            .unwrap();
+        let url = "http://localhost:9000/hooks".to_string();
+        let events = vec![EventKind::TaskUpdated.to_string()];
+        let webhook = repo
+            .create_webhook(url, "secret".into(), events, Some(story.id))
+            .await
+            .unwrap();
+        assert_eq!(repo.list_webhooks().await.unwrap().len(), 1);
+
+        // Only the task update should be queued for delivery
+        let task = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Tag v1.0".into(),
+                Some(Status::Incomplete.as_ref()),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.update_task(
+            "tester",
+            task.id,
+            &TaskChanges {
+                status: Some(Status::Complete.as_ref().into()),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        let (_, deliveries) = repo.list_deliveries(webhook.id, 1, 10).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].event_kind, EventKind::TaskUpdated.to_string());
+
+        // Claim the delivery, which leases it
+        let leased_until = Utc::now() + Duration::minutes(1);
+        let pending = repo.claim_deliveries(10, leased_until).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].attempts, 1);
+        assert!(repo
+            .claim_deliveries(10, leased_until)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Fail the attempt, then retry immediately and succeed
+        let delivery_id = pending[0].id;
+        repo.fail_delivery(delivery_id, Some(500), "oops".into(), Some(Utc::now()))
+            .await
+            .unwrap();
+        let pending = repo.claim_deliveries(10, leased_until).await.unwrap();
+        assert_eq!(pending[0].attempts, 2);
+        repo.complete_delivery(delivery_id, 204).await.unwrap();
+        let (_, deliveries) = repo.list_deliveries(webhook.id, 1, 10).await.unwrap();
+        assert_eq!(deliveries[0].status(), DeliveryStatus::Delivered);
+
+        // Cleanup
+        repo.delete_webhook(webhook.id).await.unwrap();
+        assert!(repo.fetch_webhook(webhook.id).await.is_err());
+        repo.delete_story("tester", story.id).await.unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+use super::{activity, event, Repo};
+use crate::{
+    domain::{EventKind, Transition, Workflow, WorkflowState},
+    Error, Result,
+};
+use sqlx::postgres::PgConnection;
+use uuid::Uuid;
+
+// Extend repo with queries related to story workflows.
+impl Repo {
+    /// Select the workflow of a story, or the default workflow if it hasn't defined one.
+    pub async fn fetch_workflow(&self, story_id: Uuid) -> Result<Workflow> {
+        let mut conn = self.db.acquire().await?;
+        select_workflow(&mut conn, story_id).await
+    }
+
+    /// Replace the workflow of a story. Fails if tasks of the story are in states the new
+    /// workflow leaves out.
+    pub async fn replace_workflow(
+        &self,
+        actor: &str,
+        story_id: Uuid,
+        workflow: Workflow,
+    ) -> Result<Workflow> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            "SELECT id FROM stories WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+            story_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("story not found: {story_id}")))?;
+        let before = select_workflow(&mut tx, story_id).await?;
+
+        let names: Vec<String> = workflow.states.iter().map(|s| s.name.clone()).collect();
+        let missing = sqlx::query_scalar!(
+            r#"SELECT DISTINCT status FROM tasks WHERE story_id = $1 AND status <> ALL($2)
+            ORDER BY status"#,
+            story_id,
+            &names,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if !missing.is_empty() {
+            return Err(Error::conflict(format!(
+                "tasks are in states missing from the workflow: {}",
+                missing.join(", ")
+            )));
+        }
+
+        sqlx::query!("DELETE FROM workflow_states WHERE story_id = $1", story_id)
+            .execute(&mut *tx)
+            .await?;
+        let closed: Vec<bool> = workflow.states.iter().map(|s| s.closed).collect();
+        sqlx::query!(
+            r#"INSERT INTO workflow_states (story_id, name, closed, position)
+            SELECT $1, s.name, s.closed, s.position
+            FROM unnest($2::text[], $3::bool[]) WITH ORDINALITY AS s(name, closed, position)"#,
+            story_id,
+            &names,
+            &closed,
+        )
+        .execute(&mut *tx)
+        .await?;
+        let from: Vec<String> = workflow
+            .transitions
+            .iter()
+            .map(|t| t.from.clone())
+            .collect();
+        let to: Vec<String> = workflow.transitions.iter().map(|t| t.to.clone()).collect();
+        sqlx::query!(
+            r#"INSERT INTO workflow_transitions (story_id, from_state, to_state)
+            SELECT $1, t.from_state, t.to_state
+            FROM unnest($2::text[], $3::text[]) AS t(from_state, to_state)"#,
+            story_id,
+            &from,
+            &to,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let kind = EventKind::WorkflowUpdated;
+        activity::record(
+            &mut tx,
+            actor,
+            story_id,
+            kind,
+            story_id,
+            Some(&before),
+            Some(&workflow),
+        )
+        .await?;
+        event::publish(&mut tx, story_id, kind, story_id, &workflow).await?;
+        tx.commit().await?;
+        Ok(workflow)
+    }
+}
+
+/// Select the workflow of a story on a connection.
+pub(super) async fn select_workflow(conn: &mut PgConnection, story_id: Uuid) -> Result<Workflow> {
+    let states = sqlx::query_as!(
+        WorkflowState,
+        "SELECT name, closed FROM workflow_states WHERE story_id = $1 ORDER BY position",
+        story_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    if states.is_empty() {
+        return Ok(Workflow::default());
+    }
+    let transitions = sqlx::query_as!(
+        Transition,
+        r#"SELECT from_state AS "from", to_state AS "to" FROM workflow_transitions
+        WHERE story_id = $1 ORDER BY from_state, to_state"#,
+        story_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(Workflow {
+        states,
+        transitions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Seek, SortOrder, Status, StoryFilter, StorySort, TaskChanges},
+        repo::tests,
+    };
+
+    use testcontainers::{runners::AsyncRunner, ImageExt};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let image = Postgres::default().with_tag("16-alpine");
+        let container = image.start().await.unwrap();
+        let pool = tests::setup_pg_pool(&container).await;
+        let repo = Repo::new(pool);
+
+        // Stories start with the default workflow
+        let story = repo
+            .create_story("tester", "Release".into(), None, Default::default())
+            .await
+            .unwrap();
+        let workflow = repo.fetch_workflow(story.id).await.unwrap();
+        assert_eq!(workflow, Workflow::default());
+
+        // Replace the workflow
+        let state = |name: &str, closed| WorkflowState {
+            name: name.into(),
+            closed,
+        };
+        let transition = |from: &str, to: &str| Transition {
+            from: from.into(),
+            to: to.into(),
+        };
+        let workflow = Workflow {
+            states: vec![
+                state("todo", false),
+                state("in_progress", false),
+                state("done", true),
+                state("cancelled", true),
+            ],
+            transitions: vec![
+                transition("todo", "in_progress"),
+                transition("in_progress", "done"),
+                transition("todo", "cancelled"),
+            ],
+        };
+        repo.replace_workflow("tester", story.id, workflow.clone())
+            .await
+            .unwrap();
+        let fetched = repo.fetch_workflow(story.id).await.unwrap();
+        assert_eq!(fetched.states, workflow.states);
+        assert_eq!(fetched.transitions.len(), 3);
+
+        // Tasks in closed states don't count as incomplete
+        let task = repo
+            .create_task("tester", story.id, None, "Tag".into(), Some("done"), None)
+            .await
+            .unwrap();
+        let filter = StoryFilter {
+            has_incomplete_tasks: Some(true),
+            ..Default::default()
+        };
+        let (stories, _) = repo
+            .list_stories(
+                &filter,
+                StorySort::default(),
+                SortOrder::Asc,
+                &Seek::First,
+                10,
+            )
+            .await
+            .unwrap();
+        assert!(stories.is_empty());
+
+        // Status changes must be workflow states the workflow allows moving to
+        let result = repo
+            .update_task(
+                "tester",
+                task.id,
+                &TaskChanges {
+                    status: Some("shipped".into()),
+                    ..Default::default()
+                },
+                false,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+        let result = repo
+            .update_task(
+                "tester",
+                task.id,
+                &TaskChanges {
+                    status: Some("in_progress".into()),
+                    ..Default::default()
+                },
+                false,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        let result = repo
+            .create_task(
+                "tester",
+                story.id,
+                None,
+                "Notes".into(),
+                Some("shipped"),
+                None,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+        let notes = repo
+            .create_task("tester", story.id, None, "Notes".into(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(notes.status, "todo");
+        assert_eq!(repo.count_stories(&filter).await.unwrap(), (1, false));
+
+        // Workflows can't drop states that tasks are in
+        let result = repo
+            .replace_workflow("tester", story.id, Workflow::default())
+            .await;
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+
+        // Stories on the default workflow treat complete tasks as closed
+        let other = repo
+            .create_story("tester", "Chores".into(), None, Default::default())
+            .await
+            .unwrap();
+        repo.create_task(
+            "tester",
+            other.id,
+            None,
+            "Dishes".into(),
+            Some(Status::Complete.as_ref()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(repo.count_stories(&filter).await.unwrap(), (1, false));
+
+        // The database falls back to the closed states of the default workflow
+        for state in Workflow::default().states {
+            let closed = sqlx::query_scalar!(
+                r#"SELECT task_is_closed($1, $2) AS "closed!""#,
+                other.id,
+                state.name,
+            )
+            .fetch_one(repo.db_ref())
+            .await
+            .unwrap();
+            assert_eq!(closed, state.closed, "state: {}", state.name);
+        }
+    }
+}
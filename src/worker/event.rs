@@ -0,0 +1,109 @@
+use crate::{
+    config::Config,
+    domain::Event,
+    repo::{Repo, EVENTS_CHANNEL},
+    Error, Result,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::{sync::broadcast, time};
+
+// How often to check for expired events.
+const PURGE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 60);
+
+// How long to wait before listening again after a failure.
+const RETRY_DELAY: time::Duration = time::Duration::from_secs(5);
+
+// The number of missed events to load at a time when the listener reconnects.
+const BACKFILL_PAGE_SIZE: i32 = 1000;
+
+/// Relay events from database notifications to in-process subscribers. Notifications are sent
+/// across all API replicas.
+pub async fn relay(config: Arc<Config>, repo: Arc<Repo>, sender: broadcast::Sender<Arc<Event>>) {
+    // The last relayed event is kept across restarts so events published while the listener was
+    // down are backfilled when it reconnects.
+    let mut last_id = None;
+    loop {
+        if let Err(err) = listen(&config, &repo, &sender, &mut last_id).await {
+            tracing::error!("unable to relay events: {}", err);
+        }
+        time::sleep(RETRY_DELAY).await;
+    }
+}
+
+/// Listen for event notifications, forwarding each event to subscribers.
+async fn listen(
+    config: &Config,
+    repo: &Repo,
+    sender: &broadcast::Sender<Arc<Event>>,
+    last_id: &mut Option<i64>,
+) -> Result<()> {
+    let mut listener = config.db_listener().await?;
+    listener.listen(EVENTS_CHANNEL).await?;
+    let last_id = match last_id {
+        Some(id) => {
+            tracing::warn!("event listener restarted after event {}", id);
+            backfill(repo, sender, id).await?;
+            id
+        }
+        None => last_id.insert(repo.last_event_id().await?),
+    };
+    loop {
+        match listener.try_recv().await? {
+            Some(notification) => {
+                let Ok(event_id) = notification.payload().parse::<i64>() else {
+                    tracing::warn!("invalid event notification: {}", notification.payload());
+                    continue;
+                };
+                let event = match repo.fetch_event(event_id).await {
+                    Ok(event) => event,
+                    Err(Error::NotFound { message }) => {
+                        // The event may have been purged before it was fetched
+                        tracing::warn!("unable to relay event: {}", message);
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+                *last_id = (*last_id).max(event.id);
+                // Sending only fails when there are no subscribers
+                let _ = sender.send(Arc::new(event));
+            }
+            None => {
+                // The connection was re-established, so catch up on any missed events
+                tracing::warn!("event listener reconnected after event {}", last_id);
+                backfill(repo, sender, last_id).await?;
+            }
+        }
+    }
+}
+/// Send every event after the last relayed event to subscribers, a page at a time.
+async fn backfill(
+    repo: &Repo,
+    sender: &broadcast::Sender<Arc<Event>>,
+    last_id: &mut i64,
+) -> Result<()> {
+    loop {
+        let events = repo.list_events_after(*last_id, BACKFILL_PAGE_SIZE).await?;
+        let done = events.len() < BACKFILL_PAGE_SIZE as usize;
+        for event in events {
+            *last_id = (*last_id).max(event.id);
+            let _ = sender.send(Arc::new(event));
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// Periodically delete events older than the configured retention period.
+pub async fn purge(config: Arc<Config>, repo: Arc<Repo>) {
+    let mut interval = time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let expired_before = Utc::now() - Duration::seconds(config.event_retention_secs);
+        match repo.purge_events(expired_before).await {
+            Ok(count) => tracing::debug!("purged {} expired events", count),
+            Err(err) => tracing::error!("unable to purge events: {}", err),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{config::Config, repo::Repo};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::time;
+
+// How often to check for expired idempotency keys.
+const PURGE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// Periodically delete idempotency keys older than the configured TTL.
+pub async fn purge(config: Arc<Config>, repo: Arc<Repo>) {
+    let mut interval = time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let expired_before = Utc::now() - Duration::seconds(config.idempotency_ttl_secs);
+        match repo.purge_idempotency_keys(expired_before).await {
+            Ok(count) => tracing::debug!("purged {} expired idempotency keys", count),
+            Err(err) => tracing::error!("unable to purge idempotency keys: {}", err),
+        }
+    }
+}
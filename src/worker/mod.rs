@@ -0,0 +1,5 @@
+pub mod event;
+pub mod idempotency;
+pub mod reminder;
+pub mod trash;
+pub mod webhook;
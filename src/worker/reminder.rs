@@ -0,0 +1,31 @@
+use crate::{config::Config, repo::Repo};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::time;
+
+// How often to check for tasks coming due.
+const REMIND_INTERVAL: time::Duration = time::Duration::from_secs(60);
+
+// The most reminders to send per check.
+const REMIND_BATCH_SIZE: i32 = 100;
+
+/// Periodically send reminders for open tasks that are due within the configured lead time.
+/// Reminders are published as events, so webhooks subscribed to them are notified.
+pub async fn remind(config: Arc<Config>, repo: Arc<Repo>) {
+    let mut interval = time::interval(REMIND_INTERVAL);
+    loop {
+        interval.tick().await;
+        let due_before = Utc::now() + Duration::seconds(config.reminder_lead_secs);
+        let tasks = match repo.claim_reminders(due_before, REMIND_BATCH_SIZE).await {
+            Ok(tasks) => tasks,
+            Err(err) => {
+                tracing::error!("unable to send reminders: {}", err);
+                continue;
+            }
+        };
+        for task in tasks {
+            let due_at = task.due_at.map(|t| t.to_rfc3339()).unwrap_or_default();
+            tracing::info!("reminder: task {} is due at {}", task.id, due_at);
+        }
+    }
+}
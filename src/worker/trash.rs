@@ -0,0 +1,32 @@
+use crate::{config::Config, driver::storage::Storage, repo::Repo};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::time;
+use uuid::Uuid;
+
+// How often to check for expired trash.
+const PURGE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// Periodically delete trash older than the configured retention period, including file
+/// contents in storage.
+pub async fn purge(config: Arc<Config>, repo: Arc<Repo>, storage: Arc<Box<dyn Storage<Uuid>>>) {
+    let mut interval = time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let expired_before = Utc::now() - Duration::seconds(config.trash_retention_secs);
+        let storage_ids = match repo.purge_trash(expired_before).await {
+            Ok(storage_ids) => storage_ids,
+            Err(err) => {
+                tracing::error!("unable to purge trash: {}", err);
+                continue;
+            }
+        };
+        tracing::debug!("purged {} expired files from trash", storage_ids.len());
+        // Contents are removed only after metadata deletion succeeds
+        for storage_id in storage_ids {
+            if let Err(err) = storage.delete(storage_id).await {
+                tracing::error!("unable to delete {} from storage: {}", storage_id, err);
+            }
+        }
+    }
+}
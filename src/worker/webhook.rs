@@ -0,0 +1,126 @@
+use crate::{
+    config::Config,
+    domain::{Event, PendingDelivery},
+    driver::webhook::WebhookClient,
+    repo::Repo,
+    Result,
+};
+use chrono::{Duration, Utc};
+use futures_util::future;
+use std::sync::Arc;
+use tokio::{sync::broadcast, time};
+
+// How often to check for due deliveries when no events are published.
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(5);
+
+// The max number of deliveries attempted concurrently.
+const BATCH_SIZE: i32 = 50;
+
+// How long to wait for a webhook endpoint to respond.
+const REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+// How long a claimed delivery is leased before it can be claimed again.
+const LEASE_SECS: i64 = 60;
+
+// Retry delay bounds
+const MIN_RETRY_SECS: i64 = 10;
+const MAX_RETRY_SECS: i64 = 60 * 60;
+
+/// Deliver queued events to webhooks, retrying failures with exponential backoff.
+pub async fn deliver(config: Arc<Config>, repo: Arc<Repo>, events: broadcast::Sender<Arc<Event>>) {
+    let client = match WebhookClient::new(REQUEST_TIMEOUT) {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::error!("unable to create webhook client: {}", err);
+            return;
+        }
+    };
+    let mut receiver = events.subscribe();
+    let mut interval = time::interval(POLL_INTERVAL);
+    loop {
+        // Wake up on new events, or periodically for retries
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = receiver.recv() => {}
+        }
+        loop {
+            let leased_until = Utc::now() + Duration::seconds(LEASE_SECS);
+            let deliveries = match repo.claim_deliveries(BATCH_SIZE, leased_until).await {
+                Ok(deliveries) => deliveries,
+                Err(err) => {
+                    tracing::error!("unable to claim webhook deliveries: {}", err);
+                    break;
+                }
+            };
+            if deliveries.is_empty() {
+                break;
+            }
+            let attempts = deliveries
+                .into_iter()
+                .map(|d| attempt(&config, &repo, &client, d));
+            for result in future::join_all(attempts).await {
+                if let Err(err) = result {
+                    tracing::error!("unable to record webhook delivery: {}", err);
+                }
+            }
+        }
+    }
+}
+
+/// Attempt a delivery and record the result.
+async fn attempt(
+    config: &Config,
+    repo: &Repo,
+    client: &WebhookClient,
+    delivery: PendingDelivery,
+) -> Result<()> {
+    let body = serde_json::to_vec(&delivery.payload)?;
+    let result = client
+        .send(
+            &delivery.url,
+            &delivery.secret,
+            delivery.id,
+            &delivery.event_kind,
+            body,
+        )
+        .await;
+    let (response_status, error) = match result {
+        Ok(status) if (200..300).contains(&status) => {
+            return repo.complete_delivery(delivery.id, status as i32).await;
+        }
+        Ok(status) => (
+            Some(status as i32),
+            format!("unexpected response status: {status}"),
+        ),
+        Err(err) => (None, err.to_string()),
+    };
+    let retry_at = if delivery.attempts < config.webhook_max_attempts {
+        Some(Utc::now() + backoff(delivery.attempts))
+    } else {
+        tracing::warn!("giving up on webhook delivery {}", delivery.id);
+        None
+    };
+    repo.fail_delivery(delivery.id, response_status, error, retry_at)
+        .await
+}
+
+/// Calculate the delay before retrying a delivery, doubling with each attempt.
+fn backoff(attempts: i32) -> Duration {
+    let exp = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let secs = MIN_RETRY_SECS.saturating_mul(2_i64.pow(exp));
+    Duration::seconds(secs.min(MAX_RETRY_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_max() {
+        assert_eq!(backoff(1), Duration::seconds(10));
+        assert_eq!(backoff(2), Duration::seconds(20));
+        assert_eq!(backoff(3), Duration::seconds(40));
+        assert_eq!(backoff(10), Duration::seconds(MAX_RETRY_SECS));
+        assert_eq!(backoff(i32::MAX), Duration::seconds(MAX_RETRY_SECS));
+    }
+}